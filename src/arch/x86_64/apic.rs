@@ -0,0 +1,1166 @@
+// Verus-verified version of x86_64/apic.rs
+// 虚拟 LAPIC：IRR 与 APIC 定时器模型
+
+use vstd::prelude::*;
+
+verus! {
+
+pub const APIC_BASE_BSP: u64 = 1 << 8;
+pub const APIC_BASE_EXTD: u64 = 1 << 10;
+pub const APIC_BASE_EN: u64 = 1 << 11;
+/// bit 7:0、bit 9 与 bit 63:52 保留
+pub const APIC_BASE_RESERVED: u64 = 0xfff0_0000_0000_02ff;
+/// 复位值：基址 0xfee00000，全局使能
+pub const APIC_BASE_DEFAULT: u64 = 0xfee0_0000 | APIC_BASE_EN;
+
+/// LAPIC 工作模式（由 IA32_APIC_BASE 的 EN/EXTD 位决定）
+#[derive(Clone, Copy)]
+pub enum ApicMode {
+    Disabled,
+    XApic,
+    X2Apic,
+}
+
+/// 规范函数：IA32_APIC_BASE 对应的模式（EN=0、EXTD=1 为非法组合，返回 None）
+pub open spec fn spec_apic_mode_of(base: u64) -> Option<ApicMode> {
+    let en = base & APIC_BASE_EN != 0;
+    let extd = base & APIC_BASE_EXTD != 0;
+    if en && extd {
+        Some(ApicMode::X2Apic)
+    } else if en {
+        Some(ApicMode::XApic)
+    } else if !extd {
+        Some(ApicMode::Disabled)
+    } else {
+        None
+    }
+}
+
+pub fn apic_mode_of(base: u64) -> (result: Option<ApicMode>)
+    ensures
+        result == spec_apic_mode_of(base),
+{
+    let en = base & APIC_BASE_EN != 0;
+    let extd = base & APIC_BASE_EXTD != 0;
+    if en && extd {
+        Some(ApicMode::X2Apic)
+    } else if en {
+        Some(ApicMode::XApic)
+    } else if !extd {
+        Some(ApicMode::Disabled)
+    } else {
+        None
+    }
+}
+
+/// 规范函数：模式转换表（SDM 10.12.5）
+/// 
+/// x2APIC 不能直接回到 xAPIC，disabled 不能直接进入 x2APIC，其余转换合法
+pub open spec fn spec_apic_transition_legal(from: ApicMode, to: ApicMode) -> bool {
+    &&& !(from is X2Apic && to is XApic)
+    &&& !(from is Disabled && to is X2Apic)
+}
+
+pub fn apic_transition_legal(from: ApicMode, to: ApicMode) -> (result: bool)
+    ensures
+        result == spec_apic_transition_legal(from, to),
+{
+    match (from, to) {
+        (ApicMode::X2Apic, ApicMode::XApic) => false,
+        (ApicMode::Disabled, ApicMode::X2Apic) => false,
+        _ => true,
+    }
+}
+
+/// APIC 定时器模式（LVT timer bits 18:17）
+pub enum TimerMode {
+    OneShot,
+    Periodic,
+    TscDeadline,
+}
+
+/// APIC 定时器状态
+pub struct ApicTimer {
+    pub initial_count: u32,
+    pub current_count: u32,
+    pub divide_config: u32,
+    pub mode: TimerMode,  // 与 LVT timer bits 18:17 一致
+    pub deadline: u64,  // TSC-deadline 模式下的 IA32_TSC_DEADLINE，0 表示未启动
+}
+
+impl ApicTimer {
+    pub fn new() -> (result: Self)
+        ensures
+            result.inv(),
+            result.mode is OneShot,
+    {
+        ApicTimer {
+            initial_count: 0,
+            current_count: 0,
+            divide_config: 0,
+            mode: TimerMode::OneShot,
+            deadline: 0,
+        }
+    }
+    
+    /// 定时器不变式：周期模式下计数不超过初值
+    pub open spec fn inv(&self) -> bool {
+        self.mode is Periodic ==> self.current_count <= self.initial_count
+    }
+    
+    /// 规范函数：经过 elapsed 个（分频后的）计数后定时器到期
+    pub open spec fn spec_expires(&self, elapsed: u32) -> bool {
+        &&& !(self.mode is TscDeadline)
+        &&& self.current_count != 0
+        &&& elapsed >= self.current_count
+    }
+    
+    /// 规范函数：guest TSC 为 tsc 时 TSC-deadline 定时器到期
+    /// 
+    /// 只比较 tsc >= deadline，写入时已经过去的 deadline 在下一次检查时立即到期
+    pub open spec fn spec_deadline_due(&self, tsc: u64) -> bool {
+        &&& self.mode is TscDeadline
+        &&& self.deadline != 0
+        &&& tsc >= self.deadline
+    }
+}
+
+/// 引理：已经过去的 deadline 在下一次检查时到期；写 0 解除定时器，任何 TSC 下都不会到期
+pub proof fn lemma_tsc_deadline_edges(timer: ApicTimer, tsc: u64)
+    ensures
+        timer.mode is TscDeadline && timer.deadline != 0 && timer.deadline <= tsc ==> timer.spec_deadline_due(tsc),
+        timer.deadline == 0 ==> !timer.spec_deadline_due(tsc),
+{
+}
+
+/// 规范函数：vector 的优先级类（高 4 位）高于 TPR 时中断可投递
+pub open spec fn spec_interrupt_deliverable(vector: u8, tpr: u8) -> bool {
+    (vector >> 4u8) > (tpr >> 4u8)
+}
+
+pub fn interrupt_deliverable(vector: u8, tpr: u8) -> (result: bool)
+    ensures
+        result == spec_interrupt_deliverable(vector, tpr),
+{
+    (vector >> 4u8) > (tpr >> 4u8)
+}
+
+/// 规范函数：guest 在 virtual-APIC page 中写入的 TPR
+pub uninterp spec fn spec_virtual_apic_tpr() -> u8;
+
+/// 读 virtual-APIC page 偏移 0x80 处的 TPR（信任边界）
+#[verifier::external_body]
+pub fn read_virtual_apic_tpr() -> (result: u8)
+    ensures
+        result == spec_virtual_apic_tpr(),
+{
+    0  // 内存操作：读 virtual-APIC page + 0x80
+}
+
+/// 规范函数：virtual-APIC page 中偏移 offset 处的 32 位寄存器
+pub uninterp spec fn spec_virtual_apic_reg(offset: u32) -> u32;
+
+/// 读 virtual-APIC page 中的寄存器（信任边界）
+#[verifier::external_body]
+pub fn read_virtual_apic_reg(offset: u32) -> (result: u32)
+    ensures
+        result == spec_virtual_apic_reg(offset),
+{
+    0  // 内存操作：读 virtual-APIC page + offset
+}
+
+/// xAPIC 寄存器偏移
+pub const APIC_REG_TPR: u32 = 0x80;
+pub const APIC_REG_EOI: u32 = 0xb0;
+pub const APIC_REG_SVR: u32 = 0xf0;
+pub const APIC_REG_ICR_LOW: u32 = 0x300;
+pub const APIC_REG_ICR_HIGH: u32 = 0x310;
+/// LVT 寄存器从 timer（0x320）到 error（0x370），间隔 0x10，与 LVT_* 编号顺序一致
+pub const APIC_REG_LVT_TIMER: u32 = 0x320;
+pub const APIC_REG_LVT_ERROR: u32 = 0x370;
+
+/// 规范函数：由 virtual-APIC page 中的 ICR 高低两半拼出 64 位 ICR
+pub open spec fn spec_virtual_apic_icr() -> u64 {
+    ((spec_virtual_apic_reg(APIC_REG_ICR_HIGH) as u64) << 32u64) | (spec_virtual_apic_reg(APIC_REG_ICR_LOW) as u64)
+}
+
+pub fn read_virtual_apic_icr() -> (result: u64)
+    ensures
+        result == spec_virtual_apic_icr(),
+{
+    let high = read_virtual_apic_reg(APIC_REG_ICR_HIGH);
+    let low = read_virtual_apic_reg(APIC_REG_ICR_LOW);
+    ((high as u64) << 32u64) | (low as u64)
+}
+
+/// ICR 投递模式（bits 10:8），3 与 7 保留
+pub const ICR_DELIVERY_FIXED: u64 = 0;
+pub const ICR_DELIVERY_LOWEST: u64 = 1;
+pub const ICR_DELIVERY_SMI: u64 = 2;
+pub const ICR_DELIVERY_NMI: u64 = 4;
+pub const ICR_DELIVERY_INIT: u64 = 5;
+pub const ICR_DELIVERY_STARTUP: u64 = 6;
+/// ICR level（bit 14）与 trigger mode（bit 15）
+pub const ICR_LEVEL_ASSERT: u64 = 1 << 14;
+pub const ICR_TRIGGER_LEVEL: u64 = 1 << 15;
+/// ICR 目标简写（bits 19:18）
+pub const ICR_SHORTHAND_NONE: u64 = 0;
+pub const ICR_SHORTHAND_SELF: u64 = 1;
+pub const ICR_SHORTHAND_ALL_INCLUDING_SELF: u64 = 2;
+pub const ICR_SHORTHAND_ALL_EXCLUDING_SELF: u64 = 3;
+/// fixed 模式下 0-15 号向量保留
+pub const ICR_MIN_FIXED_VECTOR: u8 = 16;
+
+/// 规范函数：ICR 字段（x2APIC 格式，目标 APIC ID 位于 bits 63:32）
+pub open spec fn spec_icr_vector(icr: u64) -> u8 {
+    (icr & 0xff) as u8
+}
+
+pub open spec fn spec_icr_delivery_mode(icr: u64) -> u64 {
+    (icr >> 8u64) & 0x7
+}
+
+pub open spec fn spec_icr_shorthand(icr: u64) -> u64 {
+    (icr >> 18u64) & 0x3
+}
+
+pub open spec fn spec_icr_dest(icr: u64) -> u64 {
+    icr >> 32u64
+}
+
+/// 规范函数：ICR 投递模式带向量（fixed 与 lowest-priority）
+pub open spec fn spec_icr_is_vectored(icr: u64) -> bool {
+    spec_icr_delivery_mode(icr) == ICR_DELIVERY_FIXED || spec_icr_delivery_mode(icr) == ICR_DELIVERY_LOWEST
+}
+
+/// 规范函数：ICR 可以发送
+/// 
+/// 带向量的模式下向量不能小于 16；模型不支持 SMM，SMI 与保留的投递模式都不能发送
+pub open spec fn spec_icr_send_valid(icr: u64) -> bool {
+    let mode = spec_icr_delivery_mode(icr);
+    &&& spec_icr_is_vectored(icr) || mode == ICR_DELIVERY_NMI || mode == ICR_DELIVERY_INIT
+        || mode == ICR_DELIVERY_STARTUP
+    &&& spec_icr_is_vectored(icr) ==> spec_icr_vector(icr) >= ICR_MIN_FIXED_VECTOR
+}
+
+/// 规范函数：INIT level de-assert（level 触发且 level 清零），不投递任何东西
+pub open spec fn spec_icr_init_deassert(icr: u64) -> bool {
+    &&& spec_icr_delivery_mode(icr) == ICR_DELIVERY_INIT
+    &&& icr & ICR_TRIGGER_LEVEL != 0
+    &&& icr & ICR_LEVEL_ASSERT == 0
+}
+
+/// 规范函数：xAPIC 格式的 ICR（目标位于 bits 63:56）换成 x2APIC 格式
+pub open spec fn spec_xapic_icr_to_x2apic(icr: u64) -> u64 {
+    (icr & 0xffff_ffff) | ((icr >> 56u64) << 32u64)
+}
+
+pub fn icr_vector(icr: u64) -> (result: u8)
+    ensures
+        result == spec_icr_vector(icr),
+{
+    (icr & 0xff) as u8
+}
+
+pub fn icr_delivery_mode(icr: u64) -> (result: u64)
+    ensures
+        result == spec_icr_delivery_mode(icr),
+{
+    (icr >> 8u64) & 0x7
+}
+
+pub fn icr_shorthand(icr: u64) -> (result: u64)
+    ensures
+        result == spec_icr_shorthand(icr),
+{
+    (icr >> 18u64) & 0x3
+}
+
+pub fn icr_dest(icr: u64) -> (result: u64)
+    ensures
+        result == spec_icr_dest(icr),
+{
+    icr >> 32u64
+}
+
+pub fn icr_is_vectored(icr: u64) -> (result: bool)
+    ensures
+        result == spec_icr_is_vectored(icr),
+{
+    let mode = icr_delivery_mode(icr);
+    mode == ICR_DELIVERY_FIXED || mode == ICR_DELIVERY_LOWEST
+}
+
+pub fn icr_send_valid(icr: u64) -> (result: bool)
+    ensures
+        result == spec_icr_send_valid(icr),
+{
+    let mode = icr_delivery_mode(icr);
+    if icr_is_vectored(icr) {
+        icr_vector(icr) >= ICR_MIN_FIXED_VECTOR
+    } else {
+        mode == ICR_DELIVERY_NMI || mode == ICR_DELIVERY_INIT || mode == ICR_DELIVERY_STARTUP
+    }
+}
+
+pub fn icr_init_deassert(icr: u64) -> (result: bool)
+    ensures
+        result == spec_icr_init_deassert(icr),
+{
+    icr_delivery_mode(icr) == ICR_DELIVERY_INIT && icr & ICR_TRIGGER_LEVEL != 0 && icr & ICR_LEVEL_ASSERT == 0
+}
+
+pub fn xapic_icr_to_x2apic(icr: u64) -> (result: u64)
+    ensures
+        result == spec_xapic_icr_to_x2apic(icr),
+{
+    (icr & 0xffff_ffff) | ((icr >> 56u64) << 32u64)
+}
+
+/// 规范函数：ICR 是否以 target 为目标（APIC ID 与 CPU ID 恒等映射）
+pub open spec fn spec_icr_targets(icr: u64, sender: usize, target: usize) -> bool {
+    let shorthand = spec_icr_shorthand(icr);
+    if shorthand == ICR_SHORTHAND_NONE {
+        spec_icr_dest(icr) == target as u64
+    } else if shorthand == ICR_SHORTHAND_SELF {
+        target == sender
+    } else if shorthand == ICR_SHORTHAND_ALL_INCLUDING_SELF {
+        true
+    } else {
+        target != sender
+    }
+}
+
+pub fn icr_targets(icr: u64, sender: usize, target: usize) -> (result: bool)
+    ensures
+        result == spec_icr_targets(icr, sender, target),
+{
+    let shorthand = icr_shorthand(icr);
+    if shorthand == ICR_SHORTHAND_NONE {
+        icr_dest(icr) == target as u64
+    } else if shorthand == ICR_SHORTHAND_SELF {
+        target == sender
+    } else if shorthand == ICR_SHORTHAND_ALL_INCLUDING_SELF {
+        true
+    } else {
+        target != sender
+    }
+}
+
+/// 引理：all-excluding-self 简写不会投递给发送者自己
+pub proof fn lemma_all_excluding_self_skips_sender(icr: u64, sender: usize)
+    requires
+        spec_icr_shorthand(icr) == ICR_SHORTHAND_ALL_EXCLUDING_SELF,
+    ensures
+        !spec_icr_targets(icr, sender, sender),
+{
+}
+
+/// SVR：bits 7:0 为伪中断向量，bit 8 为 APIC 软件使能
+/// 
+/// 模型按 P6 之后的处理器：向量的低 4 位可写（早期处理器固定为 1）；
+/// focus processor checking 与 EOI-broadcast suppression 不建模，按保留位处理
+pub const SVR_VECTOR_MASK: u32 = 0xff;
+pub const SVR_SOFTWARE_ENABLE: u32 = 1 << 8;
+pub const SVR_WRITABLE: u32 = SVR_VECTOR_MASK | SVR_SOFTWARE_ENABLE;
+/// x2APIC 下写入这些位注入 #GP
+pub const SVR_RESERVED: u64 = !(SVR_WRITABLE as u64);
+/// 复位值：向量 0xff，软件关闭
+pub const SVR_RESET: u32 = 0xff;
+
+/// LVT 项编号（x2APIC MSR 0x832 起的顺序）
+pub const LVT_TIMER: usize = 0;
+pub const LVT_THERMAL: usize = 1;
+pub const LVT_PERF: usize = 2;
+pub const LVT_LINT0: usize = 3;
+pub const LVT_LINT1: usize = 4;
+pub const LVT_ERROR: usize = 5;
+pub const LVT_COUNT: usize = 6;
+
+/// LVT 项：bits 7:0 向量，bits 10:8 投递模式，bit 16 屏蔽位
+pub const LVT_VECTOR_MASK: u32 = 0xff;
+pub const LVT_MASKED: u32 = 1 << 16;
+/// LVT 投递模式编码
+pub const LVT_DELIVERY_FIXED: u32 = 0;
+pub const LVT_DELIVERY_SMI: u32 = 2;
+pub const LVT_DELIVERY_NMI: u32 = 4;
+pub const LVT_DELIVERY_INIT: u32 = 5;
+pub const LVT_DELIVERY_EXTINT: u32 = 7;
+/// 各项的可写位：timer 另有模式位 18:17，LINT 另有极性（bit 13）与触发模式（bit 15），
+/// timer 与 error 没有投递模式字段；delivery status、remote IRR 等只读位不建模
+pub const LVT_TIMER_WRITABLE: u32 = 0x7_00ff;
+pub const LVT_ERROR_WRITABLE: u32 = 0x1_00ff;
+pub const LVT_THERMAL_WRITABLE: u32 = 0x1_07ff;
+pub const LVT_LINT_WRITABLE: u32 = 0x1_a7ff;
+/// 复位值：向量 0，屏蔽，timer 为 one-shot 模式
+pub const LVT_RESET: u32 = LVT_MASKED;
+
+/// 一个 LVT 项的原始值
+#[derive(Clone, Copy)]
+pub struct LvtEntry {
+    pub raw: u32,
+}
+
+impl LvtEntry {
+    pub open spec fn spec_vector(&self) -> u8 {
+        (self.raw & LVT_VECTOR_MASK) as u8
+    }
+    
+    pub fn vector(&self) -> (result: u8)
+        ensures
+            result == self.spec_vector(),
+    {
+        (self.raw & LVT_VECTOR_MASK) as u8
+    }
+    
+    pub open spec fn spec_masked(&self) -> bool {
+        self.raw & LVT_MASKED != 0
+    }
+    
+    pub fn masked(&self) -> (result: bool)
+        ensures
+            result == self.spec_masked(),
+    {
+        self.raw & LVT_MASKED != 0
+    }
+    
+    pub open spec fn spec_delivery_mode(&self) -> u32 {
+        (self.raw >> 8u32) & 0x7
+    }
+    
+    pub fn delivery_mode(&self) -> (result: u32)
+        ensures
+            result == self.spec_delivery_mode(),
+    {
+        (self.raw >> 8u32) & 0x7
+    }
+    
+    /// 规范函数：LVT timer 的定时器模式（bits 18:17），编码 3 保留
+    pub open spec fn spec_timer_mode(&self) -> Option<TimerMode> {
+        let bits = (self.raw >> 17u32) & 0x3;
+        if bits == 0 {
+            Some(TimerMode::OneShot)
+        } else if bits == 1 {
+            Some(TimerMode::Periodic)
+        } else if bits == 2 {
+            Some(TimerMode::TscDeadline)
+        } else {
+            None
+        }
+    }
+    
+    pub fn timer_mode(&self) -> (result: Option<TimerMode>)
+        ensures
+            result == self.spec_timer_mode(),
+    {
+        let bits = (self.raw >> 17u32) & 0x3;
+        if bits == 0 {
+            Some(TimerMode::OneShot)
+        } else if bits == 1 {
+            Some(TimerMode::Periodic)
+        } else if bits == 2 {
+            Some(TimerMode::TscDeadline)
+        } else {
+            None
+        }
+    }
+}
+
+/// 规范函数：LVT 项 idx 的可写位
+pub open spec fn spec_lvt_writable(idx: usize) -> u32 {
+    if idx == LVT_TIMER {
+        LVT_TIMER_WRITABLE
+    } else if idx == LVT_ERROR {
+        LVT_ERROR_WRITABLE
+    } else if idx == LVT_LINT0 || idx == LVT_LINT1 {
+        LVT_LINT_WRITABLE
+    } else {
+        LVT_THERMAL_WRITABLE
+    }
+}
+
+pub fn lvt_writable(idx: usize) -> (result: u32)
+    ensures
+        result == spec_lvt_writable(idx),
+{
+    if idx == LVT_TIMER {
+        LVT_TIMER_WRITABLE
+    } else if idx == LVT_ERROR {
+        LVT_ERROR_WRITABLE
+    } else if idx == LVT_LINT0 || idx == LVT_LINT1 {
+        LVT_LINT_WRITABLE
+    } else {
+        LVT_THERMAL_WRITABLE
+    }
+}
+
+/// 规范函数：LVT 项 idx 支持的投递模式（SDM 10.5.1）
+/// 
+/// thermal、performance 支持 fixed、SMI、NMI；LINT0 / LINT1 另支持 INIT 与 ExtINT；
+/// timer 与 error 只能 fixed
+pub open spec fn spec_lvt_delivery_mode_legal(idx: usize, mode: u32) -> bool {
+    if idx == LVT_THERMAL || idx == LVT_PERF {
+        mode == LVT_DELIVERY_FIXED || mode == LVT_DELIVERY_SMI || mode == LVT_DELIVERY_NMI
+    } else if idx == LVT_LINT0 || idx == LVT_LINT1 {
+        mode == LVT_DELIVERY_FIXED || mode == LVT_DELIVERY_SMI || mode == LVT_DELIVERY_NMI
+            || mode == LVT_DELIVERY_INIT || mode == LVT_DELIVERY_EXTINT
+    } else {
+        mode == LVT_DELIVERY_FIXED
+    }
+}
+
+pub fn lvt_delivery_mode_legal(idx: usize, mode: u32) -> (result: bool)
+    ensures
+        result == spec_lvt_delivery_mode_legal(idx, mode),
+{
+    if idx == LVT_THERMAL || idx == LVT_PERF {
+        mode == LVT_DELIVERY_FIXED || mode == LVT_DELIVERY_SMI || mode == LVT_DELIVERY_NMI
+    } else if idx == LVT_LINT0 || idx == LVT_LINT1 {
+        mode == LVT_DELIVERY_FIXED || mode == LVT_DELIVERY_SMI || mode == LVT_DELIVERY_NMI
+            || mode == LVT_DELIVERY_INIT || mode == LVT_DELIVERY_EXTINT
+    } else {
+        mode == LVT_DELIVERY_FIXED
+    }
+}
+
+/// 规范函数：写 LVT 项 idx 是否合法（x2APIC 下不合法时注入 #GP）
+/// 
+/// 不能写保留位，投递模式必须是该项支持的编码，timer 模式不能是保留的编码 3
+pub open spec fn spec_lvt_write_valid(idx: usize, val: u64) -> bool {
+    let entry = LvtEntry { raw: val as u32 };
+    &&& val & !(spec_lvt_writable(idx) as u64) == 0
+    &&& spec_lvt_delivery_mode_legal(idx, entry.spec_delivery_mode())
+    &&& idx == LVT_TIMER ==> entry.spec_timer_mode() is Some
+}
+
+/// 中断向量的个数（向量号为 u8）
+pub const NUM_VECTORS: usize = 256;
+
+/// 全部中断向量的位图，words[i] 的 bit j 对应向量 64 * i + j
+/// 
+/// 规范层面经 view 看作长度 NUM_VECTORS 的 Seq<bool>，bitmap@[v] 即向量 v 是否置位
+pub struct VectorBitmap {
+    words: [u64; 4],
+}
+
+impl View for VectorBitmap {
+    type V = Seq<bool>;
+    
+    closed spec fn view(&self) -> Seq<bool> {
+        Seq::new(NUM_VECTORS as nat, |v: int| (self.words[v / 64] >> ((v % 64) as u64)) & 1u64 == 1u64)
+    }
+}
+
+/// 引理：置位或清除字中的第 b 位只改变该位
+proof fn lemma_word_bit_update(w: u64, b: u64, c: u64)
+    requires
+        b < 64,
+        c < 64,
+    ensures
+        ((w | (1u64 << b)) >> c) & 1u64 == (if c == b { 1u64 } else { (w >> c) & 1u64 }),
+        ((w & !(1u64 << b)) >> c) & 1u64 == (if c == b { 0u64 } else { (w >> c) & 1u64 }),
+        (0u64 >> c) & 1u64 == 0u64,
+{
+    assert(((w | (1u64 << b)) >> c) & 1u64 == (if c == b { 1u64 } else { (w >> c) & 1u64 })) by (bit_vector)
+        requires b < 64, c < 64;
+    assert(((w & !(1u64 << b)) >> c) & 1u64 == (if c == b { 0u64 } else { (w >> c) & 1u64 })) by (bit_vector)
+        requires b < 64, c < 64;
+    assert((0u64 >> c) & 1u64 == 0u64) by (bit_vector);
+}
+
+impl VectorBitmap {
+    /// 所有向量都清零
+    pub fn new() -> (result: Self)
+        ensures
+            forall|v: int| 0 <= v < NUM_VECTORS ==> !#[trigger] result@[v],
+    {
+        let result = VectorBitmap { words: [0u64; 4] };
+        proof {
+            assert forall|v: int| 0 <= v < NUM_VECTORS implies !#[trigger] result@[v] by {
+                lemma_word_bit_update(0, 0, (v % 64) as u64);
+            }
+        }
+        result
+    }
+    
+    /// 向量 v 是否置位
+    pub fn test(&self, v: usize) -> (result: bool)
+        requires
+            v < NUM_VECTORS,
+        ensures
+            result == self@[v as int],
+    {
+        (self.words[v / 64] >> ((v % 64) as u64)) & 1u64 == 1u64
+    }
+    
+    /// 把向量 v 置为 val，其余向量不变
+    pub fn set(&mut self, v: usize, val: bool)
+        requires
+            v < NUM_VECTORS,
+        ensures
+            self@ == old(self)@.update(v as int, val),
+    {
+        let i = v / 64;
+        let b = (v % 64) as u64;
+        let w = self.words[i];
+        let nw = if val { w | (1u64 << b) } else { w & !(1u64 << b) };
+        self.words.set(i, nw);
+        proof {
+            assert forall|u: int| 0 <= u < NUM_VECTORS implies #[trigger] self@[u] == old(self)@.update(v as int, val)[u] by {
+                lemma_word_bit_update(w, b, (u % 64) as u64);
+            }
+            assert(self@ =~= old(self)@.update(v as int, val));
+        }
+    }
+}
+
+/// 虚拟 LAPIC（本地 APIC）
+pub struct VirtLocalApic {
+    pub phys_lapic: PhysLocalApic,
+    pub irr: VectorBitmap,  // Interrupt Request Register
+    pub isr: VectorBitmap,  // In-Service Register
+    pub timer: ApicTimer,
+    pub apic_base: u64,    // IA32_APIC_BASE
+    pub mode: ApicMode,
+    pub tpr: u8,           // Task Priority Register（TPR shadow 下来自 virtual-APIC page）
+    pub svr: u32,          // Spurious-Interrupt Vector Register
+    pub lvt: [u32; 6],     // Local Vector Table，按 LVT_* 编号
+}
+
+impl VirtLocalApic {
+    /// 复位状态；BSP 的 IA32_APIC_BASE 置位 BSP 位，此后该位只读
+    pub fn new(is_bsp: bool) -> (result: Self)
+        ensures
+            result.inv(),
+            (result.apic_base & APIC_BASE_BSP != 0) == is_bsp,
+    {
+        proof {
+            assert((APIC_BASE_DEFAULT | APIC_BASE_BSP) & APIC_BASE_EN != 0) by (bit_vector);
+            assert((APIC_BASE_DEFAULT | APIC_BASE_BSP) & APIC_BASE_EXTD == 0) by (bit_vector);
+            assert((APIC_BASE_DEFAULT | APIC_BASE_BSP) & APIC_BASE_BSP != 0) by (bit_vector);
+            assert(APIC_BASE_DEFAULT & APIC_BASE_BSP == 0) by (bit_vector);
+        }
+        let apic = VirtLocalApic {
+            phys_lapic: PhysLocalApic,
+            irr: VectorBitmap::new(),
+            isr: VectorBitmap::new(),
+            timer: ApicTimer::new(),
+            apic_base: if is_bsp { APIC_BASE_DEFAULT | APIC_BASE_BSP } else { APIC_BASE_DEFAULT },
+            mode: ApicMode::XApic,
+            tpr: 0,
+            svr: SVR_RESET,
+            lvt: [LVT_RESET, LVT_RESET, LVT_RESET, LVT_RESET, LVT_RESET, LVT_RESET],
+        };
+        proof {
+            assert((0x1_0000u32 >> 17u32) & 0x3 == 0) by (bit_vector);
+        }
+        apic
+    }
+    
+    /// 规范函数：APIC 已软件使能（SVR bit 8）；关闭时不投递任何中断
+    pub open spec fn spec_software_enabled(&self) -> bool {
+        self.svr & SVR_SOFTWARE_ENABLE != 0
+    }
+    
+    pub fn software_enabled(&self) -> (result: bool)
+        ensures
+            result == self.spec_software_enabled(),
+    {
+        self.svr & SVR_SOFTWARE_ENABLE != 0
+    }
+    
+    pub open spec fn spec_spurious_vector(&self) -> u8 {
+        (self.svr & SVR_VECTOR_MASK) as u8
+    }
+    
+    pub fn spurious_vector(&self) -> (result: u8)
+        ensures
+            result == self.spec_spurious_vector(),
+    {
+        (self.svr & SVR_VECTOR_MASK) as u8
+    }
+    
+    /// 写 SVR：只保留可写位（xAPIC MMIO 写保留位被忽略）
+    /// 
+    /// 软件关闭期间所有 LVT 项按屏蔽处理（见 spec_lvt_delivers），LVT 寄存器本身不改写
+    pub fn write_svr(&mut self, val: u32)
+        requires
+            old(self).inv(),
+        ensures
+            self.inv(),
+            self.svr == val & SVR_WRITABLE,
+            *self == (VirtLocalApic { svr: self.svr, ..*old(self) }),
+    {
+        self.svr = val & SVR_WRITABLE;
+    }
+    
+    pub open spec fn spec_lvt_entry(&self, idx: int) -> LvtEntry {
+        LvtEntry { raw: self.lvt@[idx] }
+    }
+    
+    pub fn lvt_entry(&self, idx: usize) -> (result: LvtEntry)
+        requires
+            idx < LVT_COUNT,
+        ensures
+            result == self.spec_lvt_entry(idx as int),
+    {
+        LvtEntry { raw: self.lvt[idx] }
+    }
+    
+    /// 规范函数：LVT 项 idx 的中断源能置位 IRR（APIC 软件使能且该项未屏蔽）
+    pub open spec fn spec_lvt_delivers(&self, idx: int) -> bool {
+        &&& self.spec_software_enabled()
+        &&& !self.spec_lvt_entry(idx).spec_masked()
+    }
+    
+    pub fn lvt_delivers(&self, idx: usize) -> (result: bool)
+        requires
+            idx < LVT_COUNT,
+        ensures
+            result == self.spec_lvt_delivers(idx as int),
+    {
+        self.software_enabled() && !self.lvt_entry(idx).masked()
+    }
+    
+    /// 写 LVT 项 idx，返回 false 表示非法写入（状态不变）
+    /// 
+    /// 写 timer 项同时切换定时器模式；模式改变时计数停止，deadline 只在 TSC-deadline 模式下生效，保持不变
+    pub fn write_lvt(&mut self, idx: usize, val: u64) -> (ok: bool)
+        requires
+            old(self).inv(),
+            idx < LVT_COUNT,
+        ensures
+            self.inv(),
+            ok == spec_lvt_write_valid(idx, val),
+            !ok ==> *self == *old(self),
+            ok ==> (val as u32) as u64 == val,
+            ok ==> self.lvt@ == old(self).lvt@.update(idx as int, val as u32),
+            ok && idx != LVT_TIMER ==> *self == (VirtLocalApic { lvt: self.lvt, ..*old(self) }),
+            ok && idx == LVT_TIMER ==> {
+                &&& *self == (VirtLocalApic { lvt: self.lvt, timer: self.timer, ..*old(self) })
+                &&& self.timer == (ApicTimer {
+                    mode: self.timer.mode,
+                    current_count: self.timer.current_count,
+                    ..old(self).timer
+                })
+                &&& Some(self.timer.mode) == (LvtEntry { raw: val as u32 }).spec_timer_mode()
+            },
+    {
+        let writable = lvt_writable(idx);
+        if val & !(writable as u64) != 0 {
+            return false;
+        }
+        proof {
+            assert(val & !(writable as u64) == 0 ==> (val as u32) as u64 == val) by (bit_vector);
+        }
+        let entry = LvtEntry { raw: val as u32 };
+        if !lvt_delivery_mode_legal(idx, entry.delivery_mode()) {
+            return false;
+        }
+        if idx == LVT_TIMER {
+            let mode = match entry.timer_mode() {
+                Some(mode) => mode,
+                None => return false,
+            };
+            // 不变式保证 lvt[LVT_TIMER] 的模式位与 timer.mode 一致
+            let changed = (entry.raw >> 17u32) & 0x3 != (self.lvt[LVT_TIMER] >> 17u32) & 0x3;
+            self.timer.mode = mode;
+            if changed {
+                self.timer.current_count = 0;
+            }
+        }
+        self.lvt.set(idx, entry.raw);
+        true
+    }
+    
+    /// APIC 错误（如 xAPIC 下发送非法向量的 IPI）：经 LVT error 投递，屏蔽时不产生中断
+    /// 
+    /// ESR 不建模
+    pub fn signal_error(&mut self)
+        requires
+            old(self).inv(),
+        ensures
+            self.inv(),
+            *self == (VirtLocalApic { irr: self.irr, ..*old(self) }),
+            old(self).spec_lvt_delivers(LVT_ERROR as int) ==> self.irr@
+                == old(self).irr@.update(old(self).spec_lvt_entry(LVT_ERROR as int).spec_vector() as int, true),
+            !old(self).spec_lvt_delivers(LVT_ERROR as int) ==> self.irr@ == old(self).irr@,
+    {
+        if self.lvt_delivers(LVT_ERROR) {
+            let vector = self.lvt_entry(LVT_ERROR).vector();
+            self.set_irr(vector);
+        }
+    }
+    
+    /// 虚拟 LAPIC 不变式
+    pub open spec fn inv(&self) -> bool {
+        &&& self.timer.inv()
+        &&& spec_apic_mode_of(self.apic_base) == Some(self.mode)  // 从不处于非法模式
+        &&& self.spec_lvt_entry(LVT_TIMER as int).spec_timer_mode() == Some(self.timer.mode)
+    }
+    
+    /// 规范函数：写 IA32_APIC_BASE 是否合法（不合法时注入 #GP）
+    pub open spec fn spec_apic_base_write_valid(&self, val: u64) -> bool {
+        &&& val & APIC_BASE_RESERVED == 0
+        &&& spec_apic_mode_of(val) is Some
+        &&& spec_apic_transition_legal(self.mode, spec_apic_mode_of(val).unwrap())
+    }
+    
+    /// 规范函数：接受写入后的 IA32_APIC_BASE
+    /// 
+    /// BSP 位只读，保持复位时的值，写入值中的该位被忽略
+    pub open spec fn spec_apic_base_written(&self, val: u64) -> u64 {
+        (val & !APIC_BASE_BSP) | (self.apic_base & APIC_BASE_BSP)
+    }
+    
+    /// 写 IA32_APIC_BASE，返回 false 表示非法写入（状态不变）
+    pub fn write_apic_base(&mut self, val: u64) -> (ok: bool)
+        requires
+            old(self).inv(),
+        ensures
+            self.inv(),
+            ok == old(self).spec_apic_base_write_valid(val),
+            ok ==> self.apic_base == old(self).spec_apic_base_written(val),
+            ok ==> Some(self.mode) == spec_apic_mode_of(val),
+            !ok ==> *self == *old(self),
+            self.apic_base & APIC_BASE_BSP == old(self).apic_base & APIC_BASE_BSP,
+            self.irr == old(self).irr,
+            self.timer == old(self).timer,
+    {
+        if val & APIC_BASE_RESERVED != 0 {
+            return false;
+        }
+        let new_mode = match apic_mode_of(val) {
+            Some(mode) => mode,
+            None => return false,
+        };
+        if !apic_transition_legal(self.mode, new_mode) {
+            return false;
+        }
+        let base = self.apic_base;
+        proof {
+            // 屏蔽 BSP 位不影响决定模式的 EN / EXTD 位
+            assert(((val & !APIC_BASE_BSP) | (base & APIC_BASE_BSP)) & APIC_BASE_EN == val & APIC_BASE_EN)
+                by (bit_vector);
+            assert(((val & !APIC_BASE_BSP) | (base & APIC_BASE_BSP)) & APIC_BASE_EXTD == val & APIC_BASE_EXTD)
+                by (bit_vector);
+            assert(((val & !APIC_BASE_BSP) | (base & APIC_BASE_BSP)) & APIC_BASE_BSP == base & APIC_BASE_BSP)
+                by (bit_vector);
+        }
+        self.apic_base = (val & !APIC_BASE_BSP) | (base & APIC_BASE_BSP);
+        self.mode = new_mode;
+        true
+    }
+    
+    /// 规范函数：vector 是 IRR 中优先级最高（编号最大）的请求
+    pub open spec fn is_highest_pending(&self, vector: u8) -> bool {
+        &&& self.irr@[vector as int]
+        &&& forall|u: int| vector < u < NUM_VECTORS ==> !self.irr@[u]
+    }
+    
+    /// IRR 中编号最大的请求向量；APIC 软件关闭时不投递，返回 None
+    pub fn highest_pending(&self) -> (result: Option<u8>)
+        ensures
+            result is Some ==> self.spec_software_enabled() && self.is_highest_pending(result.unwrap()),
+            result is None ==> !self.spec_software_enabled() || forall|u: int| 0 <= u < NUM_VECTORS ==> !self.irr@[u],
+    {
+        if !self.software_enabled() {
+            return None;
+        }
+        let mut i: usize = NUM_VECTORS;
+        while i > 0
+            invariant
+                i <= NUM_VECTORS,
+                forall|u: int| i <= u < NUM_VECTORS ==> !self.irr@[u],
+            decreases i,
+        {
+            i = i - 1;
+            if self.irr.test(i) {
+                return Some(i as u8);
+            }
+        }
+        None
+    }
+    
+    /// 受理中断：从 IRR 移到 ISR
+    pub fn accept(&mut self, vector: u8)
+        requires
+            old(self).inv(),
+        ensures
+            self.inv(),
+            self.irr@ == old(self).irr@.update(vector as int, false),
+            self.isr@ == old(self).isr@.update(vector as int, true),
+            self.timer == old(self).timer,
+            self.apic_base == old(self).apic_base,
+            self.mode == old(self).mode,
+            self.tpr == old(self).tpr,
+    {
+        self.irr.set(vector as usize, false);
+        self.isr.set(vector as usize, true);
+    }
+    
+    /// 规范函数：vector 是 ISR 中优先级最高（编号最大）的在服务向量
+    pub open spec fn is_highest_in_service(&self, vector: u8) -> bool {
+        &&& self.isr@[vector as int]
+        &&& forall|u: int| vector < u < NUM_VECTORS ==> !self.isr@[u]
+    }
+    
+    /// ISR 中编号最大的在服务向量
+    pub fn highest_in_service(&self) -> (result: Option<u8>)
+        ensures
+            result is Some ==> self.is_highest_in_service(result.unwrap()),
+            result is None ==> forall|u: int| 0 <= u < NUM_VECTORS ==> !self.isr@[u],
+    {
+        let mut i: usize = NUM_VECTORS;
+        while i > 0
+            invariant
+                i <= NUM_VECTORS,
+                forall|u: int| i <= u < NUM_VECTORS ==> !self.isr@[u],
+            decreases i,
+        {
+            i = i - 1;
+            if self.isr.test(i) {
+                return Some(i as u8);
+            }
+        }
+        None
+    }
+    
+    /// 规范函数：vector 的优先级类高于所有在服务向量（PPR 的 ISRV 部分不屏蔽它）
+    pub open spec fn above_in_service(&self, vector: u8) -> bool {
+        forall|u: int| 0 <= u < NUM_VECTORS && #[trigger] self.isr@[u] ==> u / 16 < vector as int / 16
+    }
+    
+    pub fn is_above_in_service(&self, vector: u8) -> (result: bool)
+        ensures
+            result == self.above_in_service(vector),
+    {
+        match self.highest_in_service() {
+            Some(h) => {
+                assert(self.isr@[h as int]);
+                vector / 16 > h / 16
+            },
+            None => true,
+        }
+    }
+    
+    /// 结束 vector 的服务：从 ISR 中清除，其余状态不变
+    pub fn complete(&mut self, vector: u8)
+        requires
+            old(self).inv(),
+        ensures
+            self.inv(),
+            *self == (VirtLocalApic { isr: self.isr, ..*old(self) }),
+            self.isr@ == old(self).isr@.update(vector as int, false),
+    {
+        self.isr.set(vector as usize, false);
+    }
+    
+    /// 写 EOI 寄存器：结束 ISR 中优先级最高的中断，ISR 为空时不变
+    pub fn end_of_interrupt(&mut self) -> (result: Option<u8>)
+        requires
+            old(self).inv(),
+        ensures
+            self.inv(),
+            result matches Some(v) ==> {
+                &&& old(self).is_highest_in_service(v)
+                &&& *self == (VirtLocalApic { isr: self.isr, ..*old(self) })
+                &&& self.isr@ == old(self).isr@.update(v as int, false)
+            },
+            result is None ==> *self == *old(self),
+    {
+        let v = self.highest_in_service();
+        match v {
+            Some(vector) => {
+                self.complete(vector);
+                Some(vector)
+            },
+            None => None,
+        }
+    }
+    
+    /// 将 vector 置入 IRR
+    pub fn set_irr(&mut self, vector: u8)
+        ensures
+            self.irr@ == old(self).irr@.update(vector as int, true),
+            self.timer == old(self).timer,
+            self.apic_base == old(self).apic_base,
+            self.mode == old(self).mode,
+            *self == (VirtLocalApic { irr: self.irr, ..*old(self) }),
+    {
+        self.irr.set(vector as usize, true);
+    }
+    
+    /// 规范函数：guest 读 IA32_TSC_DEADLINE 的结果，LVT timer 不在 TSC-deadline 模式时读出 0
+    pub open spec fn spec_tsc_deadline_read(&self) -> u64 {
+        if self.timer.mode is TscDeadline { self.timer.deadline } else { 0 }
+    }
+    
+    pub fn tsc_deadline_read(&self) -> (result: u64)
+        ensures
+            result == self.spec_tsc_deadline_read(),
+    {
+        match self.timer.mode {
+            TimerMode::TscDeadline => self.timer.deadline,
+            _ => 0,
+        }
+    }
+    
+    /// 写 IA32_TSC_DEADLINE：0 解除定时器
+    /// 
+    /// LVT timer 不在 TSC-deadline 模式时写入被忽略而不是 #GP（SDM 10.5.4.1）
+    pub fn write_tsc_deadline(&mut self, val: u64)
+        requires
+            old(self).inv(),
+        ensures
+            self.inv(),
+            old(self).timer.mode is TscDeadline
+                ==> *self == (VirtLocalApic { timer: ApicTimer { deadline: val, ..old(self).timer }, ..*old(self) }),
+            !(old(self).timer.mode is TscDeadline) ==> *self == *old(self),
+    {
+        if let TimerMode::TscDeadline = self.timer.mode {
+            self.timer.deadline = val;
+        }
+    }
+    
+    /// 按当前 guest TSC 检查 TSC-deadline 定时器
+    /// 
+    /// 到期时 LVT timer 未屏蔽则置位 IRR，并清零 deadline，保证只触发一次
+    pub fn poll_tsc_deadline(&mut self, tsc: u64)
+        requires
+            old(self).inv(),
+        ensures
+            self.inv(),
+            old(self).timer.spec_deadline_due(tsc) ==> {
+                &&& *self == (VirtLocalApic {
+                    irr: self.irr,
+                    timer: ApicTimer { deadline: 0, ..old(self).timer },
+                    ..*old(self)
+                })
+                &&& old(self).spec_lvt_delivers(LVT_TIMER as int) ==> self.irr@
+                    == old(self).irr@.update(old(self).spec_lvt_entry(LVT_TIMER as int).spec_vector() as int, true)
+                &&& !old(self).spec_lvt_delivers(LVT_TIMER as int) ==> self.irr@ == old(self).irr@
+            },
+            !old(self).timer.spec_deadline_due(tsc) ==> *self == *old(self),
+    {
+        let deadline_mode = match self.timer.mode {
+            TimerMode::TscDeadline => true,
+            _ => false,
+        };
+        if !deadline_mode || self.timer.deadline == 0 || tsc < self.timer.deadline {
+            return;
+        }
+        if self.lvt_delivers(LVT_TIMER) {
+            let vector = self.lvt_entry(LVT_TIMER).vector();
+            self.set_irr(vector);
+        }
+        self.timer.deadline = 0;
+    }
+    
+    /// 定时器前进 elapsed 个计数
+    /// 
+    /// 到期时 LVT timer 未屏蔽则置位 IRR；周期模式重新装入初值，单次模式停在 0。
+    /// 一次 tick 内的多次到期合并为一次（IRR 只有一位）
+    pub fn tick(&mut self, elapsed: u32)
+        requires
+            old(self).inv(),
+        ensures
+            self.inv(),
+            self.apic_base == old(self).apic_base,
+            self.mode == old(self).mode,
+            self.timer.mode == old(self).timer.mode,
+            self.lvt == old(self).lvt,
+            self.svr == old(self).svr,
+            (old(self).timer.spec_expires(elapsed) && old(self).spec_lvt_delivers(LVT_TIMER as int))
+                ==> self.irr@ == old(self).irr@.update(old(self).spec_lvt_entry(LVT_TIMER as int).spec_vector() as int, true),
+            !(old(self).timer.spec_expires(elapsed) && old(self).spec_lvt_delivers(LVT_TIMER as int))
+                ==> self.irr@ == old(self).irr@,
+            old(self).timer.spec_expires(elapsed) && old(self).timer.mode is Periodic
+                ==> self.timer.current_count == old(self).timer.initial_count,
+    {
+        let deadline_mode = match self.timer.mode {
+            TimerMode::TscDeadline => true,
+            _ => false,
+        };
+        if deadline_mode || self.timer.current_count == 0 {
+            // TSC-deadline 模式不按计数递减；计数为 0 表示未启动或已触发
+            return;
+        }
+        
+        if elapsed < self.timer.current_count {
+            self.timer.current_count = self.timer.current_count - elapsed;
+            return;
+        }
+        
+        // 到期：屏蔽的 LVT 不产生中断
+        if self.lvt_delivers(LVT_TIMER) {
+            let vector = self.lvt_entry(LVT_TIMER).vector();
+            self.set_irr(vector);
+        }
+        
+        match self.timer.mode {
+            TimerMode::Periodic => {
+                self.timer.current_count = self.timer.initial_count;
+            },
+            _ => {
+                self.timer.current_count = 0;
+            },
+        }
+    }
+}
+
+/// 引理：EOI 结束最高在服务向量 h 后，优先级类不高于 h、但高于其余在服务向量的请求不再被 ISR 屏蔽
+/// 
+/// EOI 只修改 ISR，从不直接注入；entry 前的 maybe_inject_pending 据此重新评估 IRR
+pub proof fn lemma_eoi_unmasks_pending(before: VirtLocalApic, after: VirtLocalApic, h: u8, v: u8)
+    requires
+        before.is_highest_in_service(h),
+        after.isr@ == before.isr@.update(h as int, false),
+        v / 16 <= h / 16,
+        forall|u: int| 0 <= u < NUM_VECTORS && u != h && #[trigger] before.isr@[u] ==> u / 16 < v as int / 16,
+    ensures
+        !before.above_in_service(v),
+        after.above_in_service(v),
+{
+    assert(before.isr@[h as int]);
+}
+
+/// 引理：清除使能位的 SVR 写入关闭 APIC，复位后的 APIC 处于软件关闭状态
+pub proof fn lemma_software_disabled_blocks_delivery(apic: VirtLocalApic, val: u32)
+    ensures
+        (VirtLocalApic { svr: val & SVR_WRITABLE, ..apic }).spec_software_enabled()
+            == (val & SVR_SOFTWARE_ENABLE != 0),
+        !(VirtLocalApic { svr: SVR_RESET, ..apic }).spec_software_enabled(),
+{
+    assert((val & (0xff | 0x100)) & 0x100 == val & 0x100) by (bit_vector);
+    assert(0xffu32 & 0x100 == 0) by (bit_vector);
+}
+
+/// 引理：屏蔽或软件关闭时 LVT 项不投递；复位值屏蔽；timer 与 error 项拒绝非 fixed 的投递模式
+pub proof fn lemma_lvt_mask_gates_delivery(apic: VirtLocalApic, idx: int, val: u64)
+    requires
+        0 <= idx < LVT_COUNT,
+    ensures
+        apic.spec_lvt_entry(idx).spec_masked() ==> !apic.spec_lvt_delivers(idx),
+        !apic.spec_software_enabled() ==> !apic.spec_lvt_delivers(idx),
+        (LvtEntry { raw: LVT_RESET }).spec_masked(),
+        (LvtEntry { raw: val as u32 }).spec_delivery_mode() != LVT_DELIVERY_FIXED
+            ==> !spec_lvt_write_valid(LVT_TIMER, val) && !spec_lvt_write_valid(LVT_ERROR, val),
+{
+    assert(0x1_0000u32 & 0x1_0000 != 0) by (bit_vector);
+    assert(val & !(0x7_00ffu32 as u64) == 0 ==> ((val as u32) >> 8u32) & 0x7 == 0) by (bit_vector);
+    assert(val & !(0x1_00ffu32 as u64) == 0 ==> ((val as u32) >> 8u32) & 0x7 == 0) by (bit_vector);
+}
+
+/// 物理 LAPIC（硬件抽象）
+pub struct PhysLocalApic;
+
+impl PhysLocalApic {
+    /// 发送中断结束信号（EOI）
+    #[verifier::external_body]
+    pub fn end_of_interrupt(&mut self) {
+        // 硬件操作：向 LAPIC 的 EOI 寄存器写入
+    }
+}
+
+} // verus!
@@ -2,6 +2,13 @@
 // 用于验证 CPU 虚拟化的核心功能
 
 use vstd::prelude::*;
+use super::apic::*;
+use super::cpuid::*;
+use super::ept::*;
+use super::msr::*;
+use super::vmcs::*;
+use super::vmx::*;
+use crate::percpu::{notify_cpu, IpiMailbox, PerCpuArray};
 
 verus! {
 
@@ -37,6 +44,8 @@ impl GeneralRegisters {
     pub open spec fn offset_of_rcx() -> usize { 8 }
     pub open spec fn offset_of_rdx() -> usize { 16 }
     pub open spec fn offset_of_rbx() -> usize { 24 }
+    /// rsp 槽：不对应任何寄存器，弹栈时用 add rsp, 8 跳过
+    pub open spec fn offset_of_unused_rsp() -> usize { 32 }
     pub open spec fn offset_of_rbp() -> usize { 40 }
     pub open spec fn offset_of_rsi() -> usize { 48 }
     pub open spec fn offset_of_rdi() -> usize { 56 }
@@ -51,50 +60,395 @@ impl GeneralRegisters {
     
     /// 结构体总大小（128 字节）
     pub open spec fn size() -> usize { 128 }
+    
+    /// 规范函数：第 i 个通用寄存器（rax..r15，跳过 rsp 槽）的偏移
+    pub open spec fn spec_offset_of_index(i: usize) -> usize {
+        (8 * (i + if i >= 4 { 1usize } else { 0usize })) as usize
+    }
+    
+    /// 按下标计算寄存器偏移，rbx（下标 3）之后跳过 _unused_rsp
+    /// 
+    /// 只有 15 个真实寄存器，下标 15 会落在结构体之外，因此要求 i < 15
+    pub fn offset_of_index(i: usize) -> (result: usize)
+        requires
+            i < 15,
+        ensures
+            result == Self::spec_offset_of_index(i),
+            result + 8 <= Self::size(),
+    {
+        if i >= 4 {
+            8 * (i + 1)
+        } else {
+            8 * i
+        }
+    }
 }
 
-/// 虚拟 LAPIC（本地 APIC）
-pub struct VirtLocalApic {
-    pub phys_lapic: PhysLocalApic,
+/// 引理：offset_of_index 与各个具名的 offset_of_* 一致
+pub proof fn lemma_offset_of_index_named()
+    ensures
+        GeneralRegisters::spec_offset_of_index(0) == GeneralRegisters::offset_of_rax(),
+        GeneralRegisters::spec_offset_of_index(1) == GeneralRegisters::offset_of_rcx(),
+        GeneralRegisters::spec_offset_of_index(2) == GeneralRegisters::offset_of_rdx(),
+        GeneralRegisters::spec_offset_of_index(3) == GeneralRegisters::offset_of_rbx(),
+        GeneralRegisters::spec_offset_of_index(4) == GeneralRegisters::offset_of_rbp(),
+        GeneralRegisters::spec_offset_of_index(5) == GeneralRegisters::offset_of_rsi(),
+        GeneralRegisters::spec_offset_of_index(6) == GeneralRegisters::offset_of_rdi(),
+        GeneralRegisters::spec_offset_of_index(7) == GeneralRegisters::offset_of_r8(),
+        GeneralRegisters::spec_offset_of_index(8) == GeneralRegisters::offset_of_r9(),
+        GeneralRegisters::spec_offset_of_index(9) == GeneralRegisters::offset_of_r10(),
+        GeneralRegisters::spec_offset_of_index(10) == GeneralRegisters::offset_of_r11(),
+        GeneralRegisters::spec_offset_of_index(11) == GeneralRegisters::offset_of_r12(),
+        GeneralRegisters::spec_offset_of_index(12) == GeneralRegisters::offset_of_r13(),
+        GeneralRegisters::spec_offset_of_index(13) == GeneralRegisters::offset_of_r14(),
+        GeneralRegisters::spec_offset_of_index(14) == GeneralRegisters::offset_of_r15(),
+{
 }
 
-impl VirtLocalApic {
-    pub fn new() -> Self {
-        VirtLocalApic {
-            phys_lapic: PhysLocalApic,
-        }
+/// 引理：_unused_rsp 恰好夹在 rbx 与 rbp 之间，且是 offset_of_index 唯一跳过的槽位
+pub proof fn lemma_unused_rsp_slot()
+    ensures
+        GeneralRegisters::offset_of_rbx() + 8 == GeneralRegisters::offset_of_unused_rsp(),
+        GeneralRegisters::offset_of_unused_rsp() + 8 == GeneralRegisters::offset_of_rbp(),
+        GeneralRegisters::spec_popped_bytes(4) == GeneralRegisters::offset_of_unused_rsp(),
+        forall|i: usize| i < 15 ==> #[trigger] GeneralRegisters::spec_offset_of_index(i)
+            != GeneralRegisters::offset_of_unused_rsp(),
+{
+}
+
+/// 引理：GeneralRegisters 的布局与 vmx_launch / vmx_exit 汇编的压栈顺序一致
+/// 
+/// 本树中只有这一份 GeneralRegisters 定义，汇编侧直接复用它，无需类型转换；
+/// 这里固定汇编依赖的布局：第 i 个寄存器位于 8 * i（rsp 槽位于 32），
+/// 且结构体大小 128 正是 vmx_exit 中 `mov rsp, [rsp + 128]` 读取 host_stack_top 的偏移
+pub proof fn lemma_regs_layout_identical()
+    ensures
+        GeneralRegisters::offset_of_rax() == 0 * 8,
+        GeneralRegisters::offset_of_rcx() == 1 * 8,
+        GeneralRegisters::offset_of_rdx() == 2 * 8,
+        GeneralRegisters::offset_of_rbx() == 3 * 8,
+        GeneralRegisters::offset_of_unused_rsp() == 4 * 8,
+        GeneralRegisters::offset_of_rbp() == 5 * 8,
+        GeneralRegisters::offset_of_rsi() == 6 * 8,
+        GeneralRegisters::offset_of_rdi() == 7 * 8,
+        GeneralRegisters::offset_of_r8() == 8 * 8,
+        GeneralRegisters::offset_of_r9() == 9 * 8,
+        GeneralRegisters::offset_of_r10() == 10 * 8,
+        GeneralRegisters::offset_of_r11() == 11 * 8,
+        GeneralRegisters::offset_of_r12() == 12 * 8,
+        GeneralRegisters::offset_of_r13() == 13 * 8,
+        GeneralRegisters::offset_of_r14() == 14 * 8,
+        GeneralRegisters::offset_of_r15() == 15 * 8,
+        GeneralRegisters::size() == 16 * 8,
+        GeneralRegisters::offset_of_r15() + 8 == GeneralRegisters::size(),
+{
+}
+
+impl GeneralRegisters {
+    /// 规范函数：第 i 个通用寄存器的值（与 offset_of_index 的下标一致）
+    pub open spec fn spec_reg_at(&self, i: int) -> u64 {
+        if i == 0 { self.rax }
+        else if i == 1 { self.rcx }
+        else if i == 2 { self.rdx }
+        else if i == 3 { self.rbx }
+        else if i == 4 { self.rbp }
+        else if i == 5 { self.rsi }
+        else if i == 6 { self.rdi }
+        else if i == 7 { self.r8 }
+        else if i == 8 { self.r9 }
+        else if i == 9 { self.r10 }
+        else if i == 10 { self.r11 }
+        else if i == 11 { self.r12 }
+        else if i == 12 { self.r13 }
+        else if i == 13 { self.r14 }
+        else { self.r15 }
+    }
+    
+    pub fn reg_at(&self, i: usize) -> (result: u64)
+        requires
+            i < 15,
+        ensures
+            result == self.spec_reg_at(i as int),
+    {
+        if i == 0 { self.rax }
+        else if i == 1 { self.rcx }
+        else if i == 2 { self.rdx }
+        else if i == 3 { self.rbx }
+        else if i == 4 { self.rbp }
+        else if i == 5 { self.rsi }
+        else if i == 6 { self.rdi }
+        else if i == 7 { self.r8 }
+        else if i == 8 { self.r9 }
+        else if i == 9 { self.r10 }
+        else if i == 10 { self.r11 }
+        else if i == 11 { self.r12 }
+        else if i == 12 { self.r13 }
+        else if i == 13 { self.r14 }
+        else { self.r15 }
+    }
+    
+    /// 按下标写第 i 个通用寄存器，其余寄存器（含 _unused_rsp）不变
+    pub fn set_reg_at(&mut self, i: usize, val: u64)
+        requires
+            i < 15,
+        ensures
+            self.spec_reg_at(i as int) == val,
+            self._unused_rsp == old(self)._unused_rsp,
+            forall|j: int| 0 <= j < 15 && j != i ==> #[trigger] self.spec_reg_at(j) == old(self).spec_reg_at(j),
+    {
+        if i == 0 { self.rax = val; }
+        else if i == 1 { self.rcx = val; }
+        else if i == 2 { self.rdx = val; }
+        else if i == 3 { self.rbx = val; }
+        else if i == 4 { self.rbp = val; }
+        else if i == 5 { self.rsi = val; }
+        else if i == 6 { self.rdi = val; }
+        else if i == 7 { self.r8 = val; }
+        else if i == 8 { self.r9 = val; }
+        else if i == 9 { self.r10 = val; }
+        else if i == 10 { self.r11 = val; }
+        else if i == 11 { self.r12 = val; }
+        else if i == 12 { self.r13 = val; }
+        else if i == 13 { self.r14 = val; }
+        else { self.r15 = val; }
+    }
+    
+    /// 规范函数：弹出前 i 个寄存器后 rsp 相对结构体起始的偏移
+    pub open spec fn spec_popped_bytes(i: usize) -> usize {
+        if i == 0 { 0 } else { (Self::spec_offset_of_index((i - 1) as usize) + 8) as usize }
     }
 }
 
-/// 物理 LAPIC（硬件抽象）
-pub struct PhysLocalApic;
+/// 物理 CPU 寄存器文件在模型中的影子（vmx_launch 弹栈的目标）
+pub struct CpuRegisters {
+    /// rax..r15，顺序与 GeneralRegisters 相同，不含 rsp
+    pub gprs: [u64; 15],
+    pub rsp: u64,
+}
 
-impl PhysLocalApic {
-    /// 发送中断结束信号（EOI）
-    #[verifier::external_body]
-    pub fn end_of_interrupt(&mut self) {
-        // 硬件操作：向 LAPIC 的 EOI 寄存器写入
+/// vmx_launch 中 restore_regs_from_stack! 的参考实现
+/// 
+/// 进入时 rsp 指向 guest 的 GeneralRegisters；逐个弹出 15 个寄存器，
+/// rsp 槽（rbx 之后）由 offset_of_index 跳过，结束时 rsp 越过整个结构体
+pub fn restore_all(cpu_regs: &mut CpuRegisters, guest: &GeneralRegisters)
+    requires
+        old(cpu_regs).rsp + GeneralRegisters::size() <= u64::MAX,
+    ensures
+        forall|j: int| 0 <= j < 15 ==> #[trigger] cpu_regs.gprs@[j] == guest.spec_reg_at(j),
+        cpu_regs.rsp == old(cpu_regs).rsp + GeneralRegisters::size(),
+{
+    let base = cpu_regs.rsp;
+    let mut i: usize = 0;
+    while i < 15
+        invariant
+            i <= 15,
+            base + GeneralRegisters::size() <= u64::MAX,
+            forall|j: int| 0 <= j < i ==> #[trigger] cpu_regs.gprs@[j] == guest.spec_reg_at(j),
+            cpu_regs.rsp == base + GeneralRegisters::spec_popped_bytes(i),
+        decreases 15 - i,
+    {
+        // rbx 之后的 add rsp, 8 体现在 offset_of_index 中：此时 rsp 恰好指向 _unused_rsp 槽
+        proof {
+            if i == 4 {
+                lemma_unused_rsp_slot();
+                assert(cpu_regs.rsp == base + GeneralRegisters::offset_of_unused_rsp());
+            }
+        }
+        let offset = GeneralRegisters::offset_of_index(i);
+        cpu_regs.rsp = base + offset as u64;
+        let val = guest.reg_at(i);
+        cpu_regs.gprs.set(i, val);
+        cpu_regs.rsp = cpu_regs.rsp + 8;
+        i = i + 1;
     }
 }
 
+/// vmx_launch 的参考实现：`mov rsp, rdi` 之后执行 restore_regs_from_stack!
+/// 
+/// rdi 是 guest_regs 的地址（这里把帧起始记为 0），rsp 只充当弹栈指针；
+/// 帧中的 _unused_rsp 槽被跳过，不会装入任何寄存器，结束时 rsp 恰好越过整个帧，
+/// 停在 host_stack_top 所在的位置。guest 真正的 RSP 由 vmlaunch 从 VMCS 的
+/// GUEST_RSP 字段（guest_state.rsp）加载，与 GPR 帧无关
+pub fn vmx_launch_simulated(guest: &GeneralRegisters) -> (result: CpuRegisters)
+    ensures
+        forall|j: int| 0 <= j < 15 ==> #[trigger] result.gprs@[j] == guest.spec_reg_at(j),
+        result.rsp == GeneralRegisters::size() as u64,
+{
+    // mov rsp, rdi
+    let mut cpu_regs = CpuRegisters { gprs: [0u64; 15], rsp: 0 };
+    restore_all(&mut cpu_regs, guest);
+    cpu_regs
+}
+
 /// VMX Region（用于 VMXON 和 VMCS）
 pub struct VmxRegion {
     frame: Option<u64>,  // 简化：用地址表示
 }
 
 impl VmxRegion {
-    pub fn fake_init() -> Self {
+    /// 规范函数：已分配物理页（4 KiB 对齐且低于 MAXPHYADDR）
+    pub closed spec fn is_allocated(&self) -> bool {
+        &&& self.frame is Some
+        &&& self.frame.unwrap() % 4096 == 0
+        &&& self.frame.unwrap() < spec_max_phys_addr()
+    }
+    
+    /// region 的物理地址
+    pub fn phys_addr(&self) -> (result: u64)
+        requires
+            self.is_allocated(),
+        ensures
+            result % 4096 == 0,
+            result < spec_max_phys_addr(),
+            self.spec_frame() == Some(result),
+    {
+        self.frame.unwrap()
+    }
+    
+    /// 尚未分配的 region，已分配的 region 只能经 VmxRegionPool::region 取得
+    pub fn fake_init() -> (result: Self)
+        ensures
+            !result.is_allocated(),
+    {
         VmxRegion { frame: None }
     }
     
-    #[verifier::external_body]
-    pub fn new() -> Self {
-        VmxRegion { frame: Some(0) }
+    /// 规范函数：region 所在的物理页
+    pub closed spec fn spec_frame(&self) -> Option<u64> {
+        self.frame
+    }
+}
+
+/// VMX region 池的槽位数：每个 CPU 一个 VMXON region 和一个 VMCS
+pub const VMX_REGION_POOL_SIZE: usize = 2 * MAX_CPU_NUM;
+
+/// 规范函数：VMX region 池的起始物理地址
+/// 
+/// 池是启动时在内核镜像之后静态保留的 VMX_REGION_POOL_SIZE 个连续 4 KiB 页（固定数组，不经通用分配器）
+pub uninterp spec fn spec_vmx_region_pool_base() -> u64;
+
+#[verifier::external_body]
+pub fn vmx_region_pool_base() -> (result: u64)
+    ensures
+        result == spec_vmx_region_pool_base(),
+        spec_vmx_region_pool_valid(),
+{
+    0x2000_0000  // 示例值
+}
+
+/// 规范函数：整个池 4 KiB 对齐且低于 MAXPHYADDR
+pub open spec fn spec_vmx_region_pool_valid() -> bool {
+    &&& spec_vmx_region_pool_base() % 4096 == 0
+    &&& spec_vmx_region_pool_base() + VMX_REGION_POOL_SIZE * 4096 <= spec_max_phys_addr()
+}
+
+/// 规范函数：池中第 slot 个槽位的物理页
+pub open spec fn spec_vmx_pool_frame(slot: int) -> u64 {
+    (spec_vmx_region_pool_base() + slot * 4096) as u64
+}
+
+/// 规范函数：cpuid 的 VMXON region 所在槽位
+pub open spec fn spec_vmxon_slot(cpuid: usize) -> int {
+    2 * cpuid
+}
+
+/// 规范函数：cpuid 的 VMCS 所在槽位
+pub open spec fn spec_vmcs_slot(cpuid: usize) -> int {
+    2 * cpuid + 1
+}
+
+/// 不依赖通用分配器的 VMX region 池
+/// 
+/// 槽位按 CPU 固定划分（见 spec_vmxon_slot / spec_vmcs_slot），各 CPU 之间没有共享的可变状态；
+/// 同一 CPU 重复分配得到同一对页，不会泄漏，也不需要释放
+pub struct VmxRegionPool {
+    base: u64,
+}
+
+impl VmxRegionPool {
+    /// 规范函数：描述的是静态保留的那个池
+    pub closed spec fn is_valid(&self) -> bool {
+        &&& self.base == spec_vmx_region_pool_base()
+        &&& spec_vmx_region_pool_valid()
+    }
+    
+    pub fn get() -> (result: Self)
+        ensures
+            result.is_valid(),
+    {
+        VmxRegionPool { base: vmx_region_pool_base() }
+    }
+    
+    /// 第 slot 个槽位的 region
+    pub fn region(&self, slot: usize) -> (result: VmxRegion)
+        requires
+            self.is_valid(),
+            slot < VMX_REGION_POOL_SIZE,
+        ensures
+            result.is_allocated(),
+            result.spec_frame() == Some(spec_vmx_pool_frame(slot as int)),
+    {
+        proof {
+            assert(slot * 4096 < VMX_REGION_POOL_SIZE * 4096) by (nonlinear_arith)
+                requires slot < VMX_REGION_POOL_SIZE;
+            assert((self.base + slot * 4096) % 4096 == 0) by (nonlinear_arith)
+                requires self.base % 4096 == 0;
+        }
+        VmxRegion { frame: Some(self.base + (slot as u64) * 4096) }
+    }
+    
+    /// cpuid 的 VMXON region 与 VMCS
+    pub fn cpu_regions(&self, cpuid: usize) -> (result: (VmxRegion, VmxRegion))
+        requires
+            self.is_valid(),
+            cpuid < MAX_CPU_NUM,
+        ensures
+            result.0.is_allocated(),
+            result.1.is_allocated(),
+            result.0.spec_frame() == Some(spec_vmx_pool_frame(spec_vmxon_slot(cpuid))),
+            result.1.spec_frame() == Some(spec_vmx_pool_frame(spec_vmcs_slot(cpuid))),
+    {
+        (self.region(2 * cpuid), self.region(2 * cpuid + 1))
+    }
+}
+
+/// 引理：池中不同槽位是不同的物理页
+pub proof fn lemma_pool_slots_distinct(i: int, j: int)
+    requires
+        spec_vmx_region_pool_valid(),
+        0 <= i < VMX_REGION_POOL_SIZE,
+        0 <= j < VMX_REGION_POOL_SIZE,
+        i != j,
+    ensures
+        spec_vmx_pool_frame(i) != spec_vmx_pool_frame(j),
+{
+    assert(i * 4096 != j * 4096) by (nonlinear_arith)
+        requires i != j;
+    assert(i * 4096 < VMX_REGION_POOL_SIZE * 4096 && j * 4096 < VMX_REGION_POOL_SIZE * 4096) by (nonlinear_arith)
+        requires 0 <= i < VMX_REGION_POOL_SIZE, 0 <= j < VMX_REGION_POOL_SIZE;
+}
+
+/// 引理：两个不同 CPU 从池中分配的四个 region 两两不同，同一 CPU 的 VMXON region 与 VMCS 也不同
+pub proof fn lemma_pool_no_double_alloc(a: usize, b: usize)
+    requires
+        spec_vmx_region_pool_valid(),
+        a < MAX_CPU_NUM,
+        b < MAX_CPU_NUM,
+    ensures
+        spec_vmx_pool_frame(spec_vmxon_slot(a)) != spec_vmx_pool_frame(spec_vmcs_slot(b)),
+        a != b ==> spec_vmx_pool_frame(spec_vmxon_slot(a)) != spec_vmx_pool_frame(spec_vmxon_slot(b)),
+        a != b ==> spec_vmx_pool_frame(spec_vmcs_slot(a)) != spec_vmx_pool_frame(spec_vmcs_slot(b)),
+{
+    lemma_pool_slots_distinct(spec_vmxon_slot(a), spec_vmcs_slot(b));
+    if a != b {
+        lemma_pool_slots_distinct(spec_vmxon_slot(a), spec_vmxon_slot(b));
+        lemma_pool_slots_distinct(spec_vmcs_slot(a), spec_vmcs_slot(b));
     }
 }
 
 pub const MAX_CPU_NUM: usize = 256;
 pub const PER_CPU_SIZE: usize = 524288;  // 512 KB
+pub const SCRATCH_SIZE: usize = 4096;  // 4 KB，位于每 CPU 区域底部，其余为栈
 
 /// CPU 架构相关状态
 #[repr(C)]
@@ -103,15 +457,63 @@ pub struct ArchCpu {
     pub host_stack_top: u64,
     
     pub cpuid: usize,
+    pub core_end: u64,  // 构造时读取一次的 core_end，栈布局只使用这个值
     pub power_on: bool,
     pub virt_lapic: VirtLocalApic,
     
     pub vmx_on: bool,
     pub vmcs_configured: bool,
+    pub vmcs_loaded: bool,    // VMPTRLD 后成为当前 VMCS
+    pub vmcs_launched: bool,  // 当前 VMCS 已 vmlaunch（之后用 vmresume）
     pub vmcs_revision_id: u32,
     pub vmxon_region: VmxRegion,
     pub vmcs_region: VmxRegion,
-    pub vm_launch_guest_regs: GeneralRegisters,
+    pub staged_guest_regs: GeneralRegisters,  // set_guest_regs 记录的寄存器，prepare_launch_frame 拷入 guest_regs
+    
+    pub guest_state: GuestState,
+    pub guest_efer: u64,  // guest 经 RDMSR/WRMSR 看到的 IA32_EFER
+    pub guest_pat: u64,  // guest 经 RDMSR/WRMSR 看到的 IA32_PAT
+    pub guest_feature_control: u64,  // guest 看到的 IA32_FEATURE_CONTROL（锁定后只读）
+    pub tsc_offset: u64,  // VMCS TSC offset：guest TSC = host TSC + tsc_offset（按 2^64 回绕）
+    pub entry_event: Option<InjectedEvent>,  // VM-entry interruption-information
+    pub pending_events: PendingEvents,  // 尚未写入 interruption-information 的事件
+    pub vector_owner: VectorOwnership,  // 已应答的外部中断交给 host 还是本 guest
+    pub ctls: VmcsControls,
+    pub host_cr0: u64,  // VMCS host-state 区域中的 CR0/CR3/CR4
+    pub host_cr3: u64,
+    pub host_cr4: u64,
+    pub mwait_break_on_interrupt: bool,  // MWAIT ECX[0]：屏蔽的中断也能唤醒
+    pub interrupt_window_requested: bool,  // 下次 entry 打开 interrupt-window exiting
+    pub nmi_window_requested: bool,  // 下次 entry 打开 NMI-window exiting
+    pub triple_fault_policy: TripleFaultPolicy,
+    pub exit_counts: [u64; NUM_EXIT_REASONS],  // 按基本退出原因统计已处理的退出次数
+    pub cpuid_cache: CpuidCache,  // 透传 leaf 的硬件 CPUID 结果
+    
+    pub parking_initialized: bool,
+    pub active_pagetable: ActivePageTable,
+    
+    /// 期望 guest 启动时使用的通用寄存器（由 set_guest_regs 记录）
+    pub intended_guest_regs: Ghost<GeneralRegisters>,
+    /// 当前 VMCS 中已经 VMWRITE 过的字段编码
+    pub written_fields: Ghost<Set<u32>>,
+    /// guest 视角的 CR3（影子页表模式下 guest_state.cr3 是影子页表根，两者不同）
+    pub guest_cr3: Ghost<u64>,
+}
+
+/// guest 三重错误时的处理策略
+pub enum TripleFaultPolicy {
+    /// 复位 vCPU，从复位向量重新启动
+    ResetVcpu,
+    /// 停住物理 CPU
+    Fatal,
+}
+
+/// 当前 CPU 使用的页表
+pub enum ActivePageTable {
+    /// hypervisor 自身的页表
+    Host,
+    /// parking 内存的页表（idle 时 guest 在其中自旋）
+    Parking,
 }
 
 /// 规范函数：获取 core_end（内核代码结束地址）
@@ -121,11 +523,29 @@ pub uninterp spec fn spec_core_end() -> u64;
 pub fn core_end() -> (result: u64)
     ensures 
         result > 0,
+        result % 4096 == 0,  // 内核镜像按页结束
         spec_core_end() == result,
 {
     0x10000000  // 示例值
 }
 
+/// 规范函数：host TSC 的当前值
+pub uninterp spec fn spec_host_tsc() -> u64;
+
+#[verifier::external_body]
+pub fn read_host_tsc() -> (result: u64)
+    ensures
+        result == spec_host_tsc(),
+{
+    0  // 硬件操作：RDTSC
+}
+
+/// VM exit 的入口地址（ArchCpu::vmx_exit），写入 VMCS host RIP（信任边界）
+#[verifier::external_body]
+pub fn vmx_exit_entry() -> u64 {
+    0  // 硬件操作：lea rax, [rip + vmx_exit]
+}
+
 /// 规范函数：获取当前 CPU ID
 pub uninterp spec fn spec_this_cpu_id() -> usize;
 
@@ -138,13 +558,175 @@ pub fn this_cpu_id() -> (result: usize)
     0  // 示例值，实际从 APIC 读取
 }
 
+/// CPU 致命错误（idle/启动流程中不可恢复的失败）
+pub enum FatalCpuError {
+    /// VMXON / VMCLEAR / VMPTRLD 失败
+    VmxActivationFailed,
+    /// VMCS 字段配置失败
+    VmcsSetupFailed,
+    /// vmlaunch / vmresume 失败
+    VmEntryFailed,
+    /// guest-state 区域不满足 VM entry 检查
+    InvalidGuestState,
+    /// host 栈顶地址计算溢出
+    StackTopOverflow,
+    /// guest 三重错误且策略为停机
+    GuestTripleFault,
+    /// VM entry 时 MSR-load 区域加载失败
+    MsrLoadFailed,
+    /// VM entry 期间发生 machine-check
+    EntryMachineCheck,
+    /// EPT 表项格式错误（hypervisor 自身构造了非法表项）
+    EptMisconfiguration,
+}
+
+impl FatalCpuError {
+    /// 规范函数：日志中记录的错误码
+    pub open spec fn spec_code(self) -> u32 {
+        match self {
+            FatalCpuError::VmxActivationFailed => 1,
+            FatalCpuError::VmcsSetupFailed => 2,
+            FatalCpuError::VmEntryFailed => 3,
+            FatalCpuError::InvalidGuestState => 4,
+            FatalCpuError::StackTopOverflow => 5,
+            FatalCpuError::GuestTripleFault => 6,
+            FatalCpuError::MsrLoadFailed => 7,
+            FatalCpuError::EntryMachineCheck => 8,
+            FatalCpuError::EptMisconfiguration => 9,
+        }
+    }
+    
+    pub fn code(&self) -> (result: u32)
+        ensures
+            result == self.spec_code(),
+    {
+        match self {
+            FatalCpuError::VmxActivationFailed => 1,
+            FatalCpuError::VmcsSetupFailed => 2,
+            FatalCpuError::VmEntryFailed => 3,
+            FatalCpuError::InvalidGuestState => 4,
+            FatalCpuError::StackTopOverflow => 5,
+            FatalCpuError::GuestTripleFault => 6,
+            FatalCpuError::MsrLoadFailed => 7,
+            FatalCpuError::EntryMachineCheck => 8,
+            FatalCpuError::EptMisconfiguration => 9,
+        }
+    }
+}
+
+/// 引理：不同的致命错误记录为不同的错误码，观察者可以区分
+pub proof fn lemma_fatal_codes_distinct(a: FatalCpuError, b: FatalCpuError)
+    requires
+        a != b,
+    ensures
+        a.spec_code() != b.spec_code(),
+{
+}
+
+/// 记录致命错误（日志钩子）
+#[verifier::external_body]
+pub fn log_fatal_error(code: u32) -> (recorded: Ghost<u32>)
+    ensures
+        recorded@ == code,
+{
+    // error!("CPU fatal error {}", code);
+    Ghost(code)
+}
+
+/// 停住当前 CPU（信任边界）
+#[verifier::external_body]
+pub fn halt_forever() -> ! {
+    // 硬件操作：cli; hlt 循环
+    loop {}
+}
+
+/// 启动与 entry 流程中可能失败的步骤
+pub enum BootStep {
+    /// alloc 之后的 VMXON / VMCLEAR / VMPTRLD
+    ActivateVmx,
+    /// 写入 VMCS 字段
+    SetupVmcs,
+    /// 计算 host 栈顶
+    SetStackTop,
+    /// vmlaunch / vmresume
+    Entry,
+}
+
+impl BootStep {
+    /// 规范函数：该步骤失败时应记录的致命错误
+    pub open spec fn spec_failure(self) -> FatalCpuError {
+        match self {
+            BootStep::ActivateVmx => FatalCpuError::VmxActivationFailed,
+            BootStep::SetupVmcs => FatalCpuError::VmcsSetupFailed,
+            BootStep::SetStackTop => FatalCpuError::StackTopOverflow,
+            BootStep::Entry => FatalCpuError::VmEntryFailed,
+        }
+    }
+}
+
+/// 启动步骤的失败出口：前置条件要求 err 与失败的步骤对应，错误分支传错变体就无法通过验证
+pub fn boot_failed(step: BootStep, err: FatalCpuError) -> !
+    requires
+        err == step.spec_failure(),
+{
+    cpu_fatal(err)
+}
+
+/// 致命错误出口：先记录错误再停机
+pub fn cpu_fatal(err: FatalCpuError) -> ! {
+    let code = err.code();
+    let recorded = log_fatal_error(code);
+    
+    proof {
+        assert(recorded@ == err.spec_code());
+    }
+    
+    halt_forever()
+}
+
 impl ArchCpu {
     /// 核心不变式：ArchCpu 的有效性条件
     pub closed spec fn inv(&self) -> bool {
         &&& self.cpuid < MAX_CPU_NUM
-        &&& (self.vmx_on ==> self.vmcs_configured)  // VMX 开启则必须配置 VMCS
+        &&& (self.vmcs_configured ==> self.vmx_on)  // 只有 VMX 开启后才能配置 VMCS
+        &&& (self.vmcs_launched ==> self.vmcs_configured)  // 只有配置好的 VMCS 才会被 vmlaunch
         &&& self.guest_regs.is_valid()
         &&& (self.host_stack_top == 0 || self.host_stack_top % 16 == 0)  // 栈对齐
+        &&& self.virt_lapic.inv()
+        &&& self.vector_owner.inv()
+        &&& (self.vmcs_configured ==> host_crs_fixed(self.host_cr0, self.host_cr4))
+        &&& (self.vmcs_configured ==> self.ctls.is_valid())
+        &&& self.ctls.window_policy_holds()
+        &&& (self.vmcs_launched ==> self.vmcs_loaded)
+        &&& (self.power_on ==> (self.vmcs_loaded && self.vmx_on))  // 运行 guest 必须有当前 VMCS
+        &&& (self.vmx_on ==> self.vmx_regions_allocated())
+        &&& (self.vmx_regions_allocated() ==> self.vmx_regions_from_pool())
+        &&& (self.ctls.efer_in_vmcs() ==> self.efer_coherent())
+        &&& (self.ctls.entry_ctls.load_ia32_pat ==> self.pat_coherent())
+        &&& (self.vmcs_configured ==> self.guest_cr3_isolated())
+        &&& (self.vmcs_configured ==> self.vmcs_fields_complete())
+        &&& self.cpuid_cache.inv()  // 缓存的 CPUID 结果与硬件一致
+        &&& self.staged_guest_regs == self.intended_guest_regs@
+        &&& self.core_end == spec_core_end()
+        &&& self.core_end % 4096 == 0  // 内核镜像按页结束，栈布局的对齐只依赖缓存值
+    }
+    
+    /// 规范函数：按指令编码的寄存器号（0 = rax … 4 = rsp … 15 = r15）读取 guest 通用寄存器
+    /// 
+    /// rsp 不在 guest_regs 中，取自 guest_state.rsp
+    pub open spec fn spec_guest_gpr(&self, reg: u8) -> u64 {
+        if reg == 4 {
+            self.guest_state.rsp
+        } else if reg < 4 {
+            self.guest_regs.spec_reg_at(reg as int)
+        } else {
+            self.guest_regs.spec_reg_at(reg - 1)
+        }
+    }
+    
+    /// 规范函数：必需的 VMCS 字段都已写过
+    pub open spec fn vmcs_fields_complete(&self) -> bool {
+        required_fields().subset_of(self.written_fields@)
     }
     
     /// 规范函数：准备好进入 idle 状态
@@ -152,16 +734,254 @@ impl ArchCpu {
         &&& self.vmx_on
         &&& self.vmcs_configured
         &&& self.host_stack_top > 0
-        &&& self.host_stack_top > spec_core_end()
+        &&& self.host_stack_top > self.core_end
         &&& !self.power_on
         &&& self.host_stack_top % 16 == 0
+        &&& self.parking_is_initialized()
+    }
+    
+    /// 规范函数：该 CPU 是 BSP
+    pub open spec fn spec_is_bsp(&self) -> bool {
+        self.cpuid == 0
+    }
+    
+    /// 规范函数：VMXON region 与 VMCS region 都已分配
+    pub open spec fn vmx_regions_allocated(&self) -> bool {
+        self.vmxon_region.is_allocated() && self.vmcs_region.is_allocated()
+    }
+    
+    /// 规范函数：两个 region 是本 CPU 在 VMX region 池中的固定槽位
+    pub open spec fn vmx_regions_from_pool(&self) -> bool {
+        &&& spec_vmx_region_pool_valid()
+        &&& self.vmxon_region.spec_frame() == Some(spec_vmx_pool_frame(spec_vmxon_slot(self.cpuid)))
+        &&& self.vmcs_region.spec_frame() == Some(spec_vmx_pool_frame(spec_vmcs_slot(self.cpuid)))
+    }
+    
+    /// 规范函数：parking 内存已初始化
+    pub open spec fn parking_is_initialized(&self) -> bool {
+        self.parking_initialized
+    }
+    
+    /// 规范函数：当前激活的页表
+    pub open spec fn active_pagetable(&self) -> ActivePageTable {
+        self.active_pagetable
     }
     
     /// 规范函数：准备好启动 VM
+    /// 
+    /// 包括 guest 状态一致性（例如实模式 guest 需要 unrestricted guest），vmlaunch / vmresume 之前必须成立
     pub closed spec fn ready_for_vm_launch(&self) -> bool {
         &&& self.vmx_on
         &&& self.vmcs_configured
         &&& self.guest_regs.is_valid()
+        &&& self.guest_state_consistent()
+    }
+    
+    /// 规范函数：首次 entry 的寄存器帧已就绪
+    /// 
+    /// 首次 vmlaunch 弹出的正是期望的寄存器，guest RSP 满足 ABI 对齐；
+    /// 之后的 entry 恢复的是退出处理留下的寄存器，不再要求
+    pub closed spec fn launch_frame_ready(&self) -> bool {
+        &&& self.guest_regs == self.intended_guest_regs@
+        &&& guest_rsp_aligned(self.guest_state.rsp)
+    }
+    
+    /// 规范函数：可以调用 enter_guest
+    /// 
+    /// 汇总 entry 之前的所有静态条件；注入会改变 entry_event，因此 guest 状态一致性
+    /// （ready_for_vm_launch 的其余部分）由 enter_guest 在注入之后检查
+    pub open spec fn ready_for_entry(&self) -> bool {
+        &&& self.inv()
+        &&& self.owned_by_current()
+        &&& self.vmx_on
+        &&& self.vmcs_configured
+        &&& self.guest_regs.is_valid()
+        &&& self.vmcs_loaded
+        &&& !self.vmcs_launched ==> self.launch_frame_ready()
+    }
+    
+    /// 规范函数：guest 状态满足 VM entry 的一致性检查
+    pub open spec fn guest_state_consistent(&self) -> bool {
+        // 注入的硬件异常向量必须小于 32
+        &&& self.entry_event is Some && self.entry_event.unwrap().event_type is HardwareException
+            ==> self.entry_event.unwrap().vector < 32
+        // 实模式 guest 只能在 unrestricted guest 下运行
+        &&& self.guest_in_real_mode() ==> self.ctls.unrestricted_guest_enabled()
+        // GDTR / IDTR / LDTR 的 base 与 limit 检查（LDTR 可以不可用）
+        &&& self.guest_state.descriptor_tables_valid()
+        // guest 不在 SMM 中，不能带着 blocking by SMI 进入（SDM 26.3.1.5）
+        &&& !self.guest_state.interruptibility.blocking_by_smi
+        // CR4 与 CR0 / EFER 一致（SMEP/SMAP、PAE、PCIDE）
+        &&& spec_cr4_valid(self.guest_state.cr4, self.guest_state.cr0, self.spec_guest_efer())
+        // 非规范的 RIP / RSP 会让 VM entry 立即失败
+        &&& self.guest_addrs_canonical()
+        // PAE 分页下缓存的 PDPTE 必须合法（SDM 26.3.1.6）
+        &&& self.guest_pdptes_valid()
+        // CS / SS 的 RPL 与 DPL 满足 entry 检查（v8086、unrestricted guest 各有规则）
+        &&& self.guest_state.privilege_consistent(self.ctls.unrestricted_guest_enabled())
+    }
+    
+    /// 规范函数：guest 当前特权级（SS.DPL）
+    pub open spec fn spec_guest_cpl(&self) -> u8 {
+        self.guest_state.spec_cpl()
+    }
+    
+    pub fn guest_cpl(&self) -> (result: u8)
+        ensures
+            result == self.spec_guest_cpl(),
+    {
+        self.guest_state.cpl()
+    }
+    
+    /// 规范函数：guest 处于 PAE 分页时 PDPTE 合法
+    pub open spec fn guest_pdptes_valid(&self) -> bool {
+        self.spec_guest_paging_mode() is Pae
+            ==> spec_pdptes_valid(self.guest_state.pdptes, spec_max_phys_addr())
+    }
+    
+    /// 规范函数：成功 VM entry 之后的状态
+    /// 
+    /// 硬件投递 entry_event 后清除 VM-entry interruption-information 的 valid 位，
+    /// 同一事件不会在下一次 entry 时再次投递
+    pub open spec fn spec_after_entry(&self) -> ArchCpu {
+        ArchCpu { entry_event: None, ..*self }
+    }
+    
+    /// 规范函数：本次 entry 投递给 guest 的事件
+    pub open spec fn spec_delivered(&self) -> Option<InjectedEvent> {
+        self.entry_event
+    }
+    
+    /// 规范函数：guest RIP
+    pub open spec fn spec_guest_rip(&self) -> u64 {
+        self.guest_state.rip
+    }
+    
+    /// 规范函数：guest RSP
+    pub open spec fn spec_guest_rsp(&self) -> u64 {
+        self.guest_state.rsp
+    }
+    
+    /// 规范函数：guest 使用 5 级分页（CR4.LA57），规范地址的边界移到 bit 56
+    pub open spec fn guest_la57(&self) -> bool {
+        self.guest_state.cr4 & CR4_LA57 != 0
+    }
+    
+    /// 规范函数：guest RIP 与 RSP 都是规范地址
+    pub open spec fn guest_addrs_canonical(&self) -> bool {
+        &&& spec_is_canonical_for(self.spec_guest_rip(), self.guest_la57())
+        &&& spec_is_canonical_for(self.spec_guest_rsp(), self.guest_la57())
+    }
+    
+    /// 规范函数：guest 当前的分页模式
+    pub open spec fn spec_guest_paging_mode(&self) -> PagingMode {
+        spec_paging_mode(self.guest_state.cr0, self.guest_state.cr4, self.spec_guest_efer())
+    }
+    
+    /// 规范函数：guest 活动状态
+    pub open spec fn spec_guest_activity(&self) -> ActivityState {
+        self.guest_state.activity
+    }
+    
+    /// 规范函数：guest 处于实模式（CR0.PE 清零）
+    pub open spec fn guest_in_real_mode(&self) -> bool {
+        self.guest_state.in_real_mode()
+    }
+    
+    /// 规范函数：guest 可见的 IA32_EFER
+    pub open spec fn spec_guest_efer(&self) -> u64 {
+        self.guest_efer
+    }
+    
+    /// 规范函数：guest 看到的 IA32_FEATURE_CONTROL
+    pub open spec fn spec_feature_control(&self) -> u64 {
+        self.guest_feature_control
+    }
+    
+    /// 规范函数：guest PAT 的 MSR 可见值与 VMCS guest IA32_PAT 字段一致
+    pub open spec fn pat_coherent(&self) -> bool {
+        self.guest_pat == self.guest_state.pat
+    }
+    
+    /// 规范函数：guest EFER 的 MSR 可见值、VMCS guest IA32_EFER 字段与
+    /// IA-32e mode guest entry control 三者一致，且 EFER.LMA 与 CR0.PG 一致（LMA == LME && PG）
+    pub open spec fn efer_coherent(&self) -> bool {
+        &&& self.spec_guest_efer() == self.guest_state.efer
+        &&& self.ctls.entry_ctls.ia32e_mode_guest == efer_lma(self.guest_state.efer)
+        &&& efer_lma(self.spec_guest_efer())
+            == (efer_lme(self.spec_guest_efer()) && self.guest_state.cr0 & CR0_PG != 0)
+    }
+    
+    /// 规范函数：影子页表模式下 guest CR3 不是 host 的页表
+    /// 
+    /// EPT 下 guest CR3 是 guest 物理地址，与 host CR3 不在同一地址空间，不做要求
+    pub open spec fn guest_cr3_isolated(&self) -> bool {
+        !self.ctls.ept_enabled() ==> self.guest_state.cr3 != self.spec_host_cr3()
+    }
+    
+    /// 规范函数：本 CPU 的每 CPU 区域起始地址（栈顶向下 PER_CPU_SIZE）
+    pub open spec fn per_cpu_base(&self) -> int {
+        self.host_stack_top - PER_CPU_SIZE
+    }
+    
+    /// 规范函数：scratch 区域 [scratch_base, scratch_top)，位于每 CPU 区域底部
+    pub open spec fn scratch_base(&self) -> int {
+        self.per_cpu_base()
+    }
+    
+    pub open spec fn scratch_top(&self) -> int {
+        self.scratch_base() + SCRATCH_SIZE
+    }
+    
+    /// 规范函数：可用栈的最低地址，栈为 [stack_bottom, host_stack_top)
+    pub open spec fn stack_bottom(&self) -> int {
+        self.scratch_top()
+    }
+    
+    /// 规范函数：VMCS host-state 中记录的 CR0
+    pub open spec fn spec_host_cr0(&self) -> u64 {
+        self.host_cr0
+    }
+    
+    /// 规范函数：VMCS host-state 中记录的 CR3
+    pub open spec fn spec_host_cr3(&self) -> u64 {
+        self.host_cr3
+    }
+    
+    /// 规范函数：VMCS host-state 中记录的 CR4
+    pub open spec fn spec_host_cr4(&self) -> u64 {
+        self.host_cr4
+    }
+    
+    /// 规范函数：VM Exit 处理前后保持不变的 CPU 状态
+    pub open spec fn exit_preserved(&self, prev: &Self) -> bool {
+        &&& self.cpuid == prev.cpuid
+        &&& self.vmx_on == prev.vmx_on
+        &&& self.vmcs_configured == prev.vmcs_configured
+        &&& self.power_on == prev.power_on
+        &&& self.vmcs_loaded == prev.vmcs_loaded
+        &&& self.vmcs_launched == prev.vmcs_launched
+        &&& self.host_stack_top == prev.host_stack_top
+        &&& self.spec_host_cr0() == prev.spec_host_cr0()
+        &&& self.spec_host_cr3() == prev.spec_host_cr3()
+        &&& self.spec_host_cr4() == prev.spec_host_cr4()
+        &&& self.exit_counts_grew(prev)
+        // RSP 在 VMCS 中，帧中的 rsp 槽不属于任何寄存器，处理函数从不写它
+        &&& self.guest_regs._unused_rsp == prev.guest_regs._unused_rsp
+    }
+    
+    /// 规范函数：某个退出原因已处理的次数（超出范围的编号恒为 0）
+    pub open spec fn exit_count(&self, reason: u32) -> u64 {
+        if (reason as int) < NUM_EXIT_REASONS {
+            self.exit_counts[reason as int]
+        } else {
+            0
+        }
+    }
+    
+    /// 规范函数：退出计数只增不减
+    pub open spec fn exit_counts_grew(&self, prev: &Self) -> bool {
+        forall|i: int| 0 <= i < NUM_EXIT_REASONS ==> prev.exit_counts[i] <= self.exit_counts[i]
     }
 }
 
@@ -172,12 +992,15 @@ impl ArchCpu {
             cpuid < MAX_CPU_NUM,
         ensures
             result.inv(),
-            result.cpuid == spec_this_cpu_id(),
+            result.cpuid == cpuid,
+            (result.virt_lapic.apic_base & APIC_BASE_BSP != 0) == result.spec_is_bsp(),
             !result.vmx_on,
             !result.vmcs_configured,
             !result.power_on,
+            !result.vmx_regions_allocated(),
+            result.guest_regs == result.intended_guest_regs@,
     {
-        let cpu_id = this_cpu_id();
+        let end = core_end();
         
         let cpu = ArchCpu {
             guest_regs: GeneralRegisters {
@@ -187,20 +1010,51 @@ impl ArchCpu {
                 r12: 0, r13: 0, r14: 0, r15: 0,
             },
             host_stack_top: 0,
-            cpuid: cpu_id,
+            cpuid,
+            core_end: end,
             power_on: false,
-            virt_lapic: VirtLocalApic::new(),
+            virt_lapic: VirtLocalApic::new(cpuid == 0),
             vmx_on: false,
             vmcs_configured: false,
+            vmcs_loaded: false,
+            vmcs_launched: false,
             vmcs_revision_id: 0,
             vmxon_region: VmxRegion::fake_init(),
             vmcs_region: VmxRegion::fake_init(),
-            vm_launch_guest_regs: GeneralRegisters {
+            staged_guest_regs: GeneralRegisters {
                 rax: 0, rcx: 0, rdx: 0, rbx: 0,
                 _unused_rsp: 0, rbp: 0, rsi: 0, rdi: 0,
                 r8: 0, r9: 0, r10: 0, r11: 0,
                 r12: 0, r13: 0, r14: 0, r15: 0,
             },
+            guest_state: GuestState::new(),
+            guest_efer: 0,
+            guest_pat: PAT_DEFAULT,
+            guest_feature_control: 0,
+            tsc_offset: 0,
+            entry_event: None,
+            pending_events: PendingEvents::new(),
+            vector_owner: VectorOwnership::new(),
+            ctls: VmcsControls::new(),
+            host_cr0: 0,
+            host_cr3: 0,
+            host_cr4: 0,
+            mwait_break_on_interrupt: false,
+            interrupt_window_requested: false,
+            nmi_window_requested: false,
+            triple_fault_policy: TripleFaultPolicy::Fatal,
+            exit_counts: [0; NUM_EXIT_REASONS],
+            cpuid_cache: CpuidCache::new(),
+            parking_initialized: false,
+            active_pagetable: ActivePageTable::Host,
+            intended_guest_regs: Ghost(GeneralRegisters {
+                rax: 0, rcx: 0, rdx: 0, rbx: 0,
+                _unused_rsp: 0, rbp: 0, rsi: 0, rdi: 0,
+                r8: 0, r9: 0, r10: 0, r11: 0,
+                r12: 0, r13: 0, r14: 0, r15: 0,
+            }),
+            written_fields: Ghost(Set::empty()),
+            guest_cr3: Ghost(0),
         };
         
         proof {
@@ -210,33 +1064,786 @@ impl ArchCpu {
         cpu
     }
     
-    /// 读取 CR 寄存器
-    #[verifier::external_body]
-    pub fn cr(&self, cr_idx: usize) -> (result: usize)
-        requires
-            self.inv(),
-            self.vmcs_configured,
+    /// 规范函数：当前物理 CPU 拥有该 ArchCpu
+    /// 
+    /// cpuid 在构造时取自 this_cpu_id，之后不再改变，即为唯一的所有者
+    pub open spec fn owned_by_current(&self) -> bool {
+        self.cpuid == spec_this_cpu_id()
+    }
+    
+    /// 检查调用者运行在所有者 CPU 上
+    /// 
+    /// 每个公开的 &mut self 入口开头调用，ArchCpu 不能被其它 CPU 借用
+    pub fn assert_owned_by_current(&self)
+        requires
+            self.owned_by_current(),
+    {
+        let id = this_cpu_id();
+        assert(id == self.cpuid);
+    }
+    
+    /// 是否是 BSP
+    pub fn is_bsp(&self) -> (result: bool)
+        ensures
+            result == self.spec_is_bsp(),
+    {
+        self.cpuid == 0
+    }
+    
+    /// 设置 guest 启动时的通用寄存器，并记录为期望值
+    /// 
+    /// 只写入 staged_guest_regs，vmx_launch 读取的帧由 prepare_launch_frame 填充
+    pub fn set_guest_regs(&mut self, regs: GeneralRegisters)
+        requires
+            old(self).inv(),
+        ensures
+            self.inv(),
+            *self == (ArchCpu {
+                staged_guest_regs: regs,
+                intended_guest_regs: Ghost(regs),
+                ..*old(self)
+            }),
+    {
+        self.intended_guest_regs = Ghost(regs);
+        self.staged_guest_regs = regs;
+    }
+    
+    /// 把期望的 guest 寄存器拷入 vmx_launch 弹栈读取的帧
+    /// 
+    /// guest_regs 位于 ArchCpu 偏移 0 处，布局与 restore_regs_from_stack! 的弹栈顺序一致
+    pub fn prepare_launch_frame(&mut self)
+        requires
+            old(self).inv(),
+        ensures
+            self.inv(),
+            *self == (ArchCpu { guest_regs: old(self).staged_guest_regs, ..*old(self) }),
+            self.guest_regs == self.intended_guest_regs@,
+    {
+        self.guest_regs = self.staged_guest_regs;
+    }
+    
+    /// 读取 CR 寄存器
+    #[verifier::external_body]
+    pub fn cr(&self, cr_idx: usize) -> (result: usize)
+        requires
+            self.inv(),
+            self.vmcs_configured,
     {
         // todo: 从 VMCS 读取 guest CR 寄存器
         0
     }
     
     /// 推进 guest RIP
-    #[verifier::external_body]
+    /// 
+    /// 只修改 guest RIP，溢出时模型状态不变
     pub fn advance_guest_rip(&mut self, instr_len: u8) -> (result: Result<(), ()>)
         requires
             old(self).inv(),
             old(self).vmcs_configured,
         ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            result.is_ok() == (old(self).guest_state.rip <= u64::MAX - instr_len),
+            result.is_ok() ==> *self == (ArchCpu {
+                guest_state: GuestState {
+                    rip: (old(self).guest_state.rip + instr_len) as u64,
+                    ..old(self).guest_state
+                },
+                ..*old(self)
+            }),
+            result.is_err() ==> *self == *old(self),
+    {
+        if self.guest_state.rip > u64::MAX - instr_len as u64 {
+            return Err(());
+        }
+        self.guest_state.rip = self.guest_state.rip + instr_len as u64;
+        vmcs_write_guest_rip(self.guest_state.rip);
+        Ok(())
+    }
+    
+    /// 规范函数：为下次 VM entry 写入事件后的状态
+    /// 
+    /// 事件投递本身结束 STI / MOV SS 中断影子。NMI 不同：MOV SS 阻塞会推迟 NMI，
+    /// NMI 只在没有 MOV SS 阻塞时注入（VM entry 也如此检查），因此投递时只清除 STI 阻塞
+    pub open spec fn spec_with_injected(&self, event: InjectedEvent) -> ArchCpu {
+        let intr = self.guest_state.interruptibility;
+        ArchCpu {
+            entry_event: Some(event),
+            guest_state: GuestState {
+                interruptibility: Interruptibility {
+                    blocking_by_sti: false,
+                    blocking_by_mov_ss: if event.event_type is Nmi { intr.blocking_by_mov_ss } else { false },
+                    ..intr
+                },
+                ..self.guest_state
+            },
+            ..*self
+        }
+    }
+    
+    /// 规范函数：退出时的那条指令产生 fault 之后的状态
+    /// 
+    /// 本次 entry 已有事件（NMI、外部中断或前一条指令的陷阱）时它的优先级高于该指令的 fault（SDM 6.9），
+    /// 保留原事件而不注入 fault；RIP 未推进，原事件的处理程序返回后重新执行该指令会再次产生 fault
+    pub open spec fn spec_with_fault(&self, vector: u8, error_code: Option<u32>) -> ArchCpu {
+        if self.entry_event is Some {
+            *self
+        } else {
+            self.spec_with_injected(InjectedEvent::spec_exception(vector, error_code))
+        }
+    }
+    
+    /// 向 guest 注入该指令的 fault（下次 VM entry 时写入 interruption-information），同时结束中断影子
+    /// 
+    /// 不覆盖已经写入的 entry 事件，见 spec_with_fault
+    pub fn inject_exception(&mut self, vector: u8, error_code: Option<u32>)
+        requires
+            old(self).inv(),
+        ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            *self == old(self).spec_with_fault(vector, error_code),
+            old(self).entry_event is Some ==> self.entry_event == old(self).entry_event,
+            old(self).entry_event is None ==> {
+                &&& !self.guest_state.interruptibility.blocking_by_sti
+                &&& !self.guest_state.interruptibility.blocking_by_mov_ss
+            },
+    {
+        if self.entry_event.is_some() {
+            return;
+        }
+        self.entry_event = Some(InjectedEvent {
+            event_type: EventType::HardwareException,
+            vector,
+            error_code,
+        });
+        self.guest_state.interruptibility.blocking_by_sti = false;
+        self.guest_state.interruptibility.blocking_by_mov_ss = false;
+    }
+    
+    /// 规范函数：模拟完一条指令（RIP 已推进）后，RFLAGS.TF 引起的单步 #DB
+    /// 
+    /// STI / MOV SS 中断影子内或本次 entry 已有事件时不能直接注入，改为置 pending debug exceptions 的 BS 位，
+    /// 由硬件在影子结束后投递（硬件同时更新 DR6）；否则直接注入 #DB 并由我们置 DR6.BS
+    pub open spec fn spec_single_step_trap(&self) -> ArchCpu {
+        let intr = self.guest_state.interruptibility;
+        if self.guest_state.rflags & RFLAGS_TF == 0 {
+            *self
+        } else if intr.blocking_by_sti || intr.blocking_by_mov_ss || self.entry_event is Some {
+            ArchCpu { guest_state: GuestState { pending_dbg_bs: true, ..self.guest_state }, ..*self }
+        } else {
+            ArchCpu {
+                entry_event: Some(InjectedEvent::spec_exception(EXCEPTION_DB, None)),
+                guest_state: GuestState { dr6: self.guest_state.dr6 | DR6_BS, ..self.guest_state },
+                ..*self
+            }
+        }
+    }
+    
+    /// 规范函数：退出时的那条指令执行完毕（由我们模拟并推进 RIP）之后的状态
+    /// 
+    /// 退出时的 STI / MOV SS 阻塞描述的是该指令之前的边界，只覆盖这一条指令；
+    /// 它退休后影子结束，不能一直保留到下一次 entry 之后
+    pub open spec fn spec_retired(&self) -> ArchCpu {
+        ArchCpu {
+            guest_state: GuestState {
+                interruptibility: Interruptibility {
+                    blocking_by_sti: false,
+                    blocking_by_mov_ss: false,
+                    ..self.guest_state.interruptibility
+                },
+                ..self.guest_state
+            },
+            ..*self
+        }
+    }
+    
+    /// 模拟的指令退休：清除 STI / MOV SS 中断影子
+    fn clear_interrupt_shadow(&mut self)
+        requires
+            old(self).inv(),
+        ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            *self == old(self).spec_retired(),
+    {
+        self.guest_state.interruptibility.blocking_by_sti = false;
+        self.guest_state.interruptibility.blocking_by_mov_ss = false;
+    }
+    
+    /// 单步陷阱：在 RIP 推进之后调用
+    fn deliver_single_step(&mut self)
+        requires
+            old(self).inv(),
+        ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            *self == old(self).spec_single_step_trap(),
+    {
+        if self.guest_state.rflags & RFLAGS_TF == 0 {
+            return;
+        }
+        let intr = self.guest_state.interruptibility;
+        if intr.blocking_by_sti || intr.blocking_by_mov_ss || self.entry_event.is_some() {
+            self.guest_state.pending_dbg_bs = true;
+        } else {
+            self.inject_exception(EXCEPTION_DB, None);
+            self.guest_state.dr6 = self.guest_state.dr6 | DR6_BS;
+        }
+    }
+    
+    /// 执行处理函数返回的模拟结果：推进 RIP、注入异常或原样返回 guest
+    /// 
+    /// vmexit_handler 在每个处理函数之后只在这里修改 RIP / interruption-information；
+    /// 推进 RIP 即该指令退休：先结束中断影子，再按 RFLAGS.TF 投递单步 #DB
+    pub fn apply_emulation_result(&mut self, r: EmulationResult)
+        requires
+            old(self).inv(),
+            old(self).vmcs_configured,
+        ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            r matches EmulationResult::Advance(len) ==> {
+                &&& old(self).guest_state.rip <= u64::MAX - len ==> *self == (ArchCpu {
+                    guest_state: GuestState {
+                        rip: (old(self).guest_state.rip + len) as u64,
+                        ..old(self).guest_state
+                    },
+                    ..*old(self)
+                }).spec_retired().spec_single_step_trap()
+                &&& old(self).guest_state.rip > u64::MAX - len ==> *self == *old(self)
+            },
+            r matches EmulationResult::InjectFault { vector, error_code }
+                ==> *self == old(self).spec_with_fault(vector, error_code),
+            r is Reenter ==> *self == *old(self),
+    {
+        match r {
+            EmulationResult::Advance(len) => {
+                // RIP 溢出时不推进（状态不变），也不产生单步陷阱
+                if self.advance_guest_rip(len).is_ok() {
+                    self.clear_interrupt_shadow();
+                    self.deliver_single_step();
+                }
+            },
+            EmulationResult::InjectFault { vector, error_code } => {
+                self.inject_exception(vector, error_code);
+            },
+            EmulationResult::Reenter => {},
+        }
+    }
+    
+    pub fn guest_gpr(&self, reg: u8) -> (result: u64)
+        requires
+            reg < 16,
+        ensures
+            result == self.spec_guest_gpr(reg),
+    {
+        if reg == 4 {
+            self.guest_state.rsp
+        } else if reg < 4 {
+            self.guest_regs.reg_at(reg as usize)
+        } else {
+            self.guest_regs.reg_at((reg - 1) as usize)
+        }
+    }
+    
+    /// 按指令编码的寄存器号写 guest 通用寄存器，只修改该寄存器
+    /// 
+    /// 寄存器号 4（RSP）写入 VMCS guest RSP 字段，而不是帧中的 _unused_rsp 槽
+    pub fn set_guest_gpr(&mut self, reg: u8, val: u64)
+        requires
+            old(self).inv(),
+            reg < 16,
+        ensures
+            self.inv(),
+            self.guest_regs._unused_rsp == old(self).guest_regs._unused_rsp,
+            reg == 4 ==> self.guest_state.rsp == val && self.guest_regs == old(self).guest_regs,
+            self.spec_guest_gpr(reg) == val,
+            forall|r: u8| r < 16 && r != reg ==> #[trigger] self.spec_guest_gpr(r) == old(self).spec_guest_gpr(r),
+            self.guest_state == (GuestState { rsp: self.guest_state.rsp, ..old(self).guest_state }),
+            *self == (ArchCpu {
+                guest_regs: self.guest_regs,
+                guest_state: self.guest_state,
+                ..*old(self)
+            }),
+    {
+        if reg == 4 {
+            self.guest_state.rsp = val;
+        } else if reg < 4 {
+            self.guest_regs.set_reg_at(reg as usize, val);
+        } else {
+            self.guest_regs.set_reg_at((reg - 1) as usize, val);
+        }
+    }
+    
+    /// 向 guest 注入 NMI
+    /// 
+    /// 已有 NMI 阻塞、MOV SS 阻塞（或本次 entry 已有待注入事件）时只记录为 pending，
+    /// 不会在 IRET 之前注入第二个 NMI；注入时清除 STI 阻塞
+    pub fn inject_nmi(&mut self)
+        requires
+            old(self).inv(),
+        ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            ({
+                let intr = old(self).guest_state.interruptibility;
+                &&& (intr.blocking_by_nmi || intr.blocking_by_mov_ss || old(self).entry_event is Some)
+                    ==> *self == (ArchCpu {
+                        pending_events: PendingEvents { nmi: true, ..old(self).pending_events },
+                        ..*old(self)
+                    })
+                &&& !(intr.blocking_by_nmi || intr.blocking_by_mov_ss || old(self).entry_event is Some)
+                    ==> *self == (ArchCpu {
+                        guest_state: GuestState {
+                            interruptibility: Interruptibility {
+                                blocking_by_nmi: true,
+                                blocking_by_sti: false,
+                                ..intr
+                            },
+                            ..old(self).guest_state
+                        },
+                        ..old(self).spec_with_injected(InjectedEvent::spec_nmi())
+                    })
+            }),
+    {
+        if self.guest_state.interruptibility.blocking_by_nmi
+            || self.guest_state.interruptibility.blocking_by_mov_ss
+            || self.entry_event.is_some() {
+            self.pending_events.nmi = true;
+            return;
+        }
+        self.entry_event = Some(InjectedEvent::nmi());
+        self.guest_state.interruptibility.blocking_by_nmi = true;
+        self.guest_state.interruptibility.blocking_by_sti = false;
+    }
+    
+    /// 规范函数：有待投递的 NMI（每次最多一个）
+    pub open spec fn nmi_pending(&self) -> bool {
+        self.pending_events.nmi
+    }
+    
+    /// 请求向 guest 投递 NMI，已有待投递的 NMI 时合并为一个（与硬件一致）
+    pub fn request_nmi(&mut self)
+        requires
+            old(self).inv(),
+        ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            self.nmi_pending(),
+            *self == (ArchCpu {
+                pending_events: PendingEvents { nmi: true, ..old(self).pending_events },
+                ..*old(self)
+            }),
+            old(self).nmi_pending() ==> *self == *old(self),
+    {
+        self.pending_events.nmi = true;
+    }
+    
+    /// entry 前检查待注入集合与 IRR，尝试注入
+    /// 
+    /// 待注入集合（异常 > NMI > 被打断的中断）优先，经 inject_pending_event 注入；
+    /// 最高优先级是 NMI 但仍被 NMI / MOV SS 阻塞时请求 NMI 窗口退出。
+    /// 外部中断只有本次 entry 没有其他事件、最高请求向量高于 TPR 与在服务向量、guest 可中断时才注入，
+    /// 并把该向量从 IRR 移到 ISR；guest 不可中断时改为请求中断窗口退出
+    pub fn maybe_inject_pending(&mut self) -> (decision: InjectionDecision)
+        requires
+            old(self).inv(),
+        ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            self.guest_regs == old(self).guest_regs,
+            self.intended_guest_regs == old(self).intended_guest_regs,
+            self.guest_state == (GuestState {
+                interruptibility: self.guest_state.interruptibility,
+                ..old(self).guest_state
+            }),
+            !(decision is InjectedPending) ==> self.guest_state == old(self).guest_state,
+            self.virt_lapic.tpr == old(self).virt_lapic.tpr,
+            decision is InjectedPending ==> {
+                let next = old(self).pending_events.next_event_to_inject();
+                &&& old(self).entry_event is None
+                &&& next is Some
+                &&& self.entry_event == Some(next.unwrap().spec_to_injected())
+                &&& self.pending_events == old(self).pending_events.spec_without_next()
+                &&& !self.guest_state.interruptibility.blocking_by_sti
+                &&& next.unwrap() is Nmi ==> self.guest_state.interruptibility.blocking_by_nmi
+                &&& !(next.unwrap() is Nmi) ==> self.guest_state.interruptibility.blocking_by_nmi
+                    == old(self).guest_state.interruptibility.blocking_by_nmi
+            },
+            ({
+                let next = old(self).pending_events.next_event_to_inject();
+                let intr = old(self).guest_state.interruptibility;
+                old(self).entry_event is None && next is Some
+                    && !(next == Some(PendingEvent::Nmi) && (intr.blocking_by_nmi || intr.blocking_by_mov_ss))
+                    ==> decision is InjectedPending
+            }),
+            decision is OpenNmiWindow ==> {
+                &&& old(self).pending_events.next_event_to_inject() == Some(PendingEvent::Nmi)
+                &&& old(self).guest_state.interruptibility.blocking_by_nmi
+                    || old(self).guest_state.interruptibility.blocking_by_mov_ss
+                &&& *self == (ArchCpu { nmi_window_requested: true, ..*old(self) })
+            },
+            decision matches InjectionDecision::Injected(v) ==> {
+                &&& old(self).entry_event is None
+                &&& old(self).virt_lapic.spec_software_enabled()
+                &&& old(self).guest_state.interruptible()
+                &&& old(self).virt_lapic.is_highest_pending(v)
+                &&& spec_interrupt_deliverable(v, old(self).virt_lapic.tpr)
+                &&& old(self).virt_lapic.above_in_service(v)
+                &&& self.entry_event == Some(InjectedEvent {
+                    event_type: EventType::ExternalInterrupt,
+                    vector: v,
+                    error_code: None,
+                })
+                &&& self.virt_lapic.irr@ == old(self).virt_lapic.irr@.update(v as int, false)
+                &&& self.virt_lapic.isr@ == old(self).virt_lapic.isr@.update(v as int, true)
+            },
+            decision is OpenInterruptWindow ==> {
+                &&& !old(self).guest_state.interruptible()
+                &&& *self == (ArchCpu { interrupt_window_requested: true, ..*old(self) })
+            },
+            decision is Nothing ==> *self == *old(self),
+    {
+        if self.entry_event.is_none() && self.pending_events.next_event().is_some() {
+            let nmi_next = match self.pending_events.next_event() {
+                Some(PendingEvent::Nmi) => true,
+                _ => false,
+            };
+            if nmi_next && (self.guest_state.interruptibility.blocking_by_nmi
+                || self.guest_state.interruptibility.blocking_by_mov_ss) {
+                self.nmi_window_requested = true;
+                return InjectionDecision::OpenNmiWindow;
+            }
+            self.inject_pending_event();
+            return InjectionDecision::InjectedPending;
+        }
+        let vector = match self.virt_lapic.highest_pending() {
+            Some(v) => v,
+            None => return InjectionDecision::Nothing,
+        };
+        // 有效优先级 PPR 取 TPR 与最高在服务向量中较高者
+        if self.entry_event.is_some() || !interrupt_deliverable(vector, self.virt_lapic.tpr)
+            || !self.virt_lapic.is_above_in_service(vector) {
+            return InjectionDecision::Nothing;
+        }
+        let interruptible = self.guest_state.rflags & RFLAGS_IF != 0
+            && !self.guest_state.interruptibility.blocking_by_sti
+            && !self.guest_state.interruptibility.blocking_by_mov_ss;
+        if !interruptible {
+            self.interrupt_window_requested = true;
+            return InjectionDecision::OpenInterruptWindow;
+        }
+        self.virt_lapic.accept(vector);
+        self.entry_event = Some(InjectedEvent {
+            event_type: EventType::ExternalInterrupt,
+            vector,
+            error_code: None,
+        });
+        InjectionDecision::Injected(vector)
+    }
+    
+    /// 规范函数：本次 entry 新注入的外部中断通过了虚拟 LAPIC 的优先级检查
+    /// 
+    /// 向量须高于 TPR；来自 IRR 的向量还须高于在服务向量。被打断后重新排队的中断已在 ISR 中，不再与 ISR 比较
+    pub open spec fn spec_injected_interrupt_permitted(&self, prev: &Self) -> bool {
+        prev.entry_event is None && self.entry_event matches Some(e) && e.event_type is ExternalInterrupt ==> {
+            &&& spec_interrupt_deliverable(e.vector, prev.virt_lapic.tpr)
+            &&& prev.pending_events.interrupt is None ==> prev.virt_lapic.above_in_service(e.vector)
+        }
+    }
+    
+    /// guest 重新可接收事件时（IRET、中断窗口、TPR 低于阈值退出）按优先级检查注入事件
+    /// 
+    /// 经 maybe_inject_pending 注入，IRR 中的中断须高于 TPR 与在服务向量；
+    /// 重新排队的中断优先级不高于 TPR 时保持等待，也不让 IRR 中的中断越过它
+    fn inject_permitted_event(&mut self)
+        requires
+            old(self).inv(),
+        ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            self.guest_state.rip == old(self).guest_state.rip,
+            self.virt_lapic.tpr == old(self).virt_lapic.tpr,
+            self.spec_injected_interrupt_permitted(old(self)),
+            ({
+                let intr = old(self).guest_state.interruptibility;
+                old(self).entry_event is None && !intr.blocking_by_nmi && !intr.blocking_by_mov_ss
+                    && old(self).pending_events.next_event_to_inject() == Some(PendingEvent::Nmi) ==> {
+                    &&& self.entry_event == Some(InjectedEvent::spec_nmi())
+                    &&& self.guest_state.interruptibility.blocking_by_nmi
+                    &&& !self.pending_events.nmi
+                }
+            }),
+            self.guest_state.interruptibility.blocking_by_nmi
+                ==> old(self).guest_state.interruptibility.blocking_by_nmi
+                    || self.entry_event == Some(InjectedEvent::spec_nmi()),
+    {
+        if let Some(PendingEvent::Interrupt { vector }) = self.pending_events.next_event() {
+            if !interrupt_deliverable(vector, self.virt_lapic.tpr) {
+                return;
+            }
+        }
+        let _ = self.maybe_inject_pending();
+    }
+    
+    /// 按优先级把下一个待注入事件写入本次 VM entry
+    /// 
+    /// 本次 entry 已有事件时不覆盖；最高优先级是 NMI 但仍处于 NMI 或 MOV SS 阻塞时保持等待，
+    /// 不让低优先级的中断越过它。注入的事件按 spec_with_injected 结束中断影子
+    pub fn inject_pending_event(&mut self)
+        requires
+            old(self).inv(),
+        ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            self.guest_state.rip == old(self).guest_state.rip,
+            *self == (ArchCpu {
+                entry_event: self.entry_event,
+                pending_events: self.pending_events,
+                guest_state: GuestState {
+                    interruptibility: self.guest_state.interruptibility,
+                    ..old(self).guest_state
+                },
+                ..*old(self)
+            }),
+            ({
+                let next = old(self).pending_events.next_event_to_inject();
+                let intr = old(self).guest_state.interruptibility;
+                if old(self).entry_event is Some || next is None
+                    || (next == Some(PendingEvent::Nmi) && (intr.blocking_by_nmi || intr.blocking_by_mov_ss))
+                {
+                    *self == *old(self)
+                } else {
+                    &&& self.entry_event == Some(next.unwrap().spec_to_injected())
+                    &&& self.pending_events == old(self).pending_events.spec_without_next()
+                    &&& !self.guest_state.interruptibility.blocking_by_sti
+                    &&& !self.guest_state.interruptibility.blocking_by_mov_ss
+                    &&& (next.unwrap() is Nmi ==> self.guest_state.interruptibility.blocking_by_nmi)
+                    &&& (!(next.unwrap() is Nmi) ==> self.guest_state
+                        == old(self).spec_with_injected(next.unwrap().spec_to_injected()).guest_state)
+                }
+            }),
+    {
+        if self.entry_event.is_some() {
+            return;
+        }
+        let next = self.pending_events.next_event();
+        if next.is_none() {
+            return;
+        }
+        let is_nmi = match next {
+            Some(PendingEvent::Nmi) => true,
+            _ => false,
+        };
+        if is_nmi && (self.guest_state.interruptibility.blocking_by_nmi
+            || self.guest_state.interruptibility.blocking_by_mov_ss) {
+            // 等待 IRET 解除阻塞，或 MOV SS 影子结束
+            return;
+        }
+        let event = self.pending_events.take_next().unwrap();
+        self.entry_event = Some(event.to_injected());
+        self.guest_state.interruptibility.blocking_by_sti = false;
+        if is_nmi {
+            self.guest_state.interruptibility.blocking_by_nmi = true;
+        } else {
+            self.guest_state.interruptibility.blocking_by_mov_ss = false;
+        }
+    }
+}
+
+impl ArchCpu {
+    /// 模拟 guest 的内存写（如 EPT 捕获的 MMIO store）
+    /// 
+    /// gpa 按 size 对齐，因此访问不会跨页，一次翻译即可覆盖
+    pub fn guest_mem_write(&mut self, gpa: u64, val: u64, size: u8) -> (result: Result<(), EmulationResult>)
+        requires
+            old(self).inv(),
+            is_valid_access_size(size),
+            gpa % (size as u64) == 0,
+        ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            result.is_ok() <==> (spec_gpa_in_range(gpa)
+                && spec_ept_translate(gpa) is Some
+                && spec_ept_translate(gpa).unwrap().write),
+            result.is_err() ==> result == Err::<(), EmulationResult>(EmulationResult::spec_gp()),
+    {
+        if !gpa_in_range(gpa) {
+            return Err(EmulationResult::gp());
+        }
+        match ept_translate(gpa) {
+            Some(mapping) => {
+                if !mapping.write {
+                    // 只读映射
+                    return Err(EmulationResult::gp());
+                }
+                host_phys_write(mapping.hpa, val, size);
+                Ok(())
+            },
+            None => {
+                // 未映射
+                Err(EmulationResult::gp())
+            },
+        }
+    }
+    
+    /// 模拟 guest 的内存读（与 guest_mem_write 对应）
+    /// 
+    /// 一次翻译只覆盖一页，因此禁止跨页读；1/2/4 字节的结果零扩展到 u64
+    pub fn guest_mem_read(&self, gpa: u64, size: u8) -> (result: Result<u64, EmulationResult>)
+        requires
+            self.inv(),
+            is_valid_access_size(size),
+            gpa % (size as u64) == 0,
+            within_page(gpa, size),
+        ensures
+            result.is_ok() <==> spec_guest_readable(gpa),
             result.is_ok() ==> {
-                self.inv() &&
-                self.cpuid == old(self).cpuid &&
-                self.vmx_on == old(self).vmx_on &&
-                self.vmcs_configured == old(self).vmcs_configured
+                &&& result.unwrap() == spec_host_phys_read(spec_ept_translate(gpa).unwrap().hpa, size)
+                &&& zero_extended(result.unwrap(), size)
             },
+            result.is_err() ==> result == Err::<u64, EmulationResult>(EmulationResult::spec_gp()),
     {
-        // VMCS 操作：RIP += instr_len
-        Ok(())
+        if !gpa_in_range(gpa) {
+            return Err(EmulationResult::gp());
+        }
+        match ept_translate(gpa) {
+            Some(mapping) => {
+                if !mapping.read {
+                    return Err(EmulationResult::gp());
+                }
+                Ok(host_phys_read(mapping.hpa, size))
+            },
+            None => Err(EmulationResult::gp()),
+        }
+    }
+    
+    /// 规范函数：取指处第 offset 字节的线性地址 CS.base + RIP + offset
+    /// 
+    /// 64 位代码段（EFER.LMA 且 CS.L）忽略 CS.base，回绕或非规范时为 None；其余模式按 32 位地址回绕
+    pub open spec fn spec_fetch_linear(&self, offset: u64) -> Option<u64> {
+        let rip = self.guest_state.rip;
+        if efer_lma(self.spec_guest_efer()) && self.guest_state.cs.access_rights & SEG_AR_L != 0 {
+            let la = (rip + offset) as u64;
+            if rip + offset <= u64::MAX && spec_is_canonical_for(la, self.guest_la57()) { Some(la) } else { None }
+        } else {
+            Some((((self.guest_state.cs.base & 0xffff_ffff) + (rip & 0xffff_ffff) + offset) as u64) & 0xffff_ffff)
+        }
+    }
+    
+    pub fn fetch_linear(&self, offset: u8) -> (result: Option<u64>)
+        requires
+            offset <= MAX_INSTR_LEN,
+        ensures
+            result == self.spec_fetch_linear(offset as u64),
+    {
+        let rip = self.guest_state.rip;
+        if self.guest_efer & EFER_LMA != 0 && self.guest_state.cs.access_rights & SEG_AR_L != 0 {
+            if rip > u64::MAX - offset as u64 {
+                return None;
+            }
+            let la = rip + offset as u64;
+            if !is_canonical_for(la, self.guest_state.cr4 & CR4_LA57 != 0) {
+                return None;
+            }
+            Some(la)
+        } else {
+            let base = self.guest_state.cs.base & 0xffff_ffff;
+            let ip = rip & 0xffff_ffff;
+            proof {
+                let cs_base = self.guest_state.cs.base;
+                assert(base <= 0xffff_ffff && ip <= 0xffff_ffff) by (bit_vector)
+                    requires base == cs_base & 0xffff_ffff, ip == rip & 0xffff_ffff;
+            }
+            Some((base + ip + offset as u64) & 0xffff_ffff)
+        }
+    }
+    
+    /// 规范函数：取指处第 offset 字节的 guest 物理地址
+    /// 
+    /// 开启分页时线性地址经 guest 页表（VMCS 中的 CR3）翻译，否则线性地址即物理地址
+    pub open spec fn spec_fetch_gpa(&self, offset: u64) -> Option<u64> {
+        match self.spec_fetch_linear(offset) {
+            None => None,
+            Some(la) => if self.guest_state.cr0 & CR0_PG == 0 {
+                Some(la)
+            } else {
+                spec_guest_fetch_walk(self.guest_state.cr3, self.guest_state.cr4,
+                    self.spec_guest_efer(), self.spec_guest_cpl(), la)
+            },
+        }
+    }
+    
+    pub fn fetch_gpa(&self, offset: u8) -> (result: Option<u64>)
+        requires
+            offset <= MAX_INSTR_LEN,
+        ensures
+            result == self.spec_fetch_gpa(offset as u64),
+    {
+        let la = match self.fetch_linear(offset) {
+            Some(la) => la,
+            None => return None,
+        };
+        if self.guest_state.cr0 & CR0_PG == 0 {
+            return Some(la);
+        }
+        guest_fetch_walk(self.guest_state.cr3, self.guest_state.cr4, self.guest_efer, self.guest_cpl(), la)
+    }
+    
+    /// 取 guest CS:RIP 处的指令字节（供指令模拟器解码）
+    /// 
+    /// 每个字节的线性地址单独经 guest 页表翻译为 gpa，再经 guest_mem_read 走 EPT，
+    /// 因此跨页时两页分别检查；任一字节线性地址回绕或非规范、gpa 超出 MAXPHYADDR、未映射或不可读都注入 #GP
+    pub fn fetch_guest_instr(&self, max_len: u8) -> (result: Result<([u8; 15], u8), EmulationResult>)
+        requires
+            self.inv(),
+            max_len <= MAX_INSTR_LEN,
+        ensures
+            result.is_ok() ==> {
+                let (bytes, len) = result.unwrap();
+                &&& len == max_len
+                &&& forall|j: int| 0 <= j < len ==> {
+                    let gpa = self.spec_fetch_gpa(j as u64).unwrap();
+                    &&& self.spec_fetch_gpa(j as u64) is Some
+                    &&& spec_guest_readable(gpa)
+                    &&& bytes@[j] == spec_host_phys_read(spec_ept_translate(gpa).unwrap().hpa, 1) as u8
+                }
+            },
+            result.is_err() ==> result == Err::<([u8; 15], u8), EmulationResult>(EmulationResult::spec_gp()),
+    {
+        let mut bytes: [u8; 15] = [0u8; 15];
+        let mut len: u8 = 0;
+        
+        while len < max_len
+            invariant
+                self.inv(),
+                len <= max_len,
+                max_len <= MAX_INSTR_LEN,
+                forall|j: int| 0 <= j < len ==> {
+                    let gpa = self.spec_fetch_gpa(j as u64).unwrap();
+                    &&& self.spec_fetch_gpa(j as u64) is Some
+                    &&& spec_guest_readable(gpa)
+                    &&& bytes@[j] == spec_host_phys_read(spec_ept_translate(gpa).unwrap().hpa, 1) as u8
+                },
+            decreases max_len - len,
+        {
+            let gpa = match self.fetch_gpa(len) {
+                Some(gpa) => gpa,
+                None => return Err(EmulationResult::gp()),
+            };
+            match self.guest_mem_read(gpa, 1) {
+                Ok(b) => {
+                    bytes.set(len as usize, b as u8);
+                },
+                Err(e) => {
+                    return Err(e);
+                },
+            }
+            len = len + 1;
+        }
+        
+        Ok((bytes, len))
     }
 }
 
@@ -289,24 +1896,72 @@ impl ArchCpu {
         }
     }
     
+    /// 从 VMX region 池取本 CPU 的 VMXON / VMCS region
+    fn alloc_vmx_regions(&mut self)
+        requires
+            old(self).inv(),
+        ensures
+            self.inv(),
+            self.vmx_regions_allocated(),
+            self.vmx_regions_from_pool(),
+            *self == (ArchCpu {
+                vmxon_region: self.vmxon_region,
+                vmcs_region: self.vmcs_region,
+                ..*old(self)
+            }),
+    {
+        let pool = VmxRegionPool::get();
+        let (vmxon_region, vmcs_region) = pool.cpu_regions(self.cpuid);
+        self.vmxon_region = vmxon_region;
+        self.vmcs_region = vmcs_region;
+    }
+    
     /// 激活 VMX
-    #[verifier::external_body]
     fn idle_activate_vmx(&mut self) -> (result: Result<(), ()>)
-        requires old(self).inv(),
+        requires
+            old(self).inv(),
+            old(self).vmx_regions_allocated(),
         ensures
             result.is_ok() ==> {
                 self.inv() &&
                 self.vmx_on &&
-                self.vmcs_configured &&
+                !self.vmcs_configured &&
+                self.written_fields@ == Set::<u32>::empty() &&
+                self.vmcs_loaded &&
+                !self.vmcs_launched &&
                 self.cpuid == old(self).cpuid &&
                 self.power_on == old(self).power_on &&
                 self.host_stack_top == old(self).host_stack_top
             },
     {
-        // 硬件操作：
-        // 1. 执行 VMXON
-        // 2. 执行 VMCLEAR
-        // 3. 执行 VMPTRLD
+        let vmxon_ptr = self.vmxon_phys_ptr();
+        let vmcs_ptr = self.vmcs_phys_ptr();
+        self.vmxon_and_load(vmxon_ptr, vmcs_ptr)
+    }
+    
+    /// 执行 VMXON，再 VMCLEAR、VMPTRLD 同一个 VMCS（信任边界）
+    /// 
+    /// 指针由调用方经 vmxon_phys_ptr / vmcs_phys_ptr 取得，必须是本 CPU 已分配的 region。
+    /// 只改变 VMX / VMCS 的状态位：当前 VMCS 尚未写入任何字段，也不算已配置，
+    /// 不变式由调用方从这一状态转移证明，而不是由信任边界给出
+    #[verifier::external_body]
+    fn vmxon_and_load(&mut self, vmxon_ptr: u64, vmcs_ptr: u64) -> (result: Result<(), ()>)
+        requires
+            old(self).inv(),
+            old(self).vmx_regions_allocated(),
+            old(self).vmxon_region.spec_frame() == Some(vmxon_ptr),
+            old(self).vmcs_region.spec_frame() == Some(vmcs_ptr),
+        ensures
+            result.is_ok() ==> *self == (ArchCpu {
+                vmx_on: true,
+                vmcs_loaded: true,
+                vmcs_launched: false,
+                vmcs_configured: false,
+                written_fields: Ghost(Set::empty()),
+                ..*old(self)
+            }),
+    {
+        // 硬件操作：VMXON [vmxon_ptr]，VMCLEAR [vmcs_ptr]，VMPTRLD [vmcs_ptr]
         Ok(())
     }
     
@@ -316,9 +1971,11 @@ impl ArchCpu {
         requires old(self).inv(),
         ensures 
             self.inv(),
+            self.parking_is_initialized(),
             self.cpuid == old(self).cpuid,
             self.vmx_on == old(self).vmx_on,
             self.vmcs_configured == old(self).vmcs_configured,
+            self.vmcs_loaded == old(self).vmcs_loaded,
             self.power_on == old(self).power_on,
             self.host_stack_top == old(self).host_stack_top,
     {
@@ -326,71 +1983,200 @@ impl ArchCpu {
     }
     
     /// 配置 VMCS（用于 idle）
-    #[verifier::external_body]
+    /// 
+    /// 与 setup_vmcs 相同，必需字段经 vmcs_write 写入之后才算配置完成
     fn idle_setup_vmcs(&mut self) -> (result: Result<(), ()>)
         requires
             old(self).inv(),
             old(self).vmx_on,
+            old(self).vmcs_loaded,  // VMWRITE 需要当前 VMCS
+            !old(self).vmcs_configured,
         ensures
             result.is_ok() ==> {
                 self.inv() &&
                 self.vmcs_configured &&
+                self.vmcs_fields_complete() &&
                 self.cpuid == old(self).cpuid &&
                 self.vmx_on == old(self).vmx_on &&
                 self.power_on == old(self).power_on &&
-                self.host_stack_top == old(self).host_stack_top
+                self.host_stack_top == old(self).host_stack_top &&
+                self.parking_initialized == old(self).parking_initialized &&
+                self.spec_host_cr0() == spec_current_cr0() &&
+                self.spec_host_cr3() == spec_current_cr3() &&
+                self.spec_host_cr4() == spec_current_cr4()
             },
     {
-        // VMCS 配置操作
+        if self.load_idle_vmcs_state().is_err() {
+            return Err(());
+        }
+        // 影子页表模式下 parking guest 的 CR3 同样不能是 host 页表根，在写入 GUEST_CR3 之前检查
+        let ctls = self.ctls;
+        if !self.guest_cr3_installable(&ctls) {
+            return Err(());
+        }
+        self.write_required_fields();
+        proof {
+            assert(self.guest_cr3_isolated());
+        }
+        self.vmcs_configured = true;
+        Ok(())
+    }
+    
+    /// 装入 idle（parking）guest 的状态模型与控制字段（信任边界），不写 required_fields
+    #[verifier::external_body]
+    fn load_idle_vmcs_state(&mut self) -> (result: Result<(), ()>)
+        requires
+            old(self).inv(),
+            old(self).vmx_on,
+            old(self).vmcs_loaded,
+            !old(self).vmcs_configured,
+        ensures
+            result.is_ok() ==> {
+                self.inv() &&
+                !self.vmcs_configured &&
+                self.ctls.is_valid() &&
+                host_crs_fixed(self.host_cr0, self.host_cr4) &&
+                self.cpuid == old(self).cpuid &&
+                self.vmx_on == old(self).vmx_on &&
+                self.vmcs_loaded == old(self).vmcs_loaded &&
+                self.vmcs_launched == old(self).vmcs_launched &&
+                self.power_on == old(self).power_on &&
+                self.host_stack_top == old(self).host_stack_top &&
+                self.parking_initialized == old(self).parking_initialized &&
+                self.written_fields == old(self).written_fields &&
+                self.spec_host_cr0() == spec_current_cr0() &&
+                self.spec_host_cr3() == spec_current_cr3() &&
+                self.spec_host_cr4() == spec_current_cr4()
+            },
+    {
+        // parking guest 的段、控制寄存器与执行控制，host_cr0 / host_cr3 / host_cr4 取自当前控制寄存器
         Ok(())
     }
     
     /// 设置 host 栈顶
-    fn idle_set_stack_top(&mut self)
+    /// 
+    /// 全程在 u64 中做带检查的乘法和加法，不依赖 usize 的宽度；溢出时返回 Err 且状态不变
+    fn idle_set_stack_top(&mut self) -> (result: Result<(), ()>)
         requires
             old(self).inv(),
-            old(self).cpuid < MAX_CPU_NUM,
-            spec_core_end() + ((old(self).cpuid + 1) * PER_CPU_SIZE) as u64 <= u64::MAX,
         ensures
             self.inv(),
-            self.host_stack_top == (spec_core_end() + (self.cpuid + 1) * PER_CPU_SIZE) as u64,
-            self.host_stack_top > spec_core_end(),
-            self.host_stack_top % 16 == 0,
-            self.cpuid == old(self).cpuid,
-            self.vmx_on == old(self).vmx_on,
-            self.vmcs_configured == old(self).vmcs_configured,
-            self.power_on == old(self).power_on,
+            old(self).core_end + (old(self).cpuid + 1) * PER_CPU_SIZE <= u64::MAX ==> result.is_ok(),
+            result.is_ok() ==> {
+                &&& self.host_stack_top == self.core_end + (self.cpuid + 1) * PER_CPU_SIZE
+                &&& self.host_stack_top > self.core_end
+                &&& self.host_stack_top % 16 == 0
+                &&& self.cpuid == old(self).cpuid
+                &&& self.vmx_on == old(self).vmx_on
+                &&& self.vmcs_configured == old(self).vmcs_configured
+                &&& self.power_on == old(self).power_on
+                &&& self.parking_initialized == old(self).parking_initialized
+            },
+            result.is_err() ==> *self == *old(self),
     {
-        self.host_stack_top = core_end() + ((self.cpuid + 1) * PER_CPU_SIZE) as u64;
+        let size = match (self.cpuid as u64 + 1).checked_mul(PER_CPU_SIZE as u64) {
+            Some(size) => size,
+            None => return Err(()),
+        };
+        let end = self.core_end;
+        let top = match end.checked_add(size) {
+            Some(top) => top,
+            None => return Err(()),
+        };
+        self.host_stack_top = top;
         
         proof {
-            assert(self.host_stack_top > spec_core_end());
+            assert(self.host_stack_top > self.core_end);
             
-            // 验证对齐（假设 PER_CPU_SIZE 是 16 的倍数）
-            assume(PER_CPU_SIZE % 16 == 0);
-            assume(spec_core_end() % 16 == 0);
+            // core_end 按页对齐，PER_CPU_SIZE 是 16 的倍数
+            assert(PER_CPU_SIZE % 16 == 0) by (compute);
+            assert(size % 16 == 0) by (nonlinear_arith)
+                requires size == (self.cpuid + 1) * PER_CPU_SIZE, PER_CPU_SIZE % 16 == 0;
+            assert(end % 16 == 0) by (nonlinear_arith)
+                requires end % 4096 == 0;
             assert(self.host_stack_top % 16 == 0);
         }
+        Ok(())
+    }
+    
+    /// 激活 parking 内存页表（信任边界）
+    #[verifier::external_body]
+    fn activate_parking_pagetable(&mut self)
+        requires
+            old(self).ready_for_idle(),
+            old(self).parking_is_initialized(),
+        ensures
+            self.ready_for_idle(),
+            self.active_pagetable() is Parking,
+    {
+        // 硬件操作：将 parking 内存页表写入 CR3
     }
     
-    /// 激活页表并启动 VM（信任边界）
+    /// 在 parking 地址空间中执行 vmlaunch（信任边界）
+    /// 
+    /// 成功时进入 guest 永不返回；返回即表示失败，返回值为 vmlaunch 之后的 RFLAGS
     #[verifier::external_body]
+    fn idle_vmx_launch(&mut self) -> (rflags: u64)
+        requires
+            old(self).ready_for_idle(),
+            old(self).active_pagetable() is Parking,
+        ensures
+            *self == *old(self),
+            spec_vmx_result(rflags) is Err,
+    {
+        // 信任边界：vmlaunch，失败时 pushfq 返回 RFLAGS
+        RFLAGS_CF
+    }
+    
+    /// 激活页表并启动 VM
+    /// 
+    /// 先切换到 parking 页表再 vmlaunch，否则 guest 会运行在错误的地址空间；
+    /// vmlaunch 返回即失败，经 vmx_entry_failed 记录 VmEntryFailed 后停机
     fn idle_activate_and_launch(&mut self) -> !
         requires
             old(self).ready_for_idle(),
     {
-        // 激活 parking 内存页表并执行 vmlaunch
-        loop {}
+        self.activate_parking_pagetable();
+        
+        proof {
+            // vmlaunch 只能在页表切换之后到达
+            assert(self.active_pagetable() is Parking);
+        }
+        
+        let rflags = self.idle_vmx_launch();
+        proof {
+            assert(spec_vmx_result(rflags) is Err);
+        }
+        Self::vmx_entry_failed()
     }
     
     /// idle 主函数（整合验证）
-    #[verifier::exec_allows_no_decreases_clause]
     pub fn idle(&mut self) -> !
         requires
             old(self).inv(),
-            old(self).cpuid == spec_this_cpu_id(),
+            old(self).owned_by_current(),
+            old(self).cpuid < MAX_CPU_NUM,
+            old(self).core_end + ((old(self).cpuid + 1) * PER_CPU_SIZE) as u64 <= u64::MAX,
+    {
+        self.assert_owned_by_current();
+        
+        // 步骤 1-8：启动到 idle 就绪
+        self.boot_to_idle();
+        
+        // 步骤 9：启动 VM（发散调用是函数体的最后一个表达式，之后没有任何代码）
+        self.idle_activate_and_launch()
+    }
+    
+    /// idle 的启动序列（步骤 1-8），失败时停机，返回即满足 ready_for_idle
+    fn boot_to_idle(&mut self)
+        requires
+            old(self).inv(),
             old(self).cpuid < MAX_CPU_NUM,
-            spec_core_end() + ((old(self).cpuid + 1) * PER_CPU_SIZE) as u64 <= u64::MAX,
+            old(self).cpuid == spec_this_cpu_id(),
+            old(self).core_end + ((old(self).cpuid + 1) * PER_CPU_SIZE) as u64 <= u64::MAX,
+        ensures
+            self.inv(),
+            self.ready_for_idle(),
     {
         // 步骤 1：清理中断
         self.idle_clear_interrupt();
@@ -409,35 +2195,41 @@ impl ArchCpu {
             assert(!self.power_on);
         }
         
-        // 步骤 4：激活 VMX
+        // 步骤 4：分配 VMX region（new() 中只是 fake_init 占位）
+        self.alloc_vmx_regions();
+        
+        // 步骤 5：激活 VMX
         let vmx_result = self.idle_activate_vmx();
         if vmx_result.is_err() {
-            loop {}  // 替代 panic
+            boot_failed(BootStep::ActivateVmx, FatalCpuError::VmxActivationFailed);
         }
         
         proof {
             assert(self.vmx_on);
-            assert(self.vmcs_configured);
+            assert(!self.vmcs_configured);
+            assert(self.vmcs_loaded);
         }
         
-        // 步骤 5：初始化 parking
+        // 步骤 6：初始化 parking
         self.idle_init_parking();
         
-        // 步骤 6：配置 VMCS
+        // 步骤 7：配置 VMCS
         let vmcs_result = self.idle_setup_vmcs();
         if vmcs_result.is_err() {
-            loop {}
+            boot_failed(BootStep::SetupVmcs, FatalCpuError::VmcsSetupFailed);
         }
         
         proof {
             assert(self.vmcs_configured);
         }
         
-        // 步骤 7：设置栈顶
-        self.idle_set_stack_top();
+        // 步骤 8：设置栈顶
+        if self.idle_set_stack_top().is_err() {
+            boot_failed(BootStep::SetStackTop, FatalCpuError::StackTopOverflow);
+        }
         
         proof {
-            assert(self.host_stack_top > spec_core_end());
+            assert(self.host_stack_top > self.core_end);
             assert(self.host_stack_top % 16 == 0);
             assert(!self.power_on);
             assert(self.vmx_on);
@@ -446,190 +2238,4275 @@ impl ArchCpu {
             // 验证满足 ready_for_idle
             assert(self.ready_for_idle());
         }
-        
-        // 步骤 8：启动 VM
-        self.idle_activate_and_launch();
     }
 }
 
+/// 启动流程的整体自检：在 cpuid 号 CPU 上从 ArchCpu::new 出发完成 idle 的启动序列，得到满足 ready_for_idle 的 CPU
+/// 
+/// 启动序列本身不含 assume；VMX 激活、清理中断等硬件步骤是 external_body 信任边界，这里只依赖它们的 ensures。
+/// 任何一步的后置条件弱于下一步的前置条件，这里都无法通过验证
+pub fn verify_boot_to_idle(cpuid: usize) -> (cpu: ArchCpu)
+    requires
+        cpuid < MAX_CPU_NUM,
+        cpuid == spec_this_cpu_id(),
+        spec_core_end() + ((cpuid + 1) * PER_CPU_SIZE) as u64 <= u64::MAX,
+    ensures
+        cpu.inv(),
+        cpu.ready_for_idle(),
+{
+    let mut cpu = ArchCpu::new(cpuid);
+    cpu.boot_to_idle();
+    cpu
+}
+
 impl ArchCpu {
-    /// VMX 启动失败处理
-    #[verifier::external_body]
+    /// VMX 启动失败处理（汇编中 vmlaunch/vmresume 之后的 failed 标签）
     fn vmx_entry_failed() -> !
     {
-        // panic!("VMX entry failed");
-        loop {}
+        boot_failed(BootStep::Entry, FatalCpuError::VmEntryFailed)
     }
     
     /// 激活 VMX（用于正常启动）
-    #[verifier::external_body]
+    /// 
+    /// 已处于 VMX root operation 时再次 VMXON 会出错，因此调用方必须保证 VMX 尚未开启，
+    /// 重复激活由前置条件排除，而不是当作空操作。
+    /// 固件未锁定 IA32_FEATURE_CONTROL 或未开启 SMX 之外的 VMX 时 VMXON 会 #GP，此时不执行 VMXON 并返回 Err
     pub fn activate_vmx(&mut self) -> (result: Result<(), ()>)
         requires
             old(self).inv(),
+            old(self).vmx_regions_allocated(),
+            !old(self).vmx_on,
         ensures
+            !spec_vmxon_allowed(spec_host_feature_control()) ==> result.is_err() && *self == *old(self),
+            // 成功时 VMX 由关闭变为开启，每次成功调用恰好执行一次 VMXON；新装入的 VMCS 尚未写入任何字段
             result.is_ok() ==> {
                 self.inv() &&
                 self.vmx_on &&
-                self.vmcs_configured &&
+                !self.vmcs_configured &&
+                self.written_fields@ == Set::<u32>::empty() &&
+                self.vmcs_loaded &&
+                !self.vmcs_launched &&
                 self.cpuid == old(self).cpuid &&
-                self.power_on == old(self).power_on
+                self.power_on == old(self).power_on &&
+                self.guest_regs == old(self).guest_regs &&
+                self.intended_guest_regs == old(self).intended_guest_regs &&
+                self.spec_guest_efer() == old(self).spec_guest_efer() &&
+                self.guest_state == old(self).guest_state
             },
     {
-        // 执行 VMXON, VMCLEAR, VMPTRLD
-        Ok(())
+        if !vmxon_allowed(read_host_feature_control()) {
+            return Err(());
+        }
+        let vmxon_ptr = self.vmxon_phys_ptr();
+        let vmcs_ptr = self.vmcs_phys_ptr();
+        self.vmxon_and_load(vmxon_ptr, vmcs_ptr)
+    }
+    
+    /// VMWRITE 并把字段编码记入 written_fields
+    fn vmcs_write(&mut self, field: VmcsField, val: u64)
+        requires
+            old(self).inv(),
+            old(self).vmcs_loaded,
+            fits_width(val, field_width(field.spec_encoding())),
+        ensures
+            self.inv(),
+            *self == (ArchCpu {
+                written_fields: Ghost(old(self).written_fields@.insert(field.spec_encoding())),
+                ..*old(self)
+            }),
+    {
+        vmcs_write_field(field, val);
+        self.written_fields = Ghost(self.written_fields@.insert(field.spec_encoding()));
+    }
+    
+    /// 影子页表模式下 guest CR3 不能是 host 当前的页表根，EPT 下不约束（setup_vmcs 的前置条件）
+    fn guest_cr3_installable(&self, ctls: &VmcsControls) -> (result: bool)
+        ensures
+            result == (!ctls.ept_enabled() ==> self.guest_state.cr3 != spec_current_cr3()),
+    {
+        let ept = ctls.proc_ctls.activate_secondary && ctls.secondary_ctls.enable_ept;
+        ept || self.guest_state.cr3 != current_cr3()
     }
     
     /// 配置 VMCS（完整版本）
-    #[verifier::external_body]
+    /// 
+    /// 只能配置 VMCLEAR 之后尚未 vmlaunch 的 VMCS：已 launch 的 VMCS 部分状态缓存在处理器中，
+    /// 不经 VMCLEAR 直接改写字段是不安全的；配置完成后第一次 entry 必然走 vmlaunch
     fn setup_vmcs(
         &mut self,
         entry: u64,
         rsp: u64,
+        ctls: VmcsControls,
     ) -> (result: Result<(), ()>)
         requires
             old(self).inv(),
             old(self).vmx_on,
+            old(self).vmcs_loaded,  // VMWRITE 需要当前 VMCS
+            !old(self).vmcs_launched,  // 必须在 VMCLEAR 之后配置
+            ctls.is_valid(),
+            ctls.window_policy_holds(),
+            is_valid_entry(entry),
+            guest_rsp_aligned(rsp),
+            ctls.entry_ctls.ia32e_mode_guest == efer_lma(old(self).spec_guest_efer()),
+            // 影子页表模式下不能把 host 页表装为 guest CR3
+            !ctls.ept_enabled() ==> old(self).guest_state.cr3 != spec_current_cr3(),
         ensures
             result.is_ok() ==> {
                 self.inv() &&
                 self.vmcs_configured &&
+                self.ctls == ctls &&
+                self.spec_guest_efer() == old(self).spec_guest_efer() &&
+                self.efer_coherent() &&
+                self.guest_state.rip == entry &&
+                self.guest_state.rsp == rsp &&
                 self.cpuid == old(self).cpuid &&
                 self.vmx_on == old(self).vmx_on &&
-                self.power_on == old(self).power_on
+                self.power_on == old(self).power_on &&
+                self.vmcs_loaded == old(self).vmcs_loaded &&
+                !self.vmcs_launched &&
+                self.guest_regs == old(self).guest_regs &&
+                self.intended_guest_regs == old(self).intended_guest_regs &&
+                self.spec_host_cr0() == spec_current_cr0() &&
+                self.spec_host_cr3() == spec_current_cr3() &&
+                self.spec_host_cr4() == spec_current_cr4() &&
+                self.guest_state.cr0 == old(self).guest_state.cr0 &&
+                self.guest_state.cr3 == old(self).guest_state.cr3 &&
+                self.guest_state.activity == old(self).guest_state.activity &&
+                (self.guest_in_real_mode() ==> self.guest_state.real_mode_segments()) &&
+                self.guest_state.descriptor_tables_valid() &&
+                self.vmcs_fields_complete()
             },
     {
-        // 配置 VMCS 的所有字段
-        // - Guest state
-        // - Host state
-        // - VM-execution controls
-        // - VM-exit controls
-        // - VM-entry controls
+        // 重新配置时先撤销已配置标记，写完必需字段之后才重新成立
+        self.vmcs_configured = false;
+        if self.load_vmcs_state(entry, rsp, ctls).is_err() {
+            return Err(());
+        }
+        self.write_required_fields();
+        proof {
+            // write_required_fields 写入的 GUEST_CR3 是 guest_state.cr3，HOST_CR3 是取自当前 CR3 的 host_cr3，
+            // SECONDARY_CTLS 中 enable EPT 取自 ctls；影子页表模式下前置条件保证两个 CR3 不同
+            assert(self.guest_state.cr3 == old(self).guest_state.cr3);
+            assert(self.spec_host_cr3() == spec_current_cr3());
+            assert(self.guest_cr3_isolated());
+        }
+        self.vmcs_configured = true;
         Ok(())
     }
     
-    /// VM Exit 处理器
+    /// 装入 guest / host 状态的模型并写入 required_fields 之外的字段（信任边界）
+    /// 
+    /// 不把 VMCS 标记为已配置，必需字段由 setup_vmcs 经 write_required_fields 写入
     #[verifier::external_body]
-    fn vmexit_handler(&mut self)
+    fn load_vmcs_state(
+        &mut self,
+        entry: u64,
+        rsp: u64,
+        ctls: VmcsControls,
+    ) -> (result: Result<(), ()>)
         requires
             old(self).inv(),
-            old(self).vmcs_configured,
+            old(self).vmx_on,
+            old(self).vmcs_loaded,  // VMWRITE 需要当前 VMCS
+            !old(self).vmcs_launched,  // 必须在 VMCLEAR 之后配置
+            !old(self).vmcs_configured,
+            ctls.is_valid(),
+            ctls.window_policy_holds(),
+            is_valid_entry(entry),
+            guest_rsp_aligned(rsp),
+            ctls.entry_ctls.ia32e_mode_guest == efer_lma(old(self).spec_guest_efer()),
+            // 影子页表模式下不能把 host 页表装为 guest CR3
+            !ctls.ept_enabled() ==> old(self).guest_state.cr3 != spec_current_cr3(),
+        ensures
+            result.is_ok() ==> {
+                self.inv() &&
+                !self.vmcs_configured &&
+                host_crs_fixed(self.host_cr0, self.host_cr4) &&
+                self.ctls == ctls &&
+                self.spec_guest_efer() == old(self).spec_guest_efer() &&
+                self.efer_coherent() &&
+                self.guest_state.rip == entry &&
+                self.guest_state.rsp == rsp &&
+                self.cpuid == old(self).cpuid &&
+                self.vmx_on == old(self).vmx_on &&
+                self.power_on == old(self).power_on &&
+                self.vmcs_loaded == old(self).vmcs_loaded &&
+                !self.vmcs_launched &&
+                self.guest_regs == old(self).guest_regs &&
+                self.intended_guest_regs == old(self).intended_guest_regs &&
+                self.spec_host_cr0() == spec_current_cr0() &&
+                self.spec_host_cr3() == spec_current_cr3() &&
+                self.spec_host_cr4() == spec_current_cr4() &&
+                self.guest_state.cr0 == old(self).guest_state.cr0 &&
+                self.guest_state.cr3 == old(self).guest_state.cr3 &&
+                self.guest_state.activity == old(self).guest_state.activity &&
+                (self.guest_in_real_mode() ==> self.guest_state.real_mode_segments()) &&
+                self.guest_state.descriptor_tables_valid() &&
+                self.host_stack_top == old(self).host_stack_top &&
+                self.written_fields == old(self).written_fields
+            },
+    {
+        // - 实模式 guest 的段基址按 selector << 4 设置（GuestState::load_real_mode_segments）
+        // - guest_state.rip = entry，guest_state.rsp = rsp，GUEST_IA32_EFER = spec_guest_efer
+        // - 段寄存器、GDTR / IDTR（base 规范、limit <= 0xffff）与 LDTR（默认不可用，AR = SEG_AR_UNUSABLE）
+        // - CR0 guest/host mask = ctls.cr0_mask，CR0 read shadow = guest_state.cr0_shadow
+        // - CR4 guest/host mask = ctls.cr4_mask，CR4 read shadow = guest_state.cr4_shadow
+        // - host_cr0 / host_cr3 / host_cr4 取自当前控制寄存器，self.ctls = ctls
+        Ok(())
+    }
+    
+    /// 经 vmcs_write 写入 required_fields 中的全部字段，值取自模型状态
+    fn write_required_fields(&mut self)
+        requires
+            old(self).inv(),
+            old(self).vmcs_loaded,
         ensures
             self.inv(),
-            self.cpuid == old(self).cpuid,
-            self.vmx_on == old(self).vmx_on,
-            self.vmcs_configured == old(self).vmcs_configured,
+            *self == (ArchCpu { written_fields: self.written_fields, ..*old(self) }),
+            old(self).written_fields@.subset_of(self.written_fields@),
+            self.vmcs_fields_complete(),
     {
-        // 处理各种 VM Exit 原因
-        // - I/O 指令
-        // - MSR 访问
-        // - CPUID
-        // - EPT violation
-        // 等等
+        proof {
+            lemma_field_widths(VmcsField::GuestRip);
+            lemma_field_widths(VmcsField::GuestRsp);
+            lemma_field_widths(VmcsField::GuestCr0);
+            lemma_field_widths(VmcsField::GuestCr3);
+            lemma_field_widths(VmcsField::GuestCr4);
+            lemma_field_widths(VmcsField::HostRip);
+            lemma_field_widths(VmcsField::HostRsp);
+            lemma_field_widths(VmcsField::HostCr0);
+            lemma_field_widths(VmcsField::HostCr3);
+            lemma_field_widths(VmcsField::HostCr4);
+            lemma_field_widths(VmcsField::HostEsSelector);
+            lemma_field_widths(VmcsField::HostCsSelector);
+            lemma_field_widths(VmcsField::HostSsSelector);
+            lemma_field_widths(VmcsField::HostDsSelector);
+            lemma_field_widths(VmcsField::HostFsSelector);
+            lemma_field_widths(VmcsField::HostGsSelector);
+            lemma_field_widths(VmcsField::HostTrSelector);
+            lemma_field_widths(VmcsField::PinBasedCtls);
+            lemma_field_widths(VmcsField::ProcBasedCtls);
+            lemma_field_widths(VmcsField::SecondaryCtls);
+            lemma_field_widths(VmcsField::ExitCtls);
+            lemma_field_widths(VmcsField::EntryCtls);
+        }
+        let rip = self.guest_state.rip;
+        let rsp = self.guest_state.rsp;
+        let cr0 = self.guest_state.cr0;
+        let cr3 = self.guest_state.cr3;
+        let cr4 = self.guest_state.cr4;
+        self.vmcs_write(VmcsField::GuestRip, rip);
+        self.vmcs_write(VmcsField::GuestRsp, rsp);
+        self.vmcs_write(VmcsField::GuestCr0, cr0);
+        self.vmcs_write(VmcsField::GuestCr3, cr3);
+        self.vmcs_write(VmcsField::GuestCr4, cr4);
+        
+        let stack_top = self.host_stack_top;
+        let host_cr0 = self.host_cr0;
+        let host_cr3 = self.host_cr3;
+        let host_cr4 = self.host_cr4;
+        self.vmcs_write(VmcsField::HostRip, vmx_exit_entry());
+        self.vmcs_write(VmcsField::HostRsp, stack_top);
+        self.vmcs_write(VmcsField::HostCr0, host_cr0);
+        self.vmcs_write(VmcsField::HostCr3, host_cr3);
+        self.vmcs_write(VmcsField::HostCr4, host_cr4);
+        self.vmcs_write(VmcsField::HostEsSelector, current_host_selector(VmcsField::HostEsSelector));
+        self.vmcs_write(VmcsField::HostCsSelector, current_host_selector(VmcsField::HostCsSelector));
+        self.vmcs_write(VmcsField::HostSsSelector, current_host_selector(VmcsField::HostSsSelector));
+        self.vmcs_write(VmcsField::HostDsSelector, current_host_selector(VmcsField::HostDsSelector));
+        self.vmcs_write(VmcsField::HostFsSelector, current_host_selector(VmcsField::HostFsSelector));
+        self.vmcs_write(VmcsField::HostGsSelector, current_host_selector(VmcsField::HostGsSelector));
+        self.vmcs_write(VmcsField::HostTrSelector, current_host_selector(VmcsField::HostTrSelector));
+        
+        let ctls = self.ctls;
+        self.vmcs_write(VmcsField::PinBasedCtls, ctls.pin_ctls.bits() as u64);
+        self.vmcs_write(VmcsField::ProcBasedCtls, ctls.proc_ctls.bits() as u64);
+        self.vmcs_write(VmcsField::SecondaryCtls, ctls.secondary_ctls.bits() as u64);
+        self.vmcs_write(VmcsField::ExitCtls, ctls.exit_ctls.bits() as u64);
+        self.vmcs_write(VmcsField::EntryCtls, ctls.entry_ctls.bits() as u64);
+        
+        assert forall|e: u32| #[trigger] required_fields().contains(e) implies self.written_fields@.contains(e) by {}
     }
     
-    /// vmx_exit 汇编函数的语义规范
+    /// 退出时回收在途事件
     /// 
-    /// 实际汇编代码：
-    /// ```asm
-    /// save_regs_to_stack!()           // 保存寄存器到栈
-    /// mov    r15, rsp                 // 保存临时 RSP
-    /// mov    rdi, rsp                 // 设置第一个参数
-    /// mov    rsp, [rsp + 128]         // 切换到 host_stack_top
-    /// call   vmexit_handler           // 调用处理函数
-    /// mov    rsp, r15                 // 恢复 RSP
-    /// restore_regs_from_stack!()      // 恢复寄存器
-    /// vmresume                        // 返回 VM
-    /// jmp    failed                   // 失败处理
-    /// ```
-    #[verifier::external_body]
-    unsafe extern "C" fn vmx_exit(&mut self) -> !
+    /// 上次 entry 注入的事件已被硬件消费；若投递过程中发生退出，
+    /// IDT-vectoring 字段中的事件（含 #PF 等的错误码）按 PendingEvents::push 放回 pending_events
+    /// 等待重新注入。在途的 NMI 尚未投递完成，因此同时解除 NMI 阻塞。
+    /// 中断槽位被占用时在途中断放回虚拟 LAPIC 的 IRR；异常无法排队（已有 #DF 或槽位被占用）时
+    /// 返回 Err，由调用者按三重错误处理
+    fn stash_idt_vectoring_event(&mut self) -> (result: Result<(), ()>)
         requires
             old(self).inv(),
-            old(self).vmcs_configured,
+        ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            self.ctls == old(self).ctls,
+            self.guest_state.rip == old(self).guest_state.rip,
+            self.entry_event is None,
+            ({
+                let event = spec_idt_vectoring_event(spec_idt_vectoring_info(), spec_idt_vectoring_error_code());
+                let pushed = if event is Some { old(self).pending_events.spec_push(event.unwrap()) } else { None };
+                &&& event is Some && pushed is Some ==> {
+                    &&& result.is_ok()
+                    &&& self.pending_events == pushed.unwrap()
+                    &&& self.virt_lapic == old(self).virt_lapic
+                }
+                &&& match event {
+                    Some(PendingEvent::Interrupt { vector }) => pushed is None ==> {
+                        &&& result.is_ok()
+                        &&& self.pending_events == old(self).pending_events
+                        &&& self.virt_lapic.irr@ == old(self).virt_lapic.irr@.update(vector as int, true)
+                    },
+                    _ => true,
+                }
+                &&& event is Some && pushed is None && !(event.unwrap() is Interrupt) ==> result.is_err()
+                &&& event is None ==> result.is_ok() && self.pending_events == old(self).pending_events
+                &&& event == Some(PendingEvent::Nmi) ==> !self.guest_state.interruptibility.blocking_by_nmi
+            }),
     {
-        // 信任边界：调用真实的汇编实现
-        loop {}
+        self.entry_event = None;
+        let info = read_idt_vectoring_info();
+        let error_code = read_idt_vectoring_error_code();
+        match idt_vectoring_event(info, error_code) {
+            Some(event) => {
+                if let PendingEvent::Nmi = event {
+                    self.guest_state.interruptibility.blocking_by_nmi = false;
+                }
+                if self.pending_events.push(event).is_err() {
+                    match event {
+                        PendingEvent::Interrupt { vector } => self.virt_lapic.set_irr(vector),
+                        _ => return Err(()),
+                    }
+                }
+                Ok(())
+            },
+            None => Ok(()),
+        }
     }
     
-    /// vmx_launch 汇编函数的语义规范
-    /// 
-    /// 实际汇编代码：
-    /// ```asm
-    /// mov    rsp, rdi                 // 设置 RSP 到 guest_regs
-    /// restore_regs_from_stack!()      // 恢复所有寄存器
-    /// vmlaunch                        // 启动 VM
-    /// jmp    failed                   // 失败处理
-    /// ```
+    /// 处理 CPUID 退出：计算 guest 可见的 CPUID 结果并写回 guest 寄存器
     /// 
-    /// 关键验证点：
-    /// 1. rdi 指向 guest_regs（结构体起始位置）
-    /// 2. 寄存器从内存正确恢复
-    /// 3. vmlaunch 成功则永不返回
-    #[verifier::external_body]
-    unsafe extern "C" fn vmx_launch(&mut self) -> !
+    /// hypervisor 区间（0x4000_0000 起）由 guest_cpuid 模拟，其余 leaf 透传硬件并按
+    /// spec_cpuid_mask 清除不能虚拟化的特性位
+    fn handle_cpuid(&mut self) -> (result: EmulationResult)
         requires
-            old(self).ready_for_vm_launch(),
+            old(self).inv(),
+            old(self).vmcs_configured,
+        ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            self.guest_state == old(self).guest_state,
+            result == EmulationResult::Advance(VM_EXIT_INSTR_LEN_CPUID),
+            ({
+                let res = spec_guest_cpuid(old(self).guest_regs.rax as u32, old(self).guest_regs.rcx as u32);
+                &&& self.guest_regs.rax == res.eax as u64
+                &&& self.guest_regs.rbx == res.ebx as u64
+                &&& self.guest_regs.rcx == res.ecx as u64
+                &&& self.guest_regs.rdx == res.edx as u64
+            }),
+            // 被屏蔽的特性位无论硬件如何都不会出现在 guest 寄存器中
+            !is_hv_leaf(old(self).guest_regs.rax as u32) ==> ({
+                let mask = spec_cpuid_mask(old(self).guest_regs.rax as u32, old(self).guest_regs.rcx as u32);
+                &&& (self.guest_regs.rax as u32) & !mask.0 == 0
+                &&& (self.guest_regs.rbx as u32) & !mask.1 == 0
+                &&& (self.guest_regs.rcx as u32) & !mask.2 == 0
+                &&& (self.guest_regs.rdx as u32) & !mask.3 == 0
+            }),
     {
-        // 信任边界：调用真实的汇编实现
-        loop {}
+        let leaf = self.guest_regs.rax as u32;
+        let subleaf = self.guest_regs.rcx as u32;
+        proof {
+            lemma_masked_bits_zero(spec_cpuid(leaf, subleaf), leaf, subleaf);
+        }
+        let res = guest_cpuid_cached(&mut self.cpuid_cache, leaf, subleaf);
+        
+        self.guest_regs.rax = res.eax as u64;
+        self.guest_regs.rbx = res.ebx as u64;
+        self.guest_regs.rcx = res.ecx as u64;
+        self.guest_regs.rdx = res.edx as u64;
+        
+        EmulationResult::Advance(VM_EXIT_INSTR_LEN_CPUID)
     }
     
-    /// 高层启动函数（可验证的包装）
-    #[verifier::exec_allows_no_decreases_clause]
-    pub fn launch_vm(
-        &mut self,
-        entry: u64,
-        rsp: u64,
-    ) -> !
+    /// 规范函数：guest 可访问的 MSR 当前值（不支持的 MSR 为 None）
+    pub open spec fn spec_guest_msr(&self, msr: u32) -> Option<u64> {
+        if msr == IA32_FS_BASE {
+            Some(self.guest_state.fs.base)
+        } else if msr == IA32_GS_BASE {
+            Some(self.guest_state.gs.base)
+        } else if msr == IA32_KERNEL_GS_BASE {
+            Some(self.guest_state.kernel_gs_base)
+        } else if msr == IA32_STAR {
+            Some(self.guest_state.star)
+        } else if msr == IA32_LSTAR {
+            Some(self.guest_state.lstar)
+        } else if msr == IA32_CSTAR {
+            Some(self.guest_state.cstar)
+        } else if msr == IA32_FMASK {
+            Some(self.guest_state.fmask)
+        } else if msr == IA32_SYSENTER_CS {
+            Some(self.guest_state.sysenter_cs as u64)
+        } else if msr == IA32_SYSENTER_ESP {
+            Some(self.guest_state.sysenter_esp)
+        } else if msr == IA32_SYSENTER_EIP {
+            Some(self.guest_state.sysenter_eip)
+        } else if msr == IA32_APIC_BASE {
+            Some(self.virt_lapic.apic_base)
+        } else if msr == IA32_EFER {
+            Some(self.spec_guest_efer())
+        } else if msr == IA32_PAT {
+            Some(self.guest_pat)
+        } else if msr == IA32_FEATURE_CONTROL {
+            Some(self.spec_feature_control())
+        } else if msr == IA32_TSC_DEADLINE {
+            Some(self.virt_lapic.spec_tsc_deadline_read())
+        } else if msr == IA32_X2APIC_SVR && self.virt_lapic.mode is X2Apic {
+            Some(self.virt_lapic.svr as u64)
+        } else if is_x2apic_lvt_msr(msr) && self.virt_lapic.mode is X2Apic {
+            Some(self.virt_lapic.lvt@[(msr - IA32_X2APIC_LVT_TIMER) as int] as u64)
+        } else {
+            None
+        }
+    }
+    
+    /// 规范函数：WRMSR 写入值是否合法（不合法时注入 #GP）
+    pub open spec fn spec_msr_write_valid(&self, msr: u32, val: u64) -> bool {
+        &&& is_segment_base_msr(msr) ==> spec_is_canonical(val)
+        &&& is_syscall_target_msr(msr) ==> spec_is_canonical(val)
+        &&& msr == IA32_FMASK ==> val & FMASK_RESERVED == 0
+        &&& msr == IA32_SYSENTER_CS ==> spec_sysenter_cs_valid(val)
+        &&& is_sysenter_target_msr(msr) ==> spec_is_canonical(val)
+        &&& msr == IA32_APIC_BASE ==> self.virt_lapic.spec_apic_base_write_valid(val)
+        &&& msr == IA32_EFER ==> self.spec_efer_write_valid(val)
+        &&& msr == IA32_PAT ==> spec_pat_valid(val)
+        &&& msr == IA32_FEATURE_CONTROL ==> self.spec_feature_control() & FEATURE_CONTROL_LOCK == 0
+        &&& msr == IA32_X2APIC_SVR ==> val & SVR_RESERVED == 0
+        &&& is_x2apic_lvt_msr(msr) ==> spec_lvt_write_valid((msr - IA32_X2APIC_LVT_TIMER) as usize, val)
+    }
+    
+    /// 规范函数：WRMSR 被接受（MSR 受支持且写入值合法）
+    pub open spec fn spec_msr_write_accepted(&self, msr: u32, val: u64) -> bool {
+        self.spec_guest_msr(msr) is Some && self.spec_msr_write_valid(msr, val)
+    }
+    
+    /// 读取 guest MSR
+    fn guest_msr(&self, msr: u32) -> (result: Option<u64>)
+        ensures
+            result == self.spec_guest_msr(msr),
+    {
+        if msr == IA32_FS_BASE {
+            Some(self.guest_state.fs.base)
+        } else if msr == IA32_GS_BASE {
+            Some(self.guest_state.gs.base)
+        } else if msr == IA32_KERNEL_GS_BASE {
+            Some(self.guest_state.kernel_gs_base)
+        } else if msr == IA32_STAR {
+            Some(self.guest_state.star)
+        } else if msr == IA32_LSTAR {
+            Some(self.guest_state.lstar)
+        } else if msr == IA32_CSTAR {
+            Some(self.guest_state.cstar)
+        } else if msr == IA32_FMASK {
+            Some(self.guest_state.fmask)
+        } else if msr == IA32_SYSENTER_CS {
+            Some(self.guest_state.sysenter_cs as u64)
+        } else if msr == IA32_SYSENTER_ESP {
+            Some(self.guest_state.sysenter_esp)
+        } else if msr == IA32_SYSENTER_EIP {
+            Some(self.guest_state.sysenter_eip)
+        } else if msr == IA32_APIC_BASE {
+            Some(self.virt_lapic.apic_base)
+        } else if msr == IA32_EFER {
+            Some(self.guest_efer)
+        } else if msr == IA32_PAT {
+            Some(self.guest_pat)
+        } else if msr == IA32_FEATURE_CONTROL {
+            Some(self.guest_feature_control)
+        } else if msr == IA32_TSC_DEADLINE {
+            Some(self.virt_lapic.tsc_deadline_read())
+        } else if msr == IA32_X2APIC_SVR && self.x2apic_mode() {
+            Some(self.virt_lapic.svr as u64)
+        } else if msr >= IA32_X2APIC_LVT_TIMER && msr <= IA32_X2APIC_LVT_ERROR && self.x2apic_mode() {
+            Some(self.virt_lapic.lvt[(msr - IA32_X2APIC_LVT_TIMER) as usize] as u64)
+        } else {
+            None
+        }
+    }
+    
+    /// 写入 guest MSR，返回 false 表示应注入 #GP（此时状态不变）
+    fn set_guest_msr(&mut self, msr: u32, val: u64) -> (ok: bool)
         requires
             old(self).inv(),
-            entry > 0,
-            rsp > 0,
-            rsp % 16 == 0,
+        ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            self.guest_regs == old(self).guest_regs,
+            self.entry_event == old(self).entry_event,
+            self.guest_state.rip == old(self).guest_state.rip,
+            ok == old(self).spec_msr_write_accepted(msr, val),
+            ok && msr != IA32_TSC_DEADLINE && msr != IA32_APIC_BASE ==> self.spec_guest_msr(msr) == Some(val),
+            // BSP 位只读，读回的是保留原 BSP 位之后的值
+            ok && msr == IA32_APIC_BASE
+                ==> self.spec_guest_msr(msr) == Some(old(self).virt_lapic.spec_apic_base_written(val)),
+            // LVT timer 不在 TSC-deadline 模式时写入被忽略，读回 0
+            msr == IA32_TSC_DEADLINE && old(self).virt_lapic.timer.mode is TscDeadline
+                ==> self.virt_lapic.spec_tsc_deadline_read() == val,
+            msr == IA32_TSC_DEADLINE && !(old(self).virt_lapic.timer.mode is TscDeadline)
+                ==> self.virt_lapic.spec_tsc_deadline_read() == 0,
+            ok ==> forall|other: u32| other != msr && !spec_msr_write_affects(msr, other)
+                ==> #[trigger] self.spec_guest_msr(other) == old(self).spec_guest_msr(other),
+            !ok ==> *self == *old(self),
     {
-        // 步骤 1：激活 VMX
-        let vmx_result = self.activate_vmx();
-        if vmx_result.is_err() {
-            Self::vmx_entry_failed();
+        if msr == IA32_FS_BASE || msr == IA32_GS_BASE || msr == IA32_KERNEL_GS_BASE
+            || msr == IA32_LSTAR || msr == IA32_CSTAR {
+            // 非规范的 SYSCALL 目标要到下一次 SYSCALL 才会出错，在写入时就拒绝
+            if !is_canonical(val) {
+                return false;
+            }
         }
         
-        proof {
-            assert(self.vmx_on);
-            assert(self.vmcs_configured);
+        if msr == IA32_FS_BASE {
+            self.guest_state.fs.base = val;
+            true
+        } else if msr == IA32_GS_BASE {
+            // 当前生效的 GS base
+            self.guest_state.gs.base = val;
+            true
+        } else if msr == IA32_KERNEL_GS_BASE {
+            // SWAPGS 使用的影子值
+            self.guest_state.kernel_gs_base = val;
+            true
+        } else if msr == IA32_STAR {
+            // 硬件不检查 STAR 中的选择子（SYSCALL / SYSRET 只装入固定的描述符缓存），任何值都可写入
+            self.guest_state.star = val;
+            true
+        } else if msr == IA32_LSTAR {
+            self.guest_state.lstar = val;
+            true
+        } else if msr == IA32_CSTAR {
+            self.guest_state.cstar = val;
+            true
+        } else if msr == IA32_FMASK {
+            if val & FMASK_RESERVED != 0 {
+                return false;
+            }
+            self.guest_state.fmask = val;
+            true
+        } else if msr == IA32_SYSENTER_CS {
+            if !sysenter_cs_valid(val) {
+                return false;
+            }
+            proof {
+                lemma_sysenter_cs_fits_field(val);
+            }
+            // 只写入 32 位字段，高位已检查为 0
+            self.guest_state.sysenter_cs = val as u32;
+            true
+        } else if msr == IA32_SYSENTER_ESP || msr == IA32_SYSENTER_EIP {
+            // 32 位 guest 的 SYSENTER 只使用低 32 位，但 WRMSR 总是检查整个 natural-width 值
+            if !is_canonical(val) {
+                return false;
+            }
+            if msr == IA32_SYSENTER_ESP {
+                self.guest_state.sysenter_esp = val;
+            } else {
+                self.guest_state.sysenter_eip = val;
+            }
+            true
+        } else if msr == IA32_APIC_BASE {
+            self.handle_apic_base_write(val)
+        } else if msr == IA32_EFER {
+            self.handle_efer_write(val)
+        } else if msr == IA32_PAT {
+            self.handle_pat_write(val)
+        } else if msr == IA32_FEATURE_CONTROL {
+            self.handle_feature_control_write(val)
+        } else if msr == IA32_TSC_DEADLINE {
+            self.handle_tsc_deadline_write(val)
+        } else if msr == IA32_X2APIC_SVR && self.x2apic_mode() {
+            if val & SVR_RESERVED != 0 {
+                return false;
+            }
+            proof {
+                assert(val & !(0x1ffu32 as u64) == 0 ==> ((val as u32) & 0x1ff) as u64 == val) by (bit_vector);
+            }
+            // x2APIC 下 SVR 是 32 位寄存器，高位已检查为 0
+            self.virt_lapic.write_svr(val as u32);
+            true
+        } else if msr >= IA32_X2APIC_LVT_TIMER && msr <= IA32_X2APIC_LVT_ERROR && self.x2apic_mode() {
+            // 保留位、不支持的投递模式或保留的 timer 模式注入 #GP
+            self.virt_lapic.write_lvt((msr - IA32_X2APIC_LVT_TIMER) as usize, val)
+        } else {
+            false
         }
-        
-        // 步骤 2：配置 VMCS
-        let vmcs_result = self.setup_vmcs(entry, rsp);
-        if vmcs_result.is_err() {
-            Self::vmx_entry_failed();
+    }
+    
+    /// 写 IA32_APIC_BASE：按模式转换表检查后更新虚拟 LAPIC 模式，BSP 位保持不变
+    fn handle_apic_base_write(&mut self, val: u64) -> (ok: bool)
+        requires
+            old(self).inv(),
+        ensures
+            self.inv(),
+            ok == old(self).virt_lapic.spec_apic_base_write_valid(val),
+            ok ==> *self == (ArchCpu { virt_lapic: self.virt_lapic, ..*old(self) }),
+            ok ==> self.virt_lapic.apic_base == old(self).virt_lapic.spec_apic_base_written(val),
+            self.virt_lapic.apic_base & APIC_BASE_BSP == old(self).virt_lapic.apic_base & APIC_BASE_BSP,
+            !ok ==> *self == *old(self),
+    {
+        self.virt_lapic.write_apic_base(val)
+    }
+    
+    /// 规范函数：写 IA32_EFER 是否合法（不合法时注入 #GP）
+    /// 
+    /// LMA 由硬件维护，写入值必须等于 LME && CR0.PG；分页开启时不能修改 LME（SDM 4.1.2）
+    pub open spec fn spec_efer_write_valid(&self, val: u64) -> bool {
+        let paging = self.guest_state.cr0 & CR0_PG != 0;
+        &&& val & EFER_RESERVED == 0
+        &&& efer_lma(val) == (efer_lme(val) && paging)
+        &&& paging ==> efer_lme(val) == efer_lme(self.spec_guest_efer())
+    }
+    
+    fn efer_write_valid(&self, val: u64) -> (result: bool)
+        ensures
+            result == self.spec_efer_write_valid(val),
+    {
+        let paging = self.guest_state.cr0 & CR0_PG != 0;
+        val & EFER_RESERVED == 0
+            && (val & EFER_LMA != 0) == (val & EFER_LME != 0 && paging)
+            && (!paging || (val & EFER_LME != 0) == (self.guest_efer & EFER_LME != 0))
+    }
+    
+    /// 规范函数：接受 EFER 写入后的状态
+    pub open spec fn spec_efer_written(&self, val: u64) -> ArchCpu {
+        ArchCpu {
+            guest_efer: val,
+            guest_state: GuestState { efer: val, ..self.guest_state },
+            ctls: VmcsControls {
+                entry_ctls: EntryCtls { ia32e_mode_guest: efer_lma(val), ..self.ctls.entry_ctls },
+                ..self.ctls
+            },
+            ..*self
+        }
+    }
+    
+    /// 写 IA32_EFER：MSR 可见值、VMCS guest IA32_EFER 字段与 IA-32e mode guest
+    /// 在同一步内更新，下次 entry 使用的模式与 guest 的 EFER 一致
+    fn handle_efer_write(&mut self, val: u64) -> (ok: bool)
+        requires
+            old(self).inv(),
+        ensures
+            self.inv(),
+            ok == old(self).spec_efer_write_valid(val),
+            ok ==> *self == old(self).spec_efer_written(val),
+            !ok ==> *self == *old(self),
+    {
+        if !self.efer_write_valid(val) {
+            return false;
         }
+        let lma = val & EFER_LMA != 0;
+        self.guest_efer = val;
+        self.guest_state.efer = val;
+        self.ctls.entry_ctls.ia32e_mode_guest = lma;
         
         proof {
-            assert(self.vmcs_configured);
-            assert(self.ready_for_vm_launch());
+            lemma_efer_write_coherent(*old(self), val);
         }
-        
-        // 步骤 3：启动 VM
-        unsafe {
-            Self::vmx_launch(self)
+        true
+    }
+    
+    /// 写 IA32_PAT：任一字节为保留内存类型时拒绝（注入 #GP）；
+    /// MSR 可见值与 VMCS guest IA32_PAT 字段同时更新
+    fn handle_pat_write(&mut self, val: u64) -> (ok: bool)
+        requires
+            old(self).inv(),
+        ensures
+            self.inv(),
+            ok == spec_pat_valid(val),
+            ok ==> *self == (ArchCpu {
+                guest_pat: val,
+                guest_state: GuestState { pat: val, ..old(self).guest_state },
+                ..*old(self)
+            }),
+            ok ==> self.pat_coherent(),
+            !ok ==> *self == *old(self),
+    {
+        if !pat_valid(val) {
+            return false;
         }
+        self.guest_pat = val;
+        self.guest_state.pat = val;
+        true
     }
-}
-
-/// 获取当前 APIC ID
-#[verifier::external_body]
-pub fn this_apic_id() -> (result: usize)
+    
+    /// 规范函数：guest 看到的 TSC
+    pub open spec fn spec_guest_tsc(&self) -> u64 {
+        ((spec_host_tsc() as int + self.tsc_offset as int) % 0x1_0000_0000_0000_0000) as u64
+    }
+    
+    pub fn guest_tsc(&self) -> (result: u64)
+        ensures
+            result == self.spec_guest_tsc(),
+    {
+        ((read_host_tsc() as u128 + self.tsc_offset as u128) % 0x1_0000_0000_0000_0000u128) as u64
+    }
+    
+    /// 写 IA32_TSC_DEADLINE：总是接受，LVT timer 不在 TSC-deadline 模式时忽略写入；
+    /// 只记录 deadline，是否到期由 entry 前的 poll_tsc_deadline 判断
+    fn handle_tsc_deadline_write(&mut self, val: u64) -> (ok: bool)
+        requires
+            old(self).inv(),
+        ensures
+            self.inv(),
+            ok,
+            old(self).virt_lapic.timer.mode is TscDeadline ==> *self == (ArchCpu {
+                virt_lapic: VirtLocalApic {
+                    timer: ApicTimer { deadline: val, ..old(self).virt_lapic.timer },
+                    ..old(self).virt_lapic
+                },
+                ..*old(self)
+            }),
+            !(old(self).virt_lapic.timer.mode is TscDeadline) ==> *self == *old(self),
+    {
+        self.virt_lapic.write_tsc_deadline(val);
+        true
+    }
+    
+    /// 规范函数：guest 的 TSC-deadline 定时器到期时刻对应的 host TSC，未启动时为 None
+    /// 
+    /// guest TSC = host TSC + tsc_offset（模 2^64），反过来减去偏移
+    pub open spec fn spec_host_wake_tsc(&self) -> Option<u64> {
+        let timer = self.virt_lapic.timer;
+        if timer.mode is TscDeadline && timer.deadline != 0 {
+            Some(((timer.deadline as int + 0x1_0000_0000_0000_0000 - self.tsc_offset as int) % 0x1_0000_0000_0000_0000) as u64)
+        } else {
+            None
+        }
+    }
+    
+    fn host_wake_tsc(&self) -> (result: Option<u64>)
+        ensures
+            result == self.spec_host_wake_tsc(),
+    {
+        let deadline_mode = match self.virt_lapic.timer.mode {
+            TimerMode::TscDeadline => true,
+            _ => false,
+        };
+        if deadline_mode && self.virt_lapic.timer.deadline != 0 {
+            Some(((self.virt_lapic.timer.deadline as u128 + 0x1_0000_0000_0000_0000u128 - self.tsc_offset as u128)
+                % 0x1_0000_0000_0000_0000u128) as u64)
+        } else {
+            None
+        }
+    }
+    
+    /// 按 guest TSC 检查 TSC-deadline 定时器，到期时置位 IRR（只触发一次）
+    fn poll_tsc_deadline(&mut self)
+        requires
+            old(self).inv(),
+        ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            *self == (ArchCpu { virt_lapic: self.virt_lapic, ..*old(self) }),
+            old(self).virt_lapic.timer.spec_deadline_due(old(self).spec_guest_tsc()) ==> {
+                &&& self.virt_lapic.timer.deadline == 0
+                &&& old(self).virt_lapic.spec_lvt_delivers(LVT_TIMER as int) ==> self.virt_lapic.irr@
+                    == old(self).virt_lapic.irr@.update(
+                        old(self).virt_lapic.spec_lvt_entry(LVT_TIMER as int).spec_vector() as int, true)
+            },
+            !old(self).virt_lapic.timer.spec_deadline_due(old(self).spec_guest_tsc()) ==> *self == *old(self),
+    {
+        let tsc = self.guest_tsc();
+        self.virt_lapic.poll_tsc_deadline(tsc);
+    }
+    
+    /// 写 IA32_FEATURE_CONTROL：锁定之后只读，写入注入 #GP
+    fn handle_feature_control_write(&mut self, val: u64) -> (ok: bool)
+        requires
+            old(self).inv(),
+        ensures
+            self.inv(),
+            ok == (old(self).spec_feature_control() & FEATURE_CONTROL_LOCK == 0),
+            ok ==> *self == (ArchCpu { guest_feature_control: val, ..*old(self) }),
+            !ok ==> *self == *old(self),
+    {
+        if self.guest_feature_control & FEATURE_CONTROL_LOCK != 0 {
+            return false;
+        }
+        self.guest_feature_control = val;
+        true
+    }
+    
+    /// 处理 RDMSR 退出：结果写入 EDX:EAX，不支持的 MSR 注入 #GP
+    fn handle_msr_read(&mut self) -> (result: EmulationResult)
+        requires
+            old(self).inv(),
+            old(self).vmcs_configured,
+        ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            self.guest_state == old(self).guest_state,
+            old(self).spec_guest_msr(old(self).guest_regs.rcx as u32) is None ==> {
+                &&& result == EmulationResult::spec_gp()
+                &&& *self == *old(self)
+            },
+            old(self).spec_guest_msr(old(self).guest_regs.rcx as u32) is Some ==>
+                result == EmulationResult::Advance(VM_EXIT_INSTR_LEN_RDMSR),
+    {
+        let msr = self.guest_regs.rcx as u32;
+        match self.guest_msr(msr) {
+            Some(val) => {
+                self.guest_regs.rax = val & 0xffff_ffff;
+                self.guest_regs.rdx = val >> 32u64;
+                EmulationResult::Advance(VM_EXIT_INSTR_LEN_RDMSR)
+            },
+            None => EmulationResult::gp(),
+        }
+    }
+    
+    /// 处理 WRMSR 退出：写入值为 EDX:EAX，非法写入注入 #GP 且不推进 RIP
+    fn handle_msr_write(&mut self) -> (result: EmulationResult)
+        requires
+            old(self).inv(),
+            old(self).vmcs_configured,
+        ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            self.guest_state.rip == old(self).guest_state.rip,
+            ({
+                let msr = old(self).guest_regs.rcx as u32;
+                let val = spec_msr_value(old(self).guest_regs.rax, old(self).guest_regs.rdx);
+                msr != IA32_X2APIC_EOI ==> {
+                &&& old(self).spec_msr_write_accepted(msr, val) ==> {
+                    &&& result == EmulationResult::Advance(VM_EXIT_INSTR_LEN_WRMSR)
+                    &&& msr != IA32_TSC_DEADLINE && msr != IA32_APIC_BASE ==> self.spec_guest_msr(msr) == Some(val)
+                    &&& msr == IA32_APIC_BASE
+                        ==> self.spec_guest_msr(msr) == Some(old(self).virt_lapic.spec_apic_base_written(val))
+                    &&& forall|other: u32| other != msr && !spec_msr_write_affects(msr, other)
+                        ==> #[trigger] self.spec_guest_msr(other) == old(self).spec_guest_msr(other)
+                }
+                &&& !old(self).spec_msr_write_accepted(msr, val) ==> {
+                    &&& result == EmulationResult::spec_gp()
+                    &&& *self == *old(self)
+                }
+                &&& msr == IA32_TSC_DEADLINE ==> self.virt_lapic.spec_tsc_deadline_read()
+                    == if old(self).virt_lapic.timer.mode is TscDeadline { val } else { 0 }
+                }
+            }),
+    {
+        let msr = self.guest_regs.rcx as u32;
+        if msr == IA32_X2APIC_EOI {
+            return self.handle_x2apic_eoi_write();
+        }
+        let val = msr_value(self.guest_regs.rax, self.guest_regs.rdx);
+        if self.set_guest_msr(msr, val) {
+            EmulationResult::Advance(VM_EXIT_INSTR_LEN_WRMSR)
+        } else {
+            EmulationResult::gp()
+        }
+    }
+    
+    /// 虚拟 LAPIC 处于 x2APIC 模式（x2APIC MSR 只在此模式下可访问）
+    fn x2apic_mode(&self) -> (result: bool)
+        ensures
+            result == self.virt_lapic.mode is X2Apic,
+    {
+        match self.virt_lapic.mode {
+            ApicMode::X2Apic => true,
+            _ => false,
+        }
+    }
+    
+    /// 处理 guest 写 x2APIC EOI（WRMSR 0x80b）
+    /// 
+    /// 只在 x2APIC 模式下可访问，写入非零值注入 #GP；EOI 只把最高在服务向量移出 ISR，
+    /// 不直接注入，下次 entry 前由 maybe_inject_pending 重新评估 IRR
+    fn handle_x2apic_eoi_write(&mut self) -> (result: EmulationResult)
+        requires
+            old(self).inv(),
+        ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            *self == (ArchCpu { virt_lapic: self.virt_lapic, ..*old(self) }),
+            ({
+                let val = spec_msr_value(old(self).guest_regs.rax, old(self).guest_regs.rdx);
+                &&& !(old(self).virt_lapic.mode is X2Apic) || val != 0 ==> {
+                    &&& result == EmulationResult::spec_gp()
+                    &&& *self == *old(self)
+                }
+                &&& old(self).virt_lapic.mode is X2Apic && val == 0 ==> {
+                    &&& result == EmulationResult::Advance(VM_EXIT_INSTR_LEN_WRMSR)
+                    &&& self.virt_lapic == (VirtLocalApic { isr: self.virt_lapic.isr, ..old(self).virt_lapic })
+                }
+            }),
+    {
+        let val = msr_value(self.guest_regs.rax, self.guest_regs.rdx);
+        let x2apic = match self.virt_lapic.mode {
+            ApicMode::X2Apic => true,
+            _ => false,
+        };
+        if !x2apic || val != 0 {
+            return EmulationResult::gp();
+        }
+        let _ = self.virt_lapic.end_of_interrupt();
+        EmulationResult::Advance(VM_EXIT_INSTR_LEN_WRMSR)
+    }
+    
+    /// 处理 guest 写 x2APIC ICR（WRMSR，值为 EDX:EAX）：把 IPI 记入各目标 vCPU 的信箱
+    /// 
+    /// 不能发送的 ICR（保留向量、SMI、保留的投递模式）注入 #GP，否则跳过 WRMSR；
+    /// 不带简写时目标 ID 来自 guest，发给不存在的 CPU 的 IPI 被忽略
+    fn handle_icr_write(&self, array: &mut PerCpuArray) -> (result: EmulationResult)
+        requires
+            self.inv(),
+            old(array).is_valid(),
+            old(array).in_range(self.cpuid),
+        ensures
+            array.is_valid(),
+            array.num_cpus == old(array).num_cpus,
+            ({
+                let icr = spec_msr_value(self.guest_regs.rax, self.guest_regs.rdx);
+                &&& !spec_icr_send_valid(icr) ==> {
+                    &&& result == EmulationResult::spec_gp()
+                    &&& array.mailboxes@ == old(array).mailboxes@
+                }
+                &&& spec_icr_send_valid(icr) ==> {
+                    &&& result == EmulationResult::Advance(VM_EXIT_INSTR_LEN_WRMSR)
+                    &&& array.spec_ipi_sent(old(array), self.cpuid, icr)
+                }
+            }),
+    {
+        let icr = msr_value(self.guest_regs.rax, self.guest_regs.rdx);
+        if icr_send_valid(icr) && icr_shorthand(icr) == ICR_SHORTHAND_NONE {
+            let dest = icr_dest(icr);
+            if dest >= MAX_CPU_NUM as u64 || array.try_get_cpu(dest as usize).is_none() {
+                proof {
+                    assert forall|j: int| 0 <= j < array.num_cpus implies
+                        !spec_icr_targets(icr, self.cpuid, j as usize) by {
+                        assert(j as usize as u64 != dest);
+                    }
+                }
+                return EmulationResult::Advance(VM_EXIT_INSTR_LEN_WRMSR);
+            }
+        }
+        if self.send_ipi(array, icr) {
+            EmulationResult::Advance(VM_EXIT_INSTR_LEN_WRMSR)
+        } else {
+            EmulationResult::gp()
+        }
+    }
+    
+    /// 按 icr 把 IPI 记入每个目标 vCPU 的信箱并通知目标，返回 false 表示 ICR 不能发送（信箱不变）
+    /// 
+    /// 发给自己的 IPI 同样进入自己的信箱，在下一次 entry 前由 drain_ipis 取走；
+    /// 这里从不修改任何 vCPU 的 ArchCpu，目标的虚拟 LAPIC 只由它自己的物理 CPU 更新
+    fn send_ipi(&self, array: &mut PerCpuArray, icr: u64) -> (ok: bool)
+        requires
+            self.inv(),
+            old(array).is_valid(),
+            old(array).in_range(self.cpuid),
+        ensures
+            array.is_valid(),
+            array.num_cpus == old(array).num_cpus,
+            ok == spec_icr_send_valid(icr),
+            !ok ==> array.mailboxes@ == old(array).mailboxes@,
+            ok ==> array.spec_ipi_sent(old(array), self.cpuid, icr),
+    {
+        if !icr_send_valid(icr) {
+            return false;
+        }
+        
+        let sender = self.cpuid;
+        let n = array.num_cpus;
+        let mut j: usize = 0;
+        while j < n
+            invariant
+                j <= n,
+                n == old(array).num_cpus,
+                sender == self.cpuid,
+                spec_icr_send_valid(icr),
+                array.is_valid(),
+                array.num_cpus == n,
+                forall|k: int| #![trigger array.mailboxes@[k]] 0 <= k < j ==>
+                    if spec_icr_targets(icr, sender, k as usize) {
+                        array.mailboxes@[k].spec_posted(&old(array).mailboxes@[k], icr)
+                    } else {
+                        array.mailboxes@[k] == old(array).mailboxes@[k]
+                    },
+                forall|k: int| j <= k < n ==> #[trigger] array.mailboxes@[k] == old(array).mailboxes@[k],
+            decreases n - j,
+        {
+            if icr_targets(icr, sender, j) {
+                let posted = array.try_post(j, icr);
+                assert(posted);
+                if j != sender {
+                    notify_cpu(j);
+                }
+            }
+            j = j + 1;
+        }
+        true
+    }
+    
+    /// 规范函数：guest 写入 virtual-APIC page 的 ICR，统一成 x2APIC 格式（目标位于 bits 63:32）
+    pub open spec fn spec_guest_icr(&self) -> u64 {
+        if self.virt_lapic.mode is X2Apic {
+            spec_virtual_apic_icr()
+        } else {
+            spec_xapic_icr_to_x2apic(spec_virtual_apic_icr())
+        }
+    }
+    
+    fn read_guest_icr(&self) -> (result: u64)
+        ensures
+            result == self.spec_guest_icr(),
+    {
+        let icr = read_virtual_apic_icr();
+        if self.x2apic_mode() {
+            icr
+        } else {
+            xapic_icr_to_x2apic(icr)
+        }
+    }
+    
+    /// 处理 APIC-write 退出（APIC-register virtualization）
+    /// 
+    /// 退出是 trap 类的：写入已经落在 virtual-APIC page 中且 RIP 已推进。
+    /// 按 exit qualification 的偏移重新读出寄存器并补上副作用：
+    /// ICR 低半写入发送 IPI（不能发送的 ICR 是 APIC 错误，经 LVT error 投递），
+    /// TPR 同步到虚拟 LAPIC，EOI（未开 virtual-interrupt delivery 时）结束最高优先级的中断，
+    /// LVT 写入按 write_lvt 检查，非法写入忽略
+    fn handle_apic_write(&mut self, array: &mut PerCpuArray) -> (result: EmulationResult)
+        requires
+            old(self).inv(),
+            old(self).vmcs_configured,
+            old(self).ctls.apic_register_virt_enabled(),
+            old(array).is_valid(),
+            old(array).in_range(old(self).cpuid),
+        ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            array.is_valid(),
+            array.num_cpus == old(array).num_cpus,
+            *self == (ArchCpu { virt_lapic: self.virt_lapic, ..*old(self) }),
+            result is Reenter,
+            ({
+                let offset = (spec_exit_qualification() & 0xfff) as u32;
+                let icr = old(self).spec_guest_icr();
+                &&& offset == APIC_REG_ICR_LOW && spec_icr_send_valid(icr) ==> {
+                    &&& array.spec_ipi_sent(old(array), old(self).cpuid, icr)
+                    &&& *self == *old(self)
+                }
+                &&& offset == APIC_REG_ICR_LOW && !spec_icr_send_valid(icr) ==> {
+                    &&& array.mailboxes@ == old(array).mailboxes@
+                    &&& !old(self).virt_lapic.spec_lvt_delivers(LVT_ERROR as int) ==> *self == *old(self)
+                }
+                &&& offset != APIC_REG_ICR_LOW ==> array.mailboxes@ == old(array).mailboxes@
+                &&& offset == APIC_REG_TPR ==> self.virt_lapic.tpr == spec_virtual_apic_tpr()
+            }),
+    {
+        let offset = (read_exit_qualification() & 0xfff) as u32;
+        if offset == APIC_REG_ICR_LOW {
+            let icr = self.read_guest_icr();
+            if !self.send_ipi(array, icr) {
+                self.virt_lapic.signal_error();
+            }
+        } else if offset == APIC_REG_TPR {
+            self.virt_lapic.tpr = read_virtual_apic_tpr();
+        } else if offset == APIC_REG_EOI {
+            if !(self.ctls.proc_ctls.activate_secondary && self.ctls.secondary_ctls.virtual_interrupt_delivery) {
+                let _ = self.virt_lapic.end_of_interrupt();
+            }
+        } else if offset == APIC_REG_SVR {
+            self.virt_lapic.write_svr(read_virtual_apic_reg(APIC_REG_SVR));
+        } else if offset >= APIC_REG_LVT_TIMER && offset <= APIC_REG_LVT_ERROR && offset % 0x10 == 0 {
+            let idx = ((offset - APIC_REG_LVT_TIMER) / 0x10) as usize;
+            let _ = self.virt_lapic.write_lvt(idx, read_virtual_apic_reg(offset) as u64);
+        }
+        EmulationResult::Reenter
+    }
+    
+    /// 处理 virtual-EOI 退出（virtual-interrupt delivery，EOI-exit bitmap 中的向量）
+    /// 
+    /// exit qualification 的 bits 7:0 为 EOI 的向量，与非 APICv 的 EOI 一样把它移出 ISR；
+    /// 退出是 trap 类的，RIP 已推进
+    fn handle_virtual_eoi(&mut self) -> (result: EmulationResult)
+        requires
+            old(self).inv(),
+            old(self).vmcs_configured,
+            old(self).ctls.virtual_intr_delivery_enabled(),
+        ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            result is Reenter,
+            ({
+                let vector = (spec_exit_qualification() & 0xff) as u8;
+                &&& *self == (ArchCpu {
+                    virt_lapic: VirtLocalApic { isr: self.virt_lapic.isr, ..old(self).virt_lapic },
+                    ..*old(self)
+                })
+                &&& self.virt_lapic.isr@ == old(self).virt_lapic.isr@.update(vector as int, false)
+            }),
+    {
+        let vector = (read_exit_qualification() & 0xff) as u8;
+        self.virt_lapic.complete(vector);
+        EmulationResult::Reenter
+    }
+    
+    /// 处理 MONITOR 退出：模型中无需记录监视地址，直接跳过指令
+    fn handle_monitor(&mut self) -> (result: EmulationResult)
+        requires
+            old(self).inv(),
+            old(self).vmcs_configured,
+            old(self).ctls.proc_ctls.monitor_exiting,
+        ensures
+            self.inv(),
+            *self == *old(self),
+            result == EmulationResult::Advance(VM_EXIT_INSTR_LEN_MONITOR),
+    {
+        EmulationResult::Advance(VM_EXIT_INSTR_LEN_MONITOR)
+    }
+    
+    /// 处理 MWAIT 退出
+    /// 
+    /// 请求比 C1 更深的 C-state 时 guest 进入 Hlt 等待唤醒，ECX[0] 置位时屏蔽的中断也可唤醒；
+    /// C1 与 C0 提示（EAX[7:4] 为 0 或 0xF）当作 NOP。IRR 保持不变，唤醒标志由 wake_from_hlt 清除
+    fn handle_mwait(&mut self) -> (result: EmulationResult)
+        requires
+            old(self).inv(),
+            old(self).vmcs_configured,
+            old(self).ctls.proc_ctls.mwait_exiting,
+        ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            self.guest_state.rip == old(self).guest_state.rip,
+            result == EmulationResult::Advance(VM_EXIT_INSTR_LEN_MWAIT),
+            self.virt_lapic.irr == old(self).virt_lapic.irr,
+            mwait_requests_deep_cstate(old(self).guest_regs.rax) ==> {
+                &&& self.guest_state.activity is Hlt
+                &&& self.mwait_break_on_interrupt == (old(self).guest_regs.rcx & 1 != 0)
+            },
+            !mwait_requests_deep_cstate(old(self).guest_regs.rax) ==> *self == *old(self),
+    {
+        let hint = (self.guest_regs.rax >> 4u64) & 0xf;
+        if hint != 0 && hint != MWAIT_HINT_C0 {
+            self.guest_state.activity = ActivityState::Hlt;
+            self.mwait_break_on_interrupt = self.guest_regs.rcx & 1 != 0;
+        }
+        EmulationResult::Advance(VM_EXIT_INSTR_LEN_MWAIT)
+    }
+    
+    /// 退出报告 IRET 解除了 NMI 阻塞却没有执行完时恢复阻塞
+    /// 
+    /// 重新进入后被中止的 IRET 会再执行一次，必须看到与第一次相同的阻塞状态；
+    /// 在分发之前对所有退出统一处理，处理函数不必各自检查 bit 12
+    fn restore_nmi_blocking_for_iret(&mut self, reason: u32)
+        requires
+            old(self).inv(),
+        ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            *self == (ArchCpu {
+                guest_state: GuestState {
+                    interruptibility: Interruptibility {
+                        blocking_by_nmi: spec_nmi_blocking_on_reentry(
+                            old(self).guest_state.interruptibility.blocking_by_nmi,
+                            reason,
+                            spec_exit_qualification(),
+                            spec_exit_interruption_info(),
+                            spec_idt_vectoring_info(),
+                        ),
+                        ..old(self).guest_state.interruptibility
+                    },
+                    ..old(self).guest_state
+                },
+                ..*old(self)
+            }),
+    {
+        let qual = read_exit_qualification();
+        let intr_info = read_exit_interruption_info();
+        let idt_info = read_idt_vectoring_info();
+        if iret_unblocked_nmi(reason, qual, intr_info, idt_info) {
+            self.guest_state.interruptibility.blocking_by_nmi = true;
+        }
+    }
+    
+    /// 处理 IRET 解除 NMI 阻塞（NMI-window 退出）
+    /// 
+    /// virtual NMIs 下 guest 的 IRET 执行完毕后虚拟 NMI 阻塞解除并立即产生 NMI-window 退出；
+    /// 此时清除 blocking_by_nmi，再经 inject_permitted_event 注入待处理事件（排队的 NMI 会重新进入阻塞）。
+    /// IRET 中途产生异常或 EPT violation 时不会到达这里，由 restore_nmi_blocking_for_iret 恢复阻塞
+    fn handle_iret(&mut self)
+        requires
+            old(self).inv(),
+            old(self).vmcs_configured,
+            old(self).ctls.tracks_virtual_nmi(),
+        ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            old(self).entry_event is None
+                && !old(self).guest_state.interruptibility.blocking_by_mov_ss
+                && old(self).pending_events.next_event_to_inject() == Some(PendingEvent::Nmi) ==> {
+                &&& self.entry_event == Some(InjectedEvent::spec_nmi())
+                &&& self.guest_state.interruptibility.blocking_by_nmi
+                &&& !self.pending_events.nmi
+            },
+            self.guest_state.interruptibility.blocking_by_nmi
+                ==> self.entry_event == Some(InjectedEvent::spec_nmi()),
+            self.guest_state.rip == old(self).guest_state.rip,
+            self.spec_injected_interrupt_permitted(old(self)),
+    {
+        self.guest_state.interruptibility.blocking_by_nmi = false;
+        self.inject_permitted_event();
+    }
+    
+    /// 处理 TPR 低于阈值退出
+    /// 
+    /// guest 降低 TPR 后重新评估待注入事件；该退出是 trap 类，不推进 RIP。
+    /// 优先级不高于 TPR 的中断保持等待，绝不注入
+    fn handle_tpr_below_threshold(&mut self)
+        requires
+            old(self).inv(),
+            old(self).vmcs_configured,
+            old(self).ctls.proc_ctls.use_tpr_shadow,
+        ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            self.virt_lapic.tpr == spec_virtual_apic_tpr(),
+            self.guest_state.rip == old(self).guest_state.rip,
+            old(self).entry_event is None && self.entry_event is Some
+                && self.entry_event.unwrap().event_type is ExternalInterrupt
+                ==> spec_interrupt_deliverable(self.entry_event.unwrap().vector, self.virt_lapic.tpr),
+    {
+        self.virt_lapic.tpr = read_virtual_apic_tpr();
+        self.inject_permitted_event();
+    }
+    
+    /// 本次外部中断退出已应答的向量（interruption information 无效时为 None）
+    pub fn exit_interrupt_vector(&self) -> (result: Option<u8>)
+        ensures
+            result == spec_exit_intr_vector(spec_exit_interruption_info()),
+    {
+        exit_intr_vector(read_exit_interruption_info())
+    }
+    
+    /// 处理外部中断退出
+    /// 
+    /// 未开启 acknowledge interrupt on exit 时中断仍在 host 的 LAPIC 中，开中断后由 host 处理；
+    /// 已应答的中断按 vector_owner 交给 host（由 host 的处理函数 EOI），或者置入 guest 的 IRR。
+    /// 后者的物理 ISR 位只能由我们清除：guest 的 EOI 只作用于虚拟 LAPIC，不先向物理 LAPIC 写 EOI
+    /// 会挡住所有同级与更低优先级的中断。不推进 RIP
+    fn handle_external_interrupt(&mut self) -> (result: EmulationResult)
+        requires
+            old(self).inv(),
+            old(self).vmcs_configured,
+            old(self).ctls.pin_ctls.external_interrupt_exiting,
+        ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            result is Reenter,
+            ({
+                let vector = spec_exit_intr_vector(spec_exit_interruption_info());
+                &&& vector is None ==> *self == *old(self)
+                &&& vector matches Some(v) && old(self).vector_owner.spec_host_owns(v) ==> *self == *old(self)
+                &&& vector matches Some(v) && !old(self).vector_owner.spec_host_owns(v) ==> {
+                    &&& *self == (ArchCpu { virt_lapic: self.virt_lapic, ..*old(self) })
+                    &&& self.virt_lapic.irr@ == old(self).virt_lapic.irr@.update(v as int, true)
+                }
+            }),
+    {
+        match self.exit_interrupt_vector() {
+            None => {},
+            Some(vector) => {
+                if self.vector_owner.host_owns(vector) {
+                    forward_to_host(vector);
+                } else {
+                    self.virt_lapic.phys_lapic.end_of_interrupt();
+                    self.virt_lapic.set_irr(vector);
+                }
+            },
+        }
+        EmulationResult::Reenter
+    }
+    
+    /// 处理 SIPI（经 IPI 信箱或 SIPI 退出到达）
+    /// 
+    /// 处于 wait-for-SIPI 的 AP 从 CS:IP = (vector << 8):0 开始执行；
+    /// 其他活动状态下 SIPI 被忽略。wait-for-SIPI 的 vCPU 不进入 guest，而是在 prepare_entry_events 中
+    /// 等待，发送方经 IpiMailbox 记入的 SIPI 由 drain_ipis 取走后在这里唤醒它
+    fn handle_sipi(&mut self, vector: u8)
+        requires
+            old(self).inv(),
+            old(self).vmcs_configured,
+        ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            old(self).spec_guest_activity() is WaitForSipi ==> {
+                &&& self.spec_guest_activity() is Active
+                &&& self.guest_state.cs.selector == (vector as u16) << 8u16
+                &&& self.guest_state.cs.base == (vector as u64) << 12u64
+                &&& self.guest_state.rip == 0
+            },
+            !(old(self).spec_guest_activity() is WaitForSipi) ==> *self == *old(self),
+    {
+        if let ActivityState::WaitForSipi = self.guest_state.activity {
+            self.guest_state.cs.selector = (vector as u16) << 8u16;
+            self.guest_state.cs.base = (vector as u64) << 12u64;
+            self.guest_state.rip = 0;
+            vmcs_write_guest_rip(0);
+            self.guest_state.activity = ActivityState::Active;
+        }
+    }
+    
+    /// 处理中断窗口退出：guest 重新可接收中断，经 inject_permitted_event 注入待处理事件
+    fn handle_interrupt_window(&mut self)
+        requires
+            old(self).inv(),
+            old(self).vmcs_configured,
+            old(self).ctls.proc_ctls.interrupt_window_exiting,
+            old(self).ctls.pin_ctls.external_interrupt_exiting,
+        ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            self.guest_state.rip == old(self).guest_state.rip,
+            self.spec_injected_interrupt_permitted(old(self)),
+    {
+        self.inject_permitted_event();
+    }
+    
+    /// 处理任务切换退出
+    /// 
+    /// 64 位 hypervisor 不支持硬件任务切换：向 guest 注入 #GP（错误码为 TSS selector），
+    /// 不推进 RIP；qualification 含非法保留位时交给 handle_unknown
+    fn handle_task_switch(&mut self) -> (result: EmulationResult)
+        requires
+            old(self).inv(),
+            old(self).vmcs_configured,
+            old(self).cpuid == spec_this_cpu_id(),
+            old(self).core_end + ((old(self).cpuid + 1) * PER_CPU_SIZE) as u64 <= u64::MAX,
+        ensures
+            self.inv(),
+            *self == *old(self),
+            spec_decode_task_switch(spec_exit_qualification()) is Some,
+            result == (EmulationResult::InjectFault {
+                vector: EXCEPTION_GP,
+                error_code: Some((spec_decode_task_switch(spec_exit_qualification()).unwrap().selector & 0xfffc) as u32),
+            }),
+    {
+        let qual = read_exit_qualification();
+        match decode_task_switch(qual) {
+            Some(ts) => EmulationResult::InjectFault {
+                vector: EXCEPTION_GP,
+                error_code: Some((ts.selector & 0xfffc) as u32),
+            },
+            None => self.handle_unknown(read_basic_exit_reason()),
+        }
+    }
+    
+    /// 处理 RDRAND / RDSEED 退出
+    /// 
+    /// host 提供随机数时按操作数大小写入目的寄存器并置 CF = 1；暂无熵时按 ISA 把目的寄存器
+    /// 清零并置 CF = 0。两种情况都清除 OF/SF/ZF/AF/PF 并跳过指令；操作数大小非法时注入 #UD
+    fn emulate_rand(&mut self, seed: bool) -> (result: EmulationResult)
+        requires
+            old(self).inv(),
+            old(self).vmcs_configured,
+        ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            self.ctls == old(self).ctls,
+            self.guest_state.rip == old(self).guest_state.rip,
+            spec_decode_rand_instr_info(spec_exit_instr_info()) is None ==> {
+                &&& result == EmulationResult::spec_ud()
+                &&& *self == *old(self)
+            },
+            spec_decode_rand_instr_info(spec_exit_instr_info()) matches Some(dest) ==> ({
+                let entropy = spec_host_entropy(seed);
+                let val = if entropy is Some { entropy.unwrap() } else { 0 };
+                &&& result == EmulationResult::Advance(spec_exit_instr_len())
+                &&& self.spec_guest_gpr(dest.reg)
+                    == spec_sized_write(old(self).spec_guest_gpr(dest.reg), val, dest.size)
+                &&& forall|r: u8| r < 16 && r != dest.reg
+                    ==> #[trigger] self.spec_guest_gpr(r) == old(self).spec_guest_gpr(r)
+                &&& self.guest_state.rflags == spec_rand_rflags(old(self).guest_state.rflags, entropy is Some)
+            }),
+    {
+        let dest = match decode_rand_instr_info(read_exit_instr_info()) {
+            Some(dest) => dest,
+            None => return EmulationResult::ud(),
+        };
+        let entropy = read_host_entropy(seed);
+        // CF = 0 时 ISA 规定目的寄存器清零
+        let val = match entropy {
+            Some(v) => v,
+            None => 0,
+        };
+        let old_val = self.guest_gpr(dest.reg);
+        self.set_guest_gpr(dest.reg, sized_write(old_val, val, dest.size));
+        self.guest_state.rflags = rand_rflags(self.guest_state.rflags, entropy.is_some());
+        EmulationResult::Advance(read_exit_instr_len())
+    }
+    
+    /// 处理 RDRAND 退出（secondary control bit 11）
+    fn handle_rdrand(&mut self) -> (result: EmulationResult)
+        requires
+            old(self).inv(),
+            old(self).vmcs_configured,
+            old(self).ctls.rdrand_exiting_enabled(),
+        ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            self.guest_state.rip == old(self).guest_state.rip,
+            spec_decode_rand_instr_info(spec_exit_instr_info()) matches Some(dest) ==> ({
+                let entropy = spec_host_entropy(false);
+                &&& entropy is None ==> self.spec_guest_gpr(dest.reg)
+                    == spec_sized_write(old(self).spec_guest_gpr(dest.reg), 0, dest.size)
+                &&& (self.guest_state.rflags & RFLAGS_CF != 0 <==> entropy is Some)
+            }),
+    {
+        let result = self.emulate_rand(false);
+        proof {
+            lemma_rand_rflags_cf(old(self).guest_state.rflags);
+        }
+        result
+    }
+    
+    /// 处理 RDSEED 退出（secondary control bit 16）
+    fn handle_rdseed(&mut self) -> (result: EmulationResult)
+        requires
+            old(self).inv(),
+            old(self).vmcs_configured,
+            old(self).ctls.rdseed_exiting_enabled(),
+        ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            self.guest_state.rip == old(self).guest_state.rip,
+            spec_decode_rand_instr_info(spec_exit_instr_info()) matches Some(dest) ==> ({
+                let entropy = spec_host_entropy(true);
+                &&& entropy is None ==> self.spec_guest_gpr(dest.reg)
+                    == spec_sized_write(old(self).spec_guest_gpr(dest.reg), 0, dest.size)
+                &&& (self.guest_state.rflags & RFLAGS_CF != 0 <==> entropy is Some)
+            }),
+    {
+        let result = self.emulate_rand(true);
+        proof {
+            lemma_rand_rflags_cf(old(self).guest_state.rflags);
+        }
+        result
+    }
+    
+    /// 处理 GETSEC 退出（无条件退出）
+    /// 
+    /// 模型不支持 SMX，按 CR4.SMXE = 0 的架构行为注入 #UD，不前进 RIP
+    fn handle_getsec(&mut self) -> (result: EmulationResult)
+        requires
+            old(self).inv(),
+        ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            *self == *old(self),
+            result == EmulationResult::spec_ud(),
+    {
+        EmulationResult::ud()
+    }
+    
+    /// 规范函数：VMX 指令退出应注入的异常
+    /// 
+    /// guest 视角的 CR4.VMXE（read shadow）清零时为 #UD；否则 CPL != 0 时为 #GP(0)；
+    /// 其余情况 guest 从未进入 VMX operation：VMXON 因 IA32_FEATURE_CONTROL 未开启 VMX 而 #GP(0)，
+    /// 其他 VMX 指令为 #UD
+    pub open spec fn spec_nested_vmx_fault(&self, is_vmxon: bool) -> EmulationResult {
+        if self.guest_state.cr4_shadow & CR4_VMXE == 0 {
+            EmulationResult::spec_ud()
+        } else if self.spec_guest_cpl() != 0 {
+            EmulationResult::spec_gp()
+        } else if is_vmxon {
+            EmulationResult::spec_gp()
+        } else {
+            EmulationResult::spec_ud()
+        }
+    }
+    
+    /// 处理 VMX 指令退出（VMXON / VMCLEAR / VMLAUNCH / VMRESUME / VMREAD / VMWRITE /
+    /// VMPTRLD / VMPTRST / VMXOFF / INVEPT / INVVPID / VMFUNC）
+    /// 
+    /// 不支持嵌套虚拟化，指令从不被模拟：状态不变、不前进 RIP，只注入 spec_nested_vmx_fault 给出的异常
+    fn handle_nested_vmx(&mut self, is_vmxon: bool) -> (result: EmulationResult)
+        requires
+            old(self).inv(),
+        ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            *self == *old(self),
+            result == old(self).spec_nested_vmx_fault(is_vmxon),
+            !(result is Advance),
+    {
+        if self.guest_state.cr4_shadow & CR4_VMXE == 0 {
+            EmulationResult::ud()
+        } else if self.guest_cpl() != 0 {
+            EmulationResult::gp()
+        } else if is_vmxon {
+            EmulationResult::gp()
+        } else {
+            EmulationResult::ud()
+        }
+    }
+    
+    /// 处理控制寄存器访问退出
+    /// 
+    /// 目前只模拟 MOV to CR0、MOV to CR3、MOV to CR4 与 MOV to / from CR8，其余访问交给 handle_unknown。
+    /// - CR0：见 handle_cr0_write，切换 PG 时 EFER.LMA 与 IA-32e mode guest 随之更新
+    /// - CR3：写入值按 MAXPHYADDR 与当前分页模式（PAE、PCID）的规则检查，保留位非零时注入 #GP(0) 且状态不变；
+    ///   合法时更新 guest 视角的 CR3，EPT 模式下同时写入 guest_state.cr3
+    ///   （影子页表模式下 guest_state.cr3 仍是影子页表根）
+    /// - CR4：与 CR0 / EFER 不一致或置位 VMXE 时注入 #GP(0)；否则 read shadow 记下写入值，
+    ///   真实的 guest CR4 保持 VMXE 置位，分页模式随之重新推出
+    fn handle_cr_access(&mut self) -> (result: EmulationResult)
+        requires
+            old(self).inv(),
+            old(self).vmcs_configured,
+            old(self).ctls.proc_ctls.cr3_load_exiting || old(self).ctls.cr0_mask != 0 || old(self).ctls.cr4_mask != 0
+                || old(self).ctls.proc_ctls.cr8_load_exiting || old(self).ctls.proc_ctls.cr8_store_exiting,
+            old(self).cpuid == spec_this_cpu_id(),
+            old(self).core_end + ((old(self).cpuid + 1) * PER_CPU_SIZE) as u64 <= u64::MAX,
+        ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            self.guest_state.rip == old(self).guest_state.rip,
+            ({
+                let q = spec_decode_cr_access(spec_exit_qualification());
+                let val = old(self).spec_guest_gpr(q.gpr);
+                let cr4 = old(self).guest_state.cr4;
+                let pae = old(self).spec_guest_paging_mode() is Pae;
+                &&& (q.access_type is MovToCr && (q.cr == 0 || q.cr == 3 || q.cr == 4))
+                    || (q.cr == 8 && (q.access_type is MovToCr || q.access_type is MovFromCr))
+                &&& q.cr == 0 && !spec_cr0_write_valid(val, cr4, old(self).spec_guest_efer()) ==> {
+                    &&& result == EmulationResult::spec_gp()
+                    &&& *self == *old(self)
+                }
+                &&& q.cr == 0 && spec_cr0_write_valid(val, cr4, old(self).spec_guest_efer()) ==> {
+                    &&& result == EmulationResult::Advance(spec_exit_instr_len())
+                    &&& *self == old(self).spec_cr0_written(val)
+                }
+                // MOV to CR8：bits 63:4 非零时 #GP，否则 TPR 的优先级类随之更新
+                &&& q.cr == 8 && q.access_type is MovToCr && val & !0xf != 0 ==> {
+                    &&& result == EmulationResult::spec_gp()
+                    &&& *self == *old(self)
+                }
+                &&& q.cr == 8 && q.access_type is MovToCr && val & !0xf == 0 ==> {
+                    &&& result == EmulationResult::Advance(spec_exit_instr_len())
+                    &&& self.spec_guest_cr8() == val
+                }
+                &&& q.cr == 8 && q.access_type is MovFromCr ==> {
+                    &&& result == EmulationResult::Advance(spec_exit_instr_len())
+                    &&& self.spec_guest_gpr(q.gpr) == old(self).spec_guest_cr8()
+                }
+                &&& q.cr == 3 && !spec_cr3_write_valid(val, cr4, pae, spec_max_phys_addr()) ==> {
+                    &&& result == EmulationResult::spec_gp()
+                    &&& *self == *old(self)
+                }
+                // PAE 分页下 MOV to CR3 重新装入 PDPTE，表项非法时 #GP
+                &&& q.cr == 3 && spec_cr3_write_valid(val, cr4, pae, spec_max_phys_addr())
+                    && old(self).spec_guest_paging_mode() is Pae
+                    && !spec_loaded_pdptes_valid(spec_cr3_loaded(val, cr4), spec_max_phys_addr()) ==> {
+                    &&& result == EmulationResult::spec_gp()
+                    &&& *self == *old(self)
+                }
+                &&& q.cr == 3 && spec_cr3_write_valid(val, cr4, pae, spec_max_phys_addr())
+                    && (old(self).spec_guest_paging_mode() is Pae
+                        ==> spec_loaded_pdptes_valid(spec_cr3_loaded(val, cr4), spec_max_phys_addr())) ==> {
+                    &&& result == EmulationResult::Advance(spec_exit_instr_len())
+                    &&& self.guest_cr3@ == spec_cr3_loaded(val, cr4)
+                    &&& old(self).spec_guest_paging_mode() is Pae ==> {
+                        &&& self.guest_pdptes_valid()
+                        &&& forall|i: int| 0 <= i < 4 ==> self.guest_state.pdptes[i]
+                            == spec_guest_pdpte(spec_cr3_loaded(val, cr4), i as usize)
+                    }
+                    &&& old(self).ctls.ept_enabled() ==> self.guest_state.cr3 == spec_cr3_loaded(val, cr4)
+                    &&& !old(self).ctls.ept_enabled() ==> self.guest_state.cr3 == old(self).guest_state.cr3
+                }
+                &&& q.cr == 4 && !spec_cr4_write_valid(val, old(self).guest_state.cr0, old(self).spec_guest_efer()) ==> {
+                    &&& result == EmulationResult::spec_gp()
+                    &&& *self == *old(self)
+                }
+                &&& q.cr == 4 && spec_cr4_write_valid(val, old(self).guest_state.cr0, old(self).spec_guest_efer()) ==> {
+                    &&& result == EmulationResult::Advance(spec_exit_instr_len())
+                    &&& *self == (ArchCpu {
+                        guest_state: GuestState { cr4: val | CR4_VMXE, cr4_shadow: val, ..old(self).guest_state },
+                        ..*old(self)
+                    })
+                    &&& spec_guest_visible_cr4(self.guest_state.cr4, self.guest_state.cr4_shadow, self.ctls.cr4_mask) == val
+                    &&& self.spec_guest_paging_mode()
+                        == spec_paging_mode(old(self).guest_state.cr0, val | CR4_VMXE, old(self).spec_guest_efer())
+                }
+            }),
+    {
+        let q = decode_cr_access(read_exit_qualification());
+        let is_mov_to = match q.access_type {
+            CrAccessType::MovToCr => true,
+            _ => false,
+        };
+        let is_mov_from = match q.access_type {
+            CrAccessType::MovFromCr => true,
+            _ => false,
+        };
+        if q.cr == 8 && (is_mov_to || is_mov_from) {
+            return self.handle_cr8_access(q);
+        }
+        if (q.cr != 0 && q.cr != 3 && q.cr != 4) || !is_mov_to {
+            self.handle_unknown(read_basic_exit_reason());
+        }
+        
+        let val = self.guest_gpr(q.gpr);
+        if q.cr == 0 {
+            return self.handle_cr0_write(val);
+        }
+        if q.cr == 4 {
+            if !cr4_write_valid(val, self.guest_state.cr0, self.guest_efer) {
+                return EmulationResult::gp();
+            }
+            proof { lemma_cr4_write_hides_vmxe(val, self.ctls.cr4_mask); }
+            self.guest_state.cr4 = val | CR4_VMXE;
+            self.guest_state.cr4_shadow = val;
+            return EmulationResult::Advance(read_exit_instr_len());
+        }
+        
+        let cr4 = self.guest_state.cr4;
+        let max_phys = max_phys_addr();
+        let pae = self.guest_state.cr0 & CR0_PG != 0 && self.guest_efer & EFER_LMA == 0
+            && cr4 & CR4_PAE != 0;
+        if !cr3_write_valid(val, cr4, pae, max_phys) {
+            return EmulationResult::gp();
+        }
+        let cr3 = cr3_loaded(val, cr4);
+        if pae {
+            match load_pdptes(cr3, max_phys) {
+                Some(pdptes) => self.guest_state.pdptes = pdptes,
+                None => return EmulationResult::gp(),
+            }
+        }
+        self.guest_cr3 = Ghost(cr3);
+        if self.ctls.proc_ctls.activate_secondary && self.ctls.secondary_ctls.enable_ept {
+            // EPT 下 guest CR3 直接装入 VMCS，guest_cr3_isolated 不约束
+            self.guest_state.cr3 = cr3;
+        }
+        EmulationResult::Advance(read_exit_instr_len())
+    }
+    
+    /// 规范函数：接受 CR0 写入后的状态
+    /// 
+    /// 真实 CR0 与 read shadow 都取写入值；EFER.LMA 按 LME && PG 重新推出，
+    /// MSR 可见值、VMCS guest IA32_EFER 字段与 IA-32e mode guest 同时更新
+    pub open spec fn spec_cr0_written(&self, val: u64) -> ArchCpu {
+        let efer = spec_efer_after_cr0(self.spec_guest_efer(), val);
+        ArchCpu {
+            guest_efer: efer,
+            guest_state: GuestState { cr0: val, cr0_shadow: val, efer, ..self.guest_state },
+            ctls: VmcsControls {
+                entry_ctls: EntryCtls { ia32e_mode_guest: efer_lma(efer), ..self.ctls.entry_ctls },
+                ..self.ctls
+            },
+            ..*self
+        }
+    }
+    
+    /// 模拟 MOV to CR0（CR0.PG 归 host 所有，切换分页时产生退出）
+    /// 
+    /// 写入值不合法时注入 #GP(0) 且状态不变；否则在同一步内更新 CR0 与 EFER.LMA，
+    /// 下次 entry 的 IA-32e mode guest 与 guest 的分页模式一致
+    fn handle_cr0_write(&mut self, val: u64) -> (result: EmulationResult)
+        requires
+            old(self).inv(),
+        ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            !spec_cr0_write_valid(val, old(self).guest_state.cr4, old(self).spec_guest_efer()) ==> {
+                &&& result == EmulationResult::spec_gp()
+                &&& *self == *old(self)
+            },
+            spec_cr0_write_valid(val, old(self).guest_state.cr4, old(self).spec_guest_efer()) ==> {
+                &&& result == EmulationResult::Advance(spec_exit_instr_len())
+                &&& *self == old(self).spec_cr0_written(val)
+                &&& self.efer_coherent()
+            },
+    {
+        if !cr0_write_valid(val, self.guest_state.cr4, self.guest_efer) {
+            return EmulationResult::gp();
+        }
+        let efer = efer_after_cr0(self.guest_efer, val);
+        proof {
+            lemma_cr0_write_coherent(*old(self), val);
+        }
+        self.guest_state.cr0 = val;
+        self.guest_state.cr0_shadow = val;
+        self.guest_efer = efer;
+        self.guest_state.efer = efer;
+        self.ctls.entry_ctls.ia32e_mode_guest = efer & EFER_LMA != 0;
+        EmulationResult::Advance(read_exit_instr_len())
+    }
+    
+    /// 规范函数：guest CR8，恒等于虚拟 LAPIC TPR 的优先级类（bits 7:4）
+    pub open spec fn spec_guest_cr8(&self) -> u64 {
+        (self.virt_lapic.tpr >> 4u8) as u64
+    }
+    
+    /// 处理 MOV to / from CR8
+    /// 
+    /// CR8 bits 3:0 对应 TPR bits 7:4；写入后 TPR 可能降低，重新评估待注入的中断
+    fn handle_cr8_access(&mut self, q: CrAccessQual) -> (result: EmulationResult)
+        requires
+            old(self).inv(),
+            q.cr == 8,
+            q.access_type is MovToCr || q.access_type is MovFromCr,
+            q.gpr < 16,
+        ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            self.guest_state.rip == old(self).guest_state.rip,
+            ({
+                let val = old(self).spec_guest_gpr(q.gpr);
+                &&& q.access_type is MovToCr && val & !0xf != 0 ==> {
+                    &&& result == EmulationResult::spec_gp()
+                    &&& *self == *old(self)
+                }
+                &&& q.access_type is MovToCr && val & !0xf == 0 ==> {
+                    &&& result == EmulationResult::Advance(spec_exit_instr_len())
+                    &&& self.spec_guest_cr8() == val
+                }
+                &&& q.access_type is MovFromCr ==> {
+                    &&& result == EmulationResult::Advance(spec_exit_instr_len())
+                    &&& self.spec_guest_gpr(q.gpr) == old(self).spec_guest_cr8()
+                }
+            }),
+    {
+        if let CrAccessType::MovFromCr = q.access_type {
+            let cr8 = (self.virt_lapic.tpr >> 4u8) as u64;
+            self.set_guest_gpr(q.gpr, cr8);
+            return EmulationResult::Advance(read_exit_instr_len());
+        }
+        
+        let val = self.guest_gpr(q.gpr);
+        if val & !0xfu64 != 0 {
+            return EmulationResult::gp();
+        }
+        let tpr = (val << 4u64) as u8;
+        proof {
+            assert(val & !0xfu64 == 0 ==> ((val << 4u64) as u8 >> 4u8) as u64 == val) by (bit_vector);
+        }
+        self.virt_lapic.tpr = tpr;
+        let _ = self.maybe_inject_pending();
+        EmulationResult::Advance(read_exit_instr_len())
+    }
+    
+    /// 规范函数：guest 调试寄存器（dr 已经过 DR4 / DR5 别名转换）
+    pub open spec fn spec_guest_dr(&self, dr: u8) -> u64 {
+        if dr < 4 {
+            self.guest_state.drs[dr as int]
+        } else if dr == 6 {
+            self.guest_state.dr6
+        } else {
+            self.guest_state.dr7
+        }
+    }
+    
+    /// 处理 MOV to / from DR
+    /// 
+    /// DR4 / DR5 按 CR4.DE 映射到 DR6 / DR7 或产生 #UD；写 DR6 / DR7 时 bits 63:32 非零产生 #GP
+    fn handle_mov_dr(&mut self) -> (result: EmulationResult)
+        requires
+            old(self).inv(),
+            old(self).ctls.proc_ctls.mov_dr_exiting,
+        ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            self.guest_state.rip == old(self).guest_state.rip,
+            ({
+                let q = spec_decode_dr_access(spec_exit_qualification());
+                let idx = spec_dr_alias(q.dr, old(self).guest_state.cr4);
+                let val = old(self).spec_guest_gpr(q.gpr);
+                &&& idx is None ==> {
+                    &&& result == EmulationResult::spec_ud()
+                    &&& *self == *old(self)
+                }
+                &&& idx matches Some(i) && !q.to_dr ==> {
+                    &&& result == EmulationResult::Advance(spec_exit_instr_len())
+                    &&& self.spec_guest_gpr(q.gpr) == old(self).spec_guest_dr(i)
+                }
+                &&& idx matches Some(i) && q.to_dr && i >= 6 && val & DR_HIGH_RESERVED != 0 ==> {
+                    &&& result == EmulationResult::spec_gp()
+                    &&& *self == *old(self)
+                }
+                &&& idx matches Some(i) && q.to_dr && !(i >= 6 && val & DR_HIGH_RESERVED != 0) ==> {
+                    &&& result == EmulationResult::Advance(spec_exit_instr_len())
+                    &&& i < 4 ==> self.spec_guest_dr(i) == val
+                    &&& i == 6 ==> self.guest_state.dr6 == spec_dr6_written(val)
+                    &&& i == 7 ==> self.guest_state.dr7 == spec_dr7_written(val)
+                }
+            }),
+    {
+        let q = decode_dr_access(read_exit_qualification());
+        let idx = match dr_alias(q.dr, self.guest_state.cr4) {
+            Some(i) => i,
+            None => return EmulationResult::ud(),
+        };
+        
+        if !q.to_dr {
+            let val = if idx < 4 {
+                self.guest_state.drs[idx as usize]
+            } else if idx == 6 {
+                self.guest_state.dr6
+            } else {
+                self.guest_state.dr7
+            };
+            self.set_guest_gpr(q.gpr, val);
+            return EmulationResult::Advance(read_exit_instr_len());
+        }
+        
+        let val = self.guest_gpr(q.gpr);
+        if idx >= 6 && val & DR_HIGH_RESERVED != 0 {
+            return EmulationResult::gp();
+        }
+        if idx < 4 {
+            self.guest_state.drs.set(idx as usize, val);
+        } else if idx == 6 {
+            self.guest_state.dr6 = (val | DR6_FIXED_1) & !DR6_FIXED_0;
+        } else {
+            self.guest_state.dr7 = (val | DR7_FIXED_1) & !DR7_FIXED_0;
+        }
+        EmulationResult::Advance(read_exit_instr_len())
+    }
+    
+    /// 调用实现了 ExitHandler 的处理函数，契约由 trait 统一给出
+    /// 
+    /// 特权指令在 CPL != 0 时注入 #GP(0) 且不调用处理函数。硬件在产生退出之前已做这项检查，
+    /// 这里保证模拟路径不依赖这一点
+    fn dispatch<H: ExitHandler>(&mut self, handler: &H, array: &mut PerCpuArray) -> (result: EmulationResult)
+        requires
+            old(self).inv(),
+            old(self).vmcs_configured,
+            old(self).owned_by_current(),
+            old(self).core_end + ((old(self).cpuid + 1) * PER_CPU_SIZE) as u64 <= u64::MAX,
+            old(array).is_valid(),
+            old(array).in_range(old(self).cpuid),
+            handler.enabled(old(self)),
+        ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            array.is_valid(),
+            array.num_cpus == old(array).num_cpus,
+            handler.privileged() && old(self).spec_guest_cpl() != 0 ==> {
+                &&& result == EmulationResult::spec_gp()
+                &&& *self == *old(self)
+                &&& array.mailboxes@ == old(array).mailboxes@
+            },
+            !(handler.privileged() && old(self).spec_guest_cpl() != 0) ==> (handler.fixed_result() matches Some(r) ==> {
+                &&& result == r
+                &&& *self == *old(self)
+                &&& array.mailboxes@ == old(array).mailboxes@
+            }),
+    {
+        if handler.is_privileged() && self.guest_cpl() != 0 {
+            return EmulationResult::gp();
+        }
+        handler.handle(self, array)
+    }
+    
+    /// 处理 HLT 退出：跳过 HLT 指令，guest 进入 Hlt 状态等待唤醒
+    fn handle_hlt(&mut self) -> (result: EmulationResult)
+        requires
+            old(self).inv(),
+            old(self).vmcs_configured,
+        ensures
+            self.inv(),
+            *self == (ArchCpu {
+                guest_state: GuestState { activity: ActivityState::Hlt, ..old(self).guest_state },
+                ..*old(self)
+            }),
+            self.spec_guest_activity() is Hlt,
+            result == EmulationResult::Advance(VM_EXIT_INSTR_LEN_HLT),
+    {
+        self.guest_state.activity = ActivityState::Hlt;
+        EmulationResult::Advance(VM_EXIT_INSTR_LEN_HLT)
+    }
+    
+    /// 规范函数：MWAIT ECX[0] 置位后，IRR 中被 IF 屏蔽、未能注入的中断也能唤醒
+    pub open spec fn spec_mwait_masked_wake(&self) -> bool {
+        &&& self.mwait_break_on_interrupt
+        &&& self.virt_lapic.spec_software_enabled()
+        &&& exists|u: int| 0 <= u < NUM_VECTORS && #[trigger] self.virt_lapic.irr@[u]
+    }
+    
+    /// 规范函数：entry 前唤醒后的状态，只有能唤醒的事件才把 Hlt 变为 Active，并清除 MWAIT 的唤醒标志
+    pub open spec fn spec_woken(&self) -> ArchCpu {
+        if self.guest_state.activity is Hlt && (spec_wakes_hlt(self.entry_event) || self.spec_mwait_masked_wake()) {
+            ArchCpu {
+                guest_state: GuestState { activity: ActivityState::Active, ..self.guest_state },
+                mwait_break_on_interrupt: false,
+                ..*self
+            }
+        } else {
+            *self
+        }
+    }
+    
+    /// entry 前处理 Hlt 状态：本次 entry 注入外部中断或 NMI 时 guest 回到 Active；
+    /// MWAIT 请求了 ECX[0] 时 IRR 中有请求即可唤醒，中断留在 IRR 中等 guest 开中断
+    fn wake_from_hlt(&mut self)
+        requires
+            old(self).inv(),
+        ensures
+            self.inv(),
+            *self == old(self).spec_woken(),
+    {
+        let event_wakes = match &self.entry_event {
+            Some(event) => match event.event_type {
+                EventType::ExternalInterrupt => true,
+                EventType::Nmi => true,
+                _ => false,
+            },
+            None => false,
+        };
+        let masked_wakes = if self.mwait_break_on_interrupt {
+            match self.virt_lapic.highest_pending() {
+                Some(v) => {
+                    assert(self.virt_lapic.irr@[v as int]);
+                    true
+                },
+                None => false,
+            }
+        } else {
+            false
+        };
+        if let ActivityState::Hlt = self.guest_state.activity {
+            if event_wakes || masked_wakes {
+                self.guest_state.activity = ActivityState::Active;
+                self.mwait_break_on_interrupt = false;
+            }
+        }
+    }
+    
+    fn is_halted(&self) -> (result: bool)
+        ensures
+            result == self.spec_guest_activity() is Hlt,
+    {
+        if let ActivityState::Hlt = self.guest_state.activity { true } else { false }
+    }
+    
+    fn is_waiting_for_sipi(&self) -> (result: bool)
+        ensures
+            result == self.spec_guest_activity() is WaitForSipi,
+    {
+        if let ActivityState::WaitForSipi = self.guest_state.activity { true } else { false }
+    }
+    
+    /// 在 host 中等待能够唤醒 Hlt guest 的事件（信任边界）
+    /// 
+    /// wake_tsc 为 Some 时先把 host LAPIC 定时器设为该 host TSC 的 TSC-deadline，保证 guest 的
+    /// TSC-deadline 定时器到期时 hlt 一定返回（之后由 poll_tsc_deadline 置位 IRR）；再开中断执行 hlt，
+    /// host 的定时器与 IPI 处理函数可能在此期间置位 IRR 或记录待投递的 NMI，其余状态不变
+    #[verifier::external_body]
+    fn wait_for_wake_event(&mut self, wake_tsc: Option<u64>)
+        requires
+            old(self).inv(),
+            wake_tsc == old(self).spec_host_wake_tsc(),
+        ensures
+            self.inv(),
+            *self == (ArchCpu {
+                virt_lapic: self.virt_lapic,
+                pending_events: self.pending_events,
+                ..*old(self)
+            }),
+    {
+        // 硬件操作：wrmsr IA32_TSC_DEADLINE（host 定时器，wake_tsc 为 None 时不设置）; sti; hlt; cli
+    }
+    
+    /// 取走本 vCPU 信箱中的 IPI，投递到自己的虚拟 LAPIC 与 guest 状态
+    /// 
+    /// 依次处理 INIT（复位后进入 wait-for-SIPI）、SIPI（只在 wait-for-SIPI 下生效，见 handle_sipi）、
+    /// fixed 向量（置入 IRR）与 NMI（记为待投递），之后由 maybe_inject_pending 按优先级注入
+    fn drain_ipis(&mut self, array: &mut PerCpuArray)
+        requires
+            old(self).inv(),
+            old(self).vmcs_configured,
+            old(array).is_valid(),
+            old(array).in_range(old(self).cpuid),
+        ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            array.is_valid(),
+            array.num_cpus == old(array).num_cpus,
+            array.mailboxes@[self.cpuid as int].spec_is_empty(),
+            forall|j: int| 0 <= j < array.mailboxes.len() && j != self.cpuid
+                ==> #[trigger] array.mailboxes@[j] == old(array).mailboxes@[j],
+            ({
+                let mailbox = old(array).mailboxes@[old(self).cpuid as int];
+                &&& forall|v: int| 0 <= v < NUM_VECTORS && #[trigger] mailbox.irr@[v] ==> self.virt_lapic.irr@[v]
+                &&& mailbox.nmi ==> self.nmi_pending()
+                &&& mailbox.init && mailbox.sipi is None ==> self.spec_guest_activity() is WaitForSipi
+                &&& mailbox.spec_is_empty() ==> *self == *old(self)
+            }),
+    {
+        let mailbox: IpiMailbox = array.take(self.cpuid);
+        
+        if mailbox.init {
+            self.reset_vcpu_state();
+            self.guest_state.activity = ActivityState::WaitForSipi;
+        }
+        if let Some(vector) = mailbox.sipi {
+            self.handle_sipi(vector);
+        }
+        
+        let mut v: usize = 0;
+        while v < NUM_VECTORS
+            invariant
+                v <= NUM_VECTORS,
+                self.inv(),
+                self.exit_preserved(old(self)),
+                mailbox == old(array).mailboxes@[old(self).cpuid as int],
+                forall|u: int| 0 <= u < v && #[trigger] mailbox.irr@[u] ==> self.virt_lapic.irr@[u],
+                mailbox.init && mailbox.sipi is None ==> self.spec_guest_activity() is WaitForSipi,
+                mailbox.spec_is_empty() ==> *self == *old(self),
+            decreases NUM_VECTORS - v,
+        {
+            if mailbox.irr.test(v) {
+                self.virt_lapic.set_irr(v as u8);
+            }
+            v = v + 1;
+        }
+        
+        if mailbox.nmi {
+            self.request_nmi();
+        }
+    }
+    
+    /// 每次 entry 前的事件准备：取走 IPI 信箱，检查 TSC-deadline 定时器，注入可投递的事件，
+    /// 再按注入之后的 entry_event 判定 Hlt 唤醒
+    /// 
+    /// 没有唤醒事件时 guest 保持 Hlt，不进入 guest，在 host 中等待后重新判定；
+    /// wait-for-SIPI 的 vCPU 同样在 host 中等待，直到信箱中的 SIPI 经 handle_sipi 将其唤醒。
+    /// 返回时 guest 既不处于 Hlt 也不处于 wait-for-SIPI
+    #[verifier::exec_allows_no_decreases_clause]
+    fn prepare_entry_events(&mut self, array: &mut PerCpuArray)
+        requires
+            old(self).inv(),
+            old(self).vmcs_configured,
+            old(array).is_valid(),
+            old(array).in_range(old(self).cpuid),
+        ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            array.is_valid(),
+            array.num_cpus == old(array).num_cpus,
+            !(self.spec_guest_activity() is Hlt),
+            !(self.spec_guest_activity() is WaitForSipi),
+    {
+        self.drain_ipis(array);
+        self.poll_tsc_deadline();
+        let _ = self.maybe_inject_pending();
+        self.wake_from_hlt();
+        
+        while self.is_halted() || self.is_waiting_for_sipi()
+            invariant
+                self.inv(),
+                self.exit_preserved(old(self)),
+                array.is_valid(),
+                array.num_cpus == old(array).num_cpus,
+                array.in_range(self.cpuid),
+        {
+            let wake_tsc = self.host_wake_tsc();
+            self.wait_for_wake_event(wake_tsc);
+            self.drain_ipis(array);
+            self.poll_tsc_deadline();
+            let _ = self.maybe_inject_pending();
+            self.wake_from_hlt();
+        }
+    }
+    
+    /// 按本次注入判定写入 interrupt-window / NMI-window exiting（primary processor-based controls）
+    /// 
+    /// maybe_inject_pending 只记录请求，这里才真正打开或关闭这两个控制并 VMWRITE；
+    /// 请求随之清除，窗口退出之后由下一次判定重新请求。NMI 窗口依赖 virtual NMIs，
+    /// 能力 MSR 不允许改变时保持原值
+    fn program_event_windows(&mut self)
+        requires
+            old(self).inv(),
+            old(self).vmcs_configured,
+            old(self).vmcs_loaded,
+        ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            *self == (ArchCpu {
+                ctls: VmcsControls {
+                    proc_ctls: ProcCtls {
+                        interrupt_window_exiting: self.ctls.proc_ctls.interrupt_window_exiting,
+                        nmi_window_exiting: self.ctls.proc_ctls.nmi_window_exiting,
+                        ..old(self).ctls.proc_ctls
+                    },
+                    ..old(self).ctls
+                },
+                interrupt_window_requested: false,
+                nmi_window_requested: false,
+                written_fields: self.written_fields,
+                ..*old(self)
+            }),
+            ({
+                let want_intr = old(self).interrupt_window_requested && old(self).ctls.pin_ctls.external_interrupt_exiting;
+                let want_nmi = old(self).nmi_window_requested && old(self).ctls.pin_ctls.virtual_nmis;
+                let proc_ctls = ProcCtls {
+                    interrupt_window_exiting: want_intr,
+                    nmi_window_exiting: want_nmi,
+                    ..old(self).ctls.proc_ctls
+                };
+                ctls_allowed(proc_ctls.spec_bits(), spec_vmx_proc_ctls_cap()) ==> self.ctls.proc_ctls == proc_ctls
+            }),
+    {
+        let mut proc_ctls = self.ctls.proc_ctls;
+        proc_ctls.interrupt_window_exiting = self.interrupt_window_requested && self.ctls.pin_ctls.external_interrupt_exiting;
+        proc_ctls.nmi_window_exiting = self.nmi_window_requested && self.ctls.pin_ctls.virtual_nmis;
+        if ctls_allowed_by(proc_ctls.bits(), read_vmx_proc_ctls_cap()) {
+            self.ctls.proc_ctls = proc_ctls;
+        }
+        self.interrupt_window_requested = false;
+        self.nmi_window_requested = false;
+        
+        let bits = self.ctls.proc_ctls.bits();
+        proof {
+            lemma_field_widths(VmcsField::ProcBasedCtls);
+        }
+        self.vmcs_write(VmcsField::ProcBasedCtls, bits as u64);
+    }
+    
+    /// 丢弃 guest 的全部执行状态，回到 RESET 后的状态
+    fn reset_vcpu_state(&mut self)
+        requires
+            old(self).inv(),
+        ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            self.ctls == (VmcsControls {
+                entry_ctls: EntryCtls { ia32e_mode_guest: false, ..old(self).ctls.entry_ctls },
+                ..old(self).ctls
+            }),
+            self.spec_guest_efer() == 0,
+            self.guest_state.rip == RESET_RIP,
+            self.guest_state.activity is Active,
+            !self.guest_state.interruptibility.blocking_by_nmi,
+            self.guest_state.in_real_mode(),
+            !old(self).ctls.ept_enabled() ==> self.guest_state.cr3 == old(self).guest_state.cr3,
+            self.entry_event is None,
+            self.pending_events.next_event_to_inject() is None,
+            self.guest_regs == self.intended_guest_regs@,
+            self.guest_cr3@ == 0,
+    {
+        // 复位后的 RSP 由 GuestState::reset 写入 VMCS，rsp 槽保持原值
+        let unused_rsp = self.guest_regs._unused_rsp;
+        self.set_guest_regs(GeneralRegisters {
+            rax: 0, rcx: 0, rdx: 0, rbx: 0,
+            _unused_rsp: unused_rsp, rbp: 0, rsi: 0, rdi: 0,
+            r8: 0, r9: 0, r10: 0, r11: 0,
+            r12: 0, r13: 0, r14: 0, r15: 0,
+        });
+        self.prepare_launch_frame();
+        let shadow_cr3 = self.guest_state.cr3;
+        self.guest_state = GuestState::reset();
+        if !(self.ctls.proc_ctls.activate_secondary && self.ctls.secondary_ctls.enable_ept) {
+            // 影子页表模式下 guest CR3 是 hypervisor 的影子页表根，复位不替换
+            self.guest_state.cr3 = shadow_cr3;
+        }
+        // 复位后 EFER 清零，IA-32e mode guest 随之清零；清零后的 entry 控制仍须满足能力 MSR
+        let entry_ctls = EntryCtls { ia32e_mode_guest: false, ..self.ctls.entry_ctls };
+        if !ctls_allowed_by(entry_ctls.bits(), read_vmx_entry_ctls_cap()) {
+            cpu_fatal(FatalCpuError::InvalidGuestState);
+        }
+        self.guest_efer = 0;
+        self.guest_pat = PAT_DEFAULT;
+        self.ctls.entry_ctls.ia32e_mode_guest = false;
+        self.guest_cr3 = Ghost(0);
+        self.entry_event = None;
+        self.pending_events = PendingEvents::new();
+        self.mwait_break_on_interrupt = false;
+        
+        proof {
+            // 复位后 EFER 为 0，LMA 与 CR0.PG 的一致性平凡成立
+            assert(0u64 & EFER_LMA == 0) by (bit_vector);
+            assert(0u64 & EFER_LME == 0) by (bit_vector);
+        }
+    }
+    
+    /// VMCLEAR 当前 VMCS，使下次 entry 重新走 vmlaunch
+    fn vmclear_current(&mut self)
+        requires
+            old(self).inv(),
+            old(self).vmcs_region.is_allocated(),
+        ensures
+            self.inv(),
+            *self == (ArchCpu { vmcs_launched: false, ..*old(self) }),
+    {
+        let ptr = self.vmcs_phys_ptr();
+        self.vmclear_and_reload(ptr);
+    }
+    
+    /// VMCLEAR [ptr]，再 VMPTRLD [ptr]（信任边界）
+    /// 
+    /// VMCLEAR 把当前 VMCS 的数据写回同一个 region，VMPTRLD 重新装入它，已写入的字段与配置保留，
+    /// 只有 launch 状态被清除；全新的 VMCS 只来自 vmxon_and_load
+    #[verifier::external_body]
+    fn vmclear_and_reload(&mut self, ptr: u64)
+        requires
+            old(self).inv(),
+            old(self).vmcs_region.spec_frame() == Some(ptr),
+        ensures
+            self.inv(),
+            *self == (ArchCpu { vmcs_launched: false, ..*old(self) }),
+    {
+        // 硬件操作：VMCLEAR [ptr]，再 VMPTRLD [ptr]
+    }
+    
+    /// 处理三重错误退出：guest 已进入 shutdown，绝不 vmresume
+    /// 
+    /// 按 triple_fault_policy 复位 vCPU 并重新配置 VMCS 后经 enter_guest 从复位向量 vmlaunch，
+    /// 或直接 cpu_fatal
+    fn handle_triple_fault(&mut self, array: &mut PerCpuArray) -> !
+        requires
+            old(self).inv(),
+            old(self).vmcs_configured,
+            old(self).owned_by_current(),
+            old(array).is_valid(),
+            old(array).in_range(old(self).cpuid),
+    {
+        if let TripleFaultPolicy::Fatal = self.triple_fault_policy {
+            cpu_fatal(FatalCpuError::GuestTripleFault);
+        }
+        if !self.vmx_on || !self.vmcs_loaded {
+            boot_failed(BootStep::ActivateVmx, FatalCpuError::VmxActivationFailed);
+        }
+        
+        self.reset_vcpu_state();
+        self.vmclear_current();
+        
+        let ctls = self.ctls;
+        proof {
+            assert(0u64 & EFER_LMA == 0) by (bit_vector);
+            // reset_vcpu_state 只清除 IA-32e mode guest，并已按能力 MSR 检查过清除后的 entry 控制
+            assert(ctls.is_valid());
+        }
+        if !self.guest_cr3_installable(&ctls) {
+            cpu_fatal(FatalCpuError::InvalidGuestState);
+        }
+        let vmcs_result = self.setup_vmcs(RESET_RIP, 0, ctls);
+        if vmcs_result.is_err() {
+            boot_failed(BootStep::SetupVmcs, FatalCpuError::VmcsSetupFailed);
+        }
+        // 复位后处于实模式，需要 unrestricted guest
+        if !self.check_guest_state() {
+            cpu_fatal(FatalCpuError::InvalidGuestState);
+        }
+        
+        proof {
+            assert(self.guest_regs == self.intended_guest_regs@);
+            assert(!self.vmcs_launched && self.launch_frame_ready());
+            assert(self.ready_for_entry());
+        }
+        
+        // VMCLEAR 之后 vmcs_launched 为 false，enter_guest 走 vmlaunch
+        self.enter_guest(array)
+    }
+    
+    /// 规范函数：第一个不满足的 guest-state 检查（0 表示全部满足）
+    /// 
+    /// 1：注入的硬件异常向量 >= 32；2：实模式 guest 未启用 unrestricted guest；
+    /// 3：GDTR / IDTR / LDTR 不合法；4：blocking by SMI 置位；5：CR4 与 CR0 / EFER 不一致；
+    /// 6：RIP 或 RSP 不是规范地址；7：PAE 分页下 PDPTE 非法；8：CS / SS 的 RPL、DPL 与 CPL 不一致
+    pub open spec fn spec_failing_guest_check(&self) -> u32 {
+        if self.entry_event is Some && self.entry_event.unwrap().event_type is HardwareException
+            && self.entry_event.unwrap().vector >= 32 {
+            1
+        } else if self.guest_in_real_mode() && !self.ctls.unrestricted_guest_enabled() {
+            2
+        } else if !self.guest_state.descriptor_tables_valid() {
+            3
+        } else if self.guest_state.interruptibility.blocking_by_smi {
+            4
+        } else if !spec_cr4_valid(self.guest_state.cr4, self.guest_state.cr0, self.spec_guest_efer()) {
+            5
+        } else if !self.guest_addrs_canonical() {
+            6
+        } else if !self.guest_pdptes_valid() {
+            7
+        } else if !self.guest_state.privilege_consistent(self.ctls.unrestricted_guest_enabled()) {
+            8
+        } else {
+            0
+        }
+    }
+    
+    /// 重新执行 guest_state_consistent 的各项检查，定位失败的那一项
+    fn failing_guest_check(&self) -> (result: u32)
+        ensures
+            result == self.spec_failing_guest_check(),
+            (result == 0) == self.guest_state_consistent(),
+    {
+        let bad_event = match &self.entry_event {
+            Some(event) => match event.event_type {
+                EventType::HardwareException => event.vector >= 32,
+                _ => false,
+            },
+            None => false,
+        };
+        if bad_event {
+            return 1;
+        }
+        let real_mode = self.guest_state.cr0 & CR0_PE == 0;
+        if real_mode && !(self.ctls.proc_ctls.activate_secondary && self.ctls.secondary_ctls.unrestricted_guest) {
+            return 2;
+        }
+        if !self.guest_state.check_descriptor_tables() {
+            return 3;
+        }
+        if self.guest_state.interruptibility.blocking_by_smi {
+            return 4;
+        }
+        if !cr4_valid(self.guest_state.cr4, self.guest_state.cr0, self.guest_efer) {
+            return 5;
+        }
+        if !self.check_guest_addrs_canonical() {
+            return 6;
+        }
+        if !self.check_guest_pdptes() {
+            return 7;
+        }
+        let unrestricted = self.ctls.proc_ctls.activate_secondary && self.ctls.secondary_ctls.unrestricted_guest;
+        if !self.guest_state.check_privilege(unrestricted) {
+            return 8;
+        }
+        0
+    }
+    
+    /// 处理 VM entry 失败：按子原因记录诊断信息后停机
+    /// 
+    /// entry 失败时 guest 从未运行，本函数永不返回，既不推进 RIP 也不尝试 vmresume
+    fn handle_entry_failure(&mut self, reason: u32) -> !
+        requires
+            old(self).inv(),
+    {
+        let kind = match entry_failure_kind(reason) {
+            Some(kind) => kind,
+            None => cpu_fatal(FatalCpuError::VmEntryFailed),
+        };
+        match kind {
+            EntryFailureKind::InvalidGuestState => {
+                let failing = self.failing_guest_check();
+                log_entry_failure(reason, failing as u64);
+            },
+            EntryFailureKind::MsrLoading => {
+                // exit qualification 为出错的 MSR-load 表项序号（从 1 开始）
+                let index = read_exit_qualification();
+                log_entry_failure(reason, index);
+            },
+            EntryFailureKind::MachineCheck => self.handle_mce_on_entry(reason),
+        }
+        cpu_fatal(entry_failure_error(kind))
+    }
+    
+    /// 处理 VM entry 期间的 machine-check（基本原因 41）
+    /// 
+    /// guest 从未运行，硬件状态不可信，不尝试恢复：记录 MCIP 后升级为致命错误，
+    /// 这条路径上不会再有 vmlaunch / vmresume
+    fn handle_mce_on_entry(&mut self, reason: u32) -> !
+        requires
+            old(self).inv(),
+    {
+        let pending = mce_pending();
+        log_entry_failure(reason, if pending { 1 } else { 0 });
+        cpu_fatal(FatalCpuError::EntryMachineCheck)
+    }
+    
+    /// 处理 EPT misconfiguration 退出
+    /// 
+    /// 与 EPT violation 不同，这说明 hypervisor 构造了格式错误的 EPT 表项（map_page 只产生 well_formed 表项），
+    /// 属于 hypervisor 自身的错误，不尝试修补映射
+    fn handle_ept_misconfig(&mut self) -> !
+        requires
+            old(self).inv(),
+    {
+        cpu_fatal(FatalCpuError::EptMisconfiguration)
+    }
+    
+    /// 处理 EPT violation 退出
+    /// 
+    /// 为 gpa 补上映射后不前进 RIP，重新执行引起违例的指令（被中止的 IRET 已由
+    /// restore_nmi_blocking_for_iret 恢复 NMI 阻塞）。无法建立映射的访问交给 handle_unknown 停住该 vCPU
+    fn handle_ept_violation(&mut self, reason: u32) -> (result: EmulationResult)
+        requires
+            old(self).inv(),
+            old(self).cpuid == spec_this_cpu_id(),
+            old(self).core_end + ((old(self).cpuid + 1) * PER_CPU_SIZE) as u64 <= u64::MAX,
+        ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            result is Reenter,
+            spec_ept_fault_resolvable(spec_guest_physical_address()),
+            *self == *old(self),
+    {
+        let gpa = read_guest_physical_address();
+        if !resolve_ept_fault(gpa) {
+            self.handle_unknown(reason);
+        }
+        EmulationResult::Reenter
+    }
+    
+    /// 处理 SMI 退出（I/O SMI / other SMI）
+    /// 
+    /// 模型不支持 SMM 与 dual-monitor treatment，SMI 不能被当作无事发生而 vmresume；
+    /// 交给 handle_unknown 记录并停住该 vCPU，永不返回
+    fn handle_smi(&mut self, reason: u32) -> !
+        requires
+            old(self).inv(),
+            old(self).cpuid == spec_this_cpu_id(),
+            old(self).core_end + ((old(self).cpuid + 1) * PER_CPU_SIZE) as u64 <= u64::MAX,
+    {
+        self.handle_unknown(reason)
+    }
+    
+    /// 处理未实现的退出原因：记录后经 idle 路径停住该 vCPU
+    /// 
+    /// 永不返回，保证未知原因不会带着原状态回到 vmresume
+    fn handle_unknown(&mut self, reason: u32) -> !
+        requires
+            old(self).inv(),
+            old(self).cpuid == spec_this_cpu_id(),
+            old(self).core_end + ((old(self).cpuid + 1) * PER_CPU_SIZE) as u64 <= u64::MAX,
+    {
+        log_unhandled_exit(reason);
+        self.idle()
+    }
+    
+    /// VM Exit 处理器（dispatcher）
+    /// 
+    /// 返回即说明某个已知处理函数已运行；其余原因都在 handle_unknown 中发散
+    fn vmexit_handler(&mut self, array: &mut PerCpuArray)
+        requires
+            old(self).inv(),
+            old(self).vmcs_configured,
+            // vmx_exit_continue 已经过 complete_entry（见 spec_after_entry）
+            old(self).entry_event is None,
+            old(self).owned_by_current(),
+            old(self).core_end + ((old(self).cpuid + 1) * PER_CPU_SIZE) as u64 <= u64::MAX,
+            old(array).is_valid(),
+            old(array).in_range(old(self).cpuid),
+        ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            array.is_valid(),
+            array.num_cpus == old(array).num_cpus,
+            is_handled_exit(spec_exit_reason()),
+            old(self).exit_count(spec_exit_reason()) < u64::MAX
+                ==> self.exit_count(spec_exit_reason()) > old(self).exit_count(spec_exit_reason()),
+            // GETSEC 不被模拟：注入 #UD，RIP 不前进
+            spec_exit_reason() == EXIT_REASON_GETSEC ==> {
+                &&& self.entry_event == Some(InjectedEvent::spec_exception(EXCEPTION_UD, None))
+                &&& self.guest_state.rip == old(self).guest_state.rip
+            },
+    {
+        self.assert_owned_by_current();
+        
+        // 先回收投递中被打断的事件，保证不会在退出中丢失；无法排队的异常按三重错误处理
+        if self.stash_idt_vectoring_event().is_err() {
+            self.handle_triple_fault(array);
+        }
+        
+        let raw = read_exit_reason();
+        
+        // entry 失败的退出原因 bit 31 置位，guest 从未运行，不能按普通退出处理
+        if is_entry_failure(raw) {
+            self.handle_entry_failure(raw);
+        }
+        
+        // 之后只看基本退出原因
+        let reason = raw & EXIT_REASON_BASIC_MASK;
+        
+        self.record_exit(reason);
+        self.restore_nmi_blocking_for_iret(reason);
+        
+        // 外部中断退出最频繁，先于完整分发检查；host 已处理该中断，直接返回 guest
+        // （与完整分发中 ExternalInterrupt 分支得到相同的状态，见 lemma_fast_path_matches_dispatch）
+        if is_fast_path_exit(reason, &self.ctls) {
+            proof {
+                lemma_fast_path_matches_dispatch(reason, self.ctls, spec_exit_interruption_info());
+            }
+            return;
+        }
+        
+        // 三重错误与 SMI 不属于已支持的原因，各自发散；其余原因只有控制位放行时才运行处理函数
+        let exit = match ExitReason::from_raw(reason) {
+            Some(ExitReason::TripleFault) => self.handle_triple_fault(array),
+            Some(ExitReason::IoSmi) | Some(ExitReason::OtherSmi) => self.handle_smi(reason),
+            Some(exit) => exit,
+            None => self.handle_unknown(reason),
+        };
+        if !exit.dispatch_enabled(&self.ctls) {
+            self.handle_unknown(reason);
+        }
+        proof {
+            lemma_exit_dispatch_total(reason, self.ctls, spec_exit_interruption_info());
+        }
+        
+        // 通配分支不可达（见 lemma_exit_dispatch_total），即 match 覆盖全部放行的原因；
+        // 每个分支都经 dispatch 调用 ExitHandler，特权检查与保持契约由 trait 统一给出；
+        // 各处理函数只返回模拟结果，RIP 推进与异常注入统一由 apply_emulation_result 完成
+        let result = match exit {
+            ExitReason::ExceptionNmi => self.dispatch(&ExceptionNmiExit, array),
+            ExitReason::ExternalInterrupt => self.dispatch(&ExternalInterruptExit, array),
+            ExitReason::InterruptWindow => {
+                proof {
+                    lemma_interrupt_window_requires_ext_exiting(self.ctls);
+                }
+                self.dispatch(&InterruptWindowExit, array)
+            },
+            ExitReason::NmiWindow => self.dispatch(&NmiWindowExit, array),
+            ExitReason::TprBelowThreshold => self.dispatch(&TprBelowThresholdExit, array),
+            ExitReason::StartupIpi => self.dispatch(&StartupIpiExit, array),
+            ExitReason::TaskSwitch => self.dispatch(&TaskSwitchExit, array),
+            ExitReason::EptMisconfig => self.dispatch(&EptMisconfigExit, array),
+            ExitReason::EptViolation => self.dispatch(&EptViolationExit, array),
+            ExitReason::CrAccess => self.dispatch(&CrAccessExit, array),
+            ExitReason::DrAccess => self.dispatch(&DrAccessExit, array),
+            ExitReason::Getsec => self.dispatch(&GetsecExit, array),
+            ExitReason::Vmxon => self.dispatch(&NestedVmxExit { is_vmxon: true }, array),
+            ExitReason::Vmclear | ExitReason::Vmlaunch | ExitReason::Vmresume
+            | ExitReason::Vmread | ExitReason::Vmwrite | ExitReason::Vmptrld
+            | ExitReason::Vmptrst | ExitReason::Vmxoff | ExitReason::Invept
+            | ExitReason::Invvpid | ExitReason::Vmfunc => self.dispatch(&NestedVmxExit { is_vmxon: false }, array),
+            ExitReason::Cpuid => self.dispatch(&CpuidExit, array),
+            ExitReason::Hlt => self.dispatch(&HltExit, array),
+            ExitReason::MsrRead => self.dispatch(&MsrReadExit, array),
+            ExitReason::MsrWrite => {
+                // x2APIC ICR 的目标是其他 vCPU 的信箱，不经 MsrWriteExit；两者都是特权指令
+                if self.guest_regs.rcx as u32 == IA32_X2APIC_ICR && self.x2apic_mode() {
+                    self.dispatch(&IcrWriteExit, array)
+                } else {
+                    self.dispatch(&MsrWriteExit, array)
+                }
+            },
+            ExitReason::Monitor => self.dispatch(&MonitorExit, array),
+            ExitReason::Mwait => self.dispatch(&MwaitExit, array),
+            ExitReason::ApicWrite => self.dispatch(&ApicWriteExit, array),
+            ExitReason::VirtualizedEoi => self.dispatch(&VirtualizedEoiExit, array),
+            ExitReason::Rdrand => self.dispatch(&RdrandExit, array),
+            ExitReason::Rdseed => self.dispatch(&RdseedExit, array),
+            _ => unreached(),
+        };
+        
+        self.apply_emulation_result(result);
+    }
+    
+    /// 记录一次退出，计数饱和在 u64::MAX，长时间运行的 vCPU 不会溢出
+    fn record_exit(&mut self, reason: u32)
+        requires
+            old(self).inv(),
+        ensures
+            self.inv(),
+            self.exit_preserved(old(self)),
+            *self == (ArchCpu { exit_counts: self.exit_counts, ..*old(self) }),
+            self.exit_count(reason) == if old(self).exit_count(reason) < u64::MAX {
+                (old(self).exit_count(reason) + 1) as u64
+            } else {
+                old(self).exit_count(reason)
+            },
+            forall|r: u32| r != reason ==> self.exit_count(r) == old(self).exit_count(r),
+    {
+        if (reason as usize) < NUM_EXIT_REASONS {
+            let count = self.exit_counts[reason as usize];
+            self.exit_counts.set(reason as usize, count.saturating_add(1));
+        }
+    }
+    
+    /// 当前退出计数的快照
+    pub fn exit_histogram_snapshot(&self) -> (result: [u64; NUM_EXIT_REASONS])
+        ensures
+            forall|r: u32| (r as int) < NUM_EXIT_REASONS ==> result[r as int] == self.exit_count(r),
+    {
+        self.exit_counts
+    }
+    
+    /// vmx_exit 汇编函数的语义规范
+    /// 
+    /// 实际汇编代码：
+    /// ```asm
+    /// save_regs_to_stack!()           // 保存寄存器到栈（即 guest_regs）
+    /// mov    rdi, rsp                 // 设置第一个参数
+    /// lea    rsi, [rip + PER_CPU_ARRAY] // 第二个参数：各 vCPU 的 IPI 信箱
+    /// mov    rsp, [rsp + 128]         // 切换到 host_stack_top
+    /// call   vmx_exit_continue        // 处理退出并重新进入 guest，永不返回
+    /// ud2
+    /// ```
+    /// 
+    /// 汇编中不再有 vmresume：重新进入与首次启动一样经过 enter_guest
+    #[verifier::external_body]
+    unsafe extern "C" fn vmx_exit(&mut self) -> !
+        requires
+            old(self).inv(),
+            old(self).vmx_on,
+            old(self).vmcs_loaded,
+            old(self).vmcs_launched,
+            old(self).owned_by_current(),
+            old(self).core_end + ((old(self).cpuid + 1) * PER_CPU_SIZE) as u64 <= u64::MAX,
+    {
+        // 信任边界：调用真实的汇编实现
+        loop {}
+    }
+    
+    /// VM exit 的 Rust 入口：处理本次退出后经 enter_guest 重新进入 guest
+    /// 
+    /// 能到达这里说明上一次 entry 成功，先按 complete_entry 清除已投递的 entry_event；
+    /// 退出处理要么在 vmexit_handler 中发散，要么返回后由 enter_guest 完成下一次 entry；
+    /// 注入、Hlt 唤醒、一致性检查与 launch / resume 的选择只有 enter_guest 这一份
+    fn vmx_exit_continue(&mut self, array: &mut PerCpuArray) -> !
+        requires
+            old(self).inv(),
+            old(self).vmx_on,
+            old(self).vmcs_loaded,
+            old(self).vmcs_launched,
+            old(self).owned_by_current(),
+            old(self).core_end + ((old(self).cpuid + 1) * PER_CPU_SIZE) as u64 <= u64::MAX,
+            old(array).is_valid(),
+            old(array).in_range(old(self).cpuid),
+    {
+        self.complete_entry();
+        self.vmexit_handler(array);
+        
+        proof {
+            // 已 launch 的 VMCS 不要求首次启动的寄存器帧
+            assert(self.ready_for_entry());
+        }
+        
+        self.enter_guest(array)
+    }
+    
+    /// vmx_launch 汇编函数的语义规范
+    /// 
+    /// 实际汇编代码：
+    /// ```asm
+    /// mov    rsp, rdi                 // 设置 RSP 到 guest_regs
+    /// restore_regs_from_stack!()      // 恢复所有寄存器
+    /// vmlaunch                        // 启动 VM
+    /// jmp    failed                   // 失败处理
+    /// ```
+    /// 
+    /// 关键验证点：
+    /// 1. rdi 指向 guest_regs（结构体起始位置）
+    /// 2. 寄存器从内存正确恢复
+    /// 3. vmlaunch 成功则永不返回
+    #[verifier::external_body]
+    unsafe extern "C" fn vmx_launch(&mut self) -> !
+        requires
+            old(self).ready_for_vm_launch(),
+    {
+        // 信任边界：调用真实的汇编实现
+        loop {}
+    }
+    
+    /// 记录当前 VMCS 的启动状态为 launched
+    /// 
+    /// 在 vmlaunch 之前置位：vmlaunch 成功后控制不会回到这里，之后的退出与 entry 都应看到 launched；
+    /// vmlaunch 失败时要么经 vmx_entry_failed 停机，要么 VMCLEAR 后清除该标志再重试
+    fn mark_vmcs_launched(&mut self)
+        requires
+            old(self).inv(),
+            old(self).vmcs_loaded,
+            old(self).vmcs_configured,
+        ensures
+            self.inv(),
+            *self == (ArchCpu { vmcs_launched: true, ..*old(self) }),
+    {
+        self.vmcs_launched = true;
+    }
+    
+    /// 成功 entry 之后的状态转移：硬件已投递 entry_event 并清除 interruption-information 的 valid 位
+    /// 
+    /// 投递中被打断的事件由 vmexit_handler 从 IDT-vectoring 字段放回 pending_events，不经 entry_event 重复投递
+    fn complete_entry(&mut self)
+        requires
+            old(self).inv(),
+        ensures
+            self.inv(),
+            *self == old(self).spec_after_entry(),
+    {
+        self.entry_event = None;
+    }
+    
+    /// 统一的 VM entry：launch 与 resume 共用的检查流程
+    /// 
+    /// 1. 确认调用者是本 vCPU 的所有者
+    /// 2. 取走 IPI 信箱，检查 TSC-deadline 定时器并注入可投递的事件，Hlt 的 guest 只随注入后的唤醒事件回到 Active，
+    ///    wait-for-SIPI 的 guest 只随 SIPI 回到 Active；注入判定请求的窗口退出随后写入 VMCS
+    /// 3. 重新检查 guest 状态一致性，不一致时停机
+    /// 4. 按 vmcs_launched 选择 vmresume 或 vmlaunch，成功时进入 guest 不再返回
+    /// 5. VMfailValid 且错误号可恢复（launch 状态与指令不匹配）时 VMCLEAR 后以 vmlaunch 重试一次，
+    ///    VMfailInvalid 与其余错误经 vmx_entry_failed 停机
+    /// 
+    /// 首次启动（launch_vm）与每次退出后的重新进入（vmx_exit_continue）都经过这里
+    pub fn enter_guest(&mut self, array: &mut PerCpuArray) -> !
+        requires
+            old(self).ready_for_entry(),
+            old(array).is_valid(),
+            old(array).in_range(old(self).cpuid),
+    {
+        self.assert_owned_by_current();
+        
+        self.prepare_entry_events(array);
+        self.program_event_windows();
+        
+        let mut retried = false;
+        loop
+            invariant
+                self.inv(),
+                self.vmx_on,
+                self.vmcs_configured,
+                self.guest_regs.is_valid(),
+                self.vmcs_loaded,
+                !(self.spec_guest_activity() is Hlt),
+            decreases (if retried { 0int } else { 1int }),
+        {
+            if !self.check_guest_state() {
+                cpu_fatal(FatalCpuError::InvalidGuestState);
+            }
+            
+            let rflags = if self.vmcs_launched {
+                self.try_vmx_resume()
+            } else {
+                self.mark_vmcs_launched();
+                self.try_vmx_launch()
+            };
+            match vmx_result(rflags) {
+                Ok(()) => {
+                    // try_vmx_launch / try_vmx_resume 只在失败时返回
+                    Self::vmx_entry_failed();
+                },
+                Err(VmxError::VMfailInvalid) => {
+                    Self::vmx_entry_failed();
+                },
+                Err(VmxError::VMfailValid) => {
+                    let err_no = vmcs_read_field(VmcsField::VmInstructionError);
+                    if !vm_error_recoverable(err_no) || retried {
+                        Self::vmx_entry_failed();
+                    }
+                    self.vmclear_current();
+                    retried = true;
+                },
+            }
+        }
+    }
+    
+    /// 高层启动函数（可验证的包装）
+    /// 
+    /// 激活 VMX 并配置 VMCS 后交给 enter_guest，以其 `!` 作为结尾表达式发散
+    pub fn launch_vm(
+        &mut self,
+        entry: u64,
+        rsp: u64,
+        ctls: VmcsControls,
+        array: &mut PerCpuArray,
+    ) -> !
+        requires
+            old(self).inv(),
+            is_valid_entry(entry),
+            rsp > 0,
+            guest_rsp_aligned(rsp),
+            ctls.is_valid(),
+            ctls.window_policy_holds(),
+            // NMI 阻塞由硬件跟踪，NMI-window 退出才可用（见 program_event_windows）
+            ctls.pin_ctls.virtual_nmis,
+            ctls.entry_ctls.ia32e_mode_guest == efer_lma(old(self).spec_guest_efer()),
+            !ctls.ept_enabled() ==> old(self).guest_state.cr3 != spec_current_cr3(),
+            !old(self).vmx_on,
+            // 首次启动的 guest 不处于 Hlt
+            !(old(self).spec_guest_activity() is Hlt),
+            old(self).owned_by_current(),
+            old(array).is_valid(),
+            old(array).in_range(old(self).cpuid),
+    {
+        self.assert_owned_by_current();
+        
+        // 步骤 1：分配 VMX region 并激活 VMX
+        self.alloc_vmx_regions();
+        let vmx_result = self.activate_vmx();
+        if vmx_result.is_err() {
+            boot_failed(BootStep::ActivateVmx, FatalCpuError::VmxActivationFailed);
+        }
+        
+        proof {
+            assert(self.vmx_on);
+            assert(self.vmcs_loaded);
+        }
+        
+        // 步骤 2：配置 VMCS
+        let vmcs_result = self.setup_vmcs(entry, rsp, ctls);
+        if vmcs_result.is_err() {
+            boot_failed(BootStep::SetupVmcs, FatalCpuError::VmcsSetupFailed);
+        }
+        
+        // VMPTRLD 已在 activate_vmx 中完成，此后 CPU 进入运行 guest 的状态
+        self.mark_power_on();
+        
+        // 步骤 3：把期望的寄存器装入 vmx_launch 读取的帧
+        self.prepare_launch_frame();
+        
+        proof {
+            assert(self.vmcs_configured);
+            assert(self.guest_regs == self.intended_guest_regs@);
+            assert(!self.vmcs_launched && self.launch_frame_ready());
+            assert(self.ready_for_entry());
+        }
+        
+        // 步骤 4：经统一入口启动 VM（VMCS 刚配置，走 vmlaunch）
+        self.enter_guest(array)
+    }
+    
+    /// VMPTRLD / VMCLEAR 的指针操作数：VMCS region 的物理地址
+    pub fn vmcs_phys_ptr(&self) -> (result: u64)
+        requires
+            self.vmcs_region.is_allocated(),
+        ensures
+            result % 4096 == 0,
+            result < spec_max_phys_addr(),
+            self.vmcs_region.spec_frame() == Some(result),
+    {
+        self.vmcs_region.phys_addr()
+    }
+    
+    /// VMXON 的指针操作数：VMXON region 的物理地址
+    pub fn vmxon_phys_ptr(&self) -> (result: u64)
+        requires
+            self.vmxon_region.is_allocated(),
+        ensures
+            result % 4096 == 0,
+            result < spec_max_phys_addr(),
+            self.vmxon_region.spec_frame() == Some(result),
+    {
+        self.vmxon_region.phys_addr()
+    }
+    
+    /// 标记 CPU 正在运行 guest（必须已有当前 VMCS）
+    fn mark_power_on(&mut self)
+        requires
+            old(self).inv(),
+            old(self).vmx_on,
+            old(self).vmcs_loaded,
+        ensures
+            self.inv(),
+            *self == (ArchCpu { power_on: true, ..*old(self) }),
+    {
+        self.power_on = true;
+    }
+    
+    /// 检查 guest 状态的一致性
+    fn check_guest_state(&self) -> (result: bool)
+        ensures
+            result == self.guest_state_consistent(),
+    {
+        let event_ok = match &self.entry_event {
+            Some(event) => match event.event_type {
+                EventType::HardwareException => event.vector < 32,
+                _ => true,
+            },
+            None => true,
+        };
+        let real_mode = self.guest_state.cr0 & CR0_PE == 0;
+        let mode_ok = !real_mode
+            || (self.ctls.proc_ctls.activate_secondary && self.ctls.secondary_ctls.unrestricted_guest);
+        event_ok && mode_ok && self.guest_state.check_descriptor_tables()
+            && !self.guest_state.interruptibility.blocking_by_smi
+            && cr4_valid(self.guest_state.cr4, self.guest_state.cr0, self.guest_efer)
+            && self.check_guest_addrs_canonical()
+            && self.check_guest_pdptes()
+            && self.guest_state.check_privilege(
+                self.ctls.proc_ctls.activate_secondary && self.ctls.secondary_ctls.unrestricted_guest)
+    }
+    
+    fn check_guest_pdptes(&self) -> (result: bool)
+        ensures
+            result == self.guest_pdptes_valid(),
+    {
+        let pae = self.guest_state.cr0 & CR0_PG != 0 && self.guest_efer & EFER_LMA == 0
+            && self.guest_state.cr4 & CR4_PAE != 0;
+        if !pae {
+            return true;
+        }
+        let max_phys = max_phys_addr();
+        let pdptes = self.guest_state.pdptes;
+        pdpte_valid(pdptes[0], max_phys) && pdpte_valid(pdptes[1], max_phys)
+            && pdpte_valid(pdptes[2], max_phys) && pdpte_valid(pdptes[3], max_phys)
+    }
+    
+    fn check_guest_addrs_canonical(&self) -> (result: bool)
+        ensures
+            result == self.guest_addrs_canonical(),
+    {
+        let la57 = self.guest_state.cr4 & CR4_LA57 != 0;
+        is_canonical_for(self.guest_state.rip, la57) && is_canonical_for(self.guest_state.rsp, la57)
+    }
+    
+    /// 执行 vmlaunch（信任边界）
+    /// 
+    /// 实际汇编代码与 vmx_launch 相同：从 guest_regs 恢复寄存器后 vmlaunch。
+    /// 成功时进入 guest 永不返回；返回即表示失败，返回值为 vmlaunch 之后的 RFLAGS，失败不修改模型状态
+    #[verifier::external_body]
+    fn try_vmx_launch(&mut self) -> (rflags: u64)
+        requires
+            old(self).ready_for_vm_launch(),
+            !(old(self).guest_state.activity is Hlt),
+        ensures
+            *self == *old(self),
+            spec_vmx_result(rflags) is Err,
+    {
+        // 信任边界：调用真实的汇编实现，失败时 pushfq 返回 RFLAGS
+        RFLAGS_CF
+    }
+    
+    /// 执行 vmresume（信任边界）
+    /// 
+    /// 与 try_vmx_launch 相同地从 guest_regs 恢复寄存器，只能用于已 launch 的 VMCS；
+    /// 返回即表示失败，返回值为 vmresume 之后的 RFLAGS，失败不修改模型状态
+    #[verifier::external_body]
+    fn try_vmx_resume(&mut self) -> (rflags: u64)
+        requires
+            old(self).ready_for_vm_launch(),
+            !(old(self).guest_state.activity is Hlt),
+            old(self).vmcs_launched,
+        ensures
+            *self == *old(self),
+            spec_vmx_result(rflags) is Err,
+    {
+        // 信任边界：调用真实的汇编实现，失败时 pushfq 返回 RFLAGS
+        RFLAGS_CF
+    }
+}
+
+/// 注入周期的自检：inject_exception 写入的事件只由下一次 entry 投递一次
+/// 
+/// 走的是真实的转移：注入之后 entry 投递该事件，退出时 vmx_exit_continue 经 complete_entry 清除它，
+/// 满足 vmexit_handler 的前置条件；没有重新注入的下一次 entry 不投递任何事件
+pub fn verify_injection_delivered_once(cpu: &mut ArchCpu, vector: u8, error_code: Option<u32>)
+    requires
+        old(cpu).inv(),
+    ensures
+        cpu.inv(),
+        cpu.entry_event is None,
+{
+    cpu.complete_entry();
+    cpu.inject_exception(vector, error_code);
+    assert(cpu.spec_delivered() == Some(InjectedEvent::spec_exception(vector, error_code)));
+    
+    // 第一次 entry 投递该异常，退出后 valid 位已清除
+    cpu.complete_entry();
+    assert(cpu.spec_delivered() is None);
+    
+    // 第二次 entry 没有重新注入，不会再投递
+    cpu.complete_entry();
+    assert(cpu.spec_delivered() is None);
+}
+
+/// IPI 投递的自检：sender 写 ICR 发出的 fixed IPI 经信箱进入 target 自己的虚拟 LAPIC
+/// 
+/// 走的是真实的路径：send_ipi 只写 target 的信箱，target 在下一次 entry 前经 drain_ipis 取走，
+/// 向量出现在 target.virt_lapic 的 IRR 中，信箱随之清空，不会重复投递
+pub fn verify_ipi_reaches_target(sender: &ArchCpu, target: &mut ArchCpu, array: &mut PerCpuArray, vector: u8)
+    requires
+        sender.inv(),
+        old(target).inv(),
+        old(target).vmcs_configured,
+        old(array).is_valid(),
+        old(array).in_range(sender.cpuid),
+        old(array).in_range(old(target).cpuid),
+        sender.cpuid != old(target).cpuid,
+        vector >= ICR_MIN_FIXED_VECTOR,
+    ensures
+        target.inv(),
+        target.virt_lapic.irr@[vector as int],
+        array.mailboxes@[target.cpuid as int].spec_is_empty(),
+{
+    let dest = target.cpuid as u64;
+    let icr = (dest << 32u64) | (vector as u64);
+    proof {
+        let v = vector as u64;
+        assert(dest < 256);
+        assert(((dest << 32u64) | v) & 0xff == v && ((((dest << 32u64) | v) >> 8u64) & 0x7) == 0
+            && ((((dest << 32u64) | v) >> 18u64) & 0x3) == 0 && ((dest << 32u64) | v) >> 32u64 == dest) by (bit_vector)
+            requires
+                dest < 256,
+                v < 256;
+        assert(spec_icr_targets(icr, sender.cpuid, target.cpuid));
+    }
+    
+    let ok = sender.send_ipi(array, icr);
+    assert(ok);
+    assert(array.mailboxes@[target.cpuid as int].irr@[vector as int]);
+    
+    target.drain_ipis(array);
+    assert(target.virt_lapic.irr@[vector as int]);
+}
+
+/// 引理：Hlt 状态只有外部中断、NMI 或 MWAIT ECX[0] 下的屏蔽中断能唤醒，其余情况保持原状态；
+/// 唤醒后 MWAIT 的唤醒标志被清除
+pub proof fn lemma_only_events_wake_hlt(cpu: ArchCpu)
+    requires
+        cpu.spec_guest_activity() is Hlt,
+    ensures
+        cpu.spec_woken().spec_guest_activity() is Active
+            <==> spec_wakes_hlt(cpu.entry_event) || cpu.spec_mwait_masked_wake(),
+        !spec_wakes_hlt(cpu.entry_event) && !cpu.spec_mwait_masked_wake() ==> cpu.spec_woken() == cpu,
+        cpu.spec_woken().spec_guest_activity() is Active ==> !cpu.spec_woken().mwait_break_on_interrupt,
+{
+}
+
+/// 引理：4 级分页下 RIP 落在非规范空洞起点时，guest 状态检查失败，vmlaunch 不会被调用
+pub proof fn lemma_noncanonical_rip_blocks_launch(cpu: ArchCpu)
+    requires
+        cpu.spec_guest_rip() == 0x0000_8000_0000_0000,
+        !cpu.guest_la57(),
+    ensures
+        !cpu.guest_state_consistent(),
+{
+}
+
+/// 引理：LA57 下规范地址的边界移到 bit 56，48 位空洞中的地址变为合法，57 位空洞起点仍非法
+pub proof fn lemma_la57_shifts_canonical_boundary()
+    ensures
+        !spec_is_canonical_for(0x0000_8000_0000_0000, false),
+        spec_is_canonical_for(0x0000_8000_0000_0000, true),
+        !spec_is_canonical_for(0x0100_0000_0000_0000, true),
+        spec_is_canonical_for(0xff00_0000_0000_0000, true),
+{
+}
+
+/// 引理：IPI 的发送方只改动目标的信箱，不是目标的 vCPU 的信箱保持不变
+/// 
+/// 发送方只持有自己的 ArchCpu（send_ipi 取 &self），其它物理 CPU 拥有的 vCPU 只能经信箱被影响，
+/// 因此 ICR 没有指向的 vCPU 完全不受这次发送的影响
+pub proof fn lemma_foreign_cpu_excluded(old_array: PerCpuArray, array: PerCpuArray, sender: usize, icr: u64, target: usize)
+    requires
+        array.spec_ipi_sent(&old_array, sender, icr),
+        old_array.in_range(target),
+        !spec_icr_targets(icr, sender, target),
+    ensures
+        array.mailboxes@[target as int] == old_array.mailboxes@[target as int],
+{
+}
+
+/// 引理：向 LSTAR / CSTAR 写入非规范地址被拒绝（注入 #GP），不会留到 SYSCALL 时才出错
+pub proof fn lemma_noncanonical_syscall_target_rejected(cpu: ArchCpu, val: u64)
+    requires
+        !spec_is_canonical(val),
+    ensures
+        !cpu.spec_msr_write_accepted(IA32_LSTAR, val),
+        !cpu.spec_msr_write_accepted(IA32_CSTAR, val),
+{
+}
+
+/// 引理：TF 置位时单步 #DB 恰好投递一次；中断影子内推迟到 pending debug exceptions，不直接注入
+pub proof fn lemma_single_step_delivery(cpu: ArchCpu)
+    requires
+        cpu.guest_state.rflags & RFLAGS_TF != 0,
+        cpu.entry_event is None,
+    ensures
+        (cpu.guest_state.interruptibility.blocking_by_sti || cpu.guest_state.interruptibility.blocking_by_mov_ss)
+            ==> cpu.spec_single_step_trap().guest_state.pending_dbg_bs
+                && cpu.spec_single_step_trap().entry_event is None,
+        !(cpu.guest_state.interruptibility.blocking_by_sti || cpu.guest_state.interruptibility.blocking_by_mov_ss)
+            ==> cpu.spec_single_step_trap().entry_event == Some(InjectedEvent::spec_exception(EXCEPTION_DB, None))
+                && cpu.spec_single_step_trap().guest_state.pending_dbg_bs == cpu.guest_state.pending_dbg_bs,
+{
+}
+
+/// 引理：STI 影子内不可能注入外部中断；影子所覆盖的那条指令退休后，IF 置位且无其它阻塞时可以注入
+pub proof fn lemma_sti_shadow_lasts_one_instruction(cpu: ArchCpu)
+    requires
+        cpu.guest_state.rflags & RFLAGS_IF != 0,
+        cpu.guest_state.interruptibility.blocking_by_sti,
+    ensures
+        !cpu.guest_state.interruptible(),
+        cpu.spec_retired().guest_state.interruptible(),
+        cpu.spec_retired().guest_state.rflags == cpu.guest_state.rflags,
+{
+}
+
+/// 重新配置的自检：已 launch 的 VMCS 经 vmclear_current 之后才满足 setup_vmcs 的前置条件，
+/// 配置成功后 vmcs_launched 仍为 false，下一次 entry 走 vmlaunch
+pub fn verify_setup_vmcs_follows_vmclear(cpu: &mut ArchCpu, entry: u64, rsp: u64, ctls: VmcsControls)
+    requires
+        old(cpu).inv(),
+        old(cpu).vmx_on,
+        old(cpu).vmcs_loaded,
+        old(cpu).vmcs_launched,
+        ctls.is_valid(),
+        ctls.window_policy_holds(),
+        is_valid_entry(entry),
+        guest_rsp_aligned(rsp),
+        ctls.entry_ctls.ia32e_mode_guest == efer_lma(old(cpu).spec_guest_efer()),
+        !ctls.ept_enabled() ==> old(cpu).guest_state.cr3 != spec_current_cr3(),
+{
+    cpu.vmclear_current();
+    assert(!cpu.vmcs_launched && cpu.vmcs_loaded);
+    
+    if cpu.setup_vmcs(entry, rsp, ctls).is_ok() {
+        assert(cpu.vmcs_configured && !cpu.vmcs_launched);
+    }
+}
+
+/// 引理：VMX 指令退出在 guest 视角的 CR4.VMXE 清零时注入 #UD，在 CPL != 0 时注入 #GP(0)，从不前进 RIP
+/// 
+/// 真实的 guest CR4 始终置位 VMXE，决定异常的是 read shadow
+pub proof fn lemma_nested_vmx_faults(cpu: &ArchCpu, is_vmxon: bool)
+    ensures
+        cpu.guest_state.cr4_shadow & CR4_VMXE == 0 ==> cpu.spec_nested_vmx_fault(is_vmxon) == EmulationResult::spec_ud(),
+        cpu.guest_state.cr4_shadow & CR4_VMXE != 0 && cpu.spec_guest_cpl() != 0
+            ==> cpu.spec_nested_vmx_fault(is_vmxon) == EmulationResult::spec_gp(),
+        !(cpu.spec_nested_vmx_fault(is_vmxon) is Advance),
+{
+}
+
+/// 引理：非规范地址不是合法的 guest 入口
+pub proof fn lemma_noncanonical_entry_rejected(entry: u64)
+    requires
+        !spec_is_canonical(entry),
+    ensures
+        !is_valid_entry(entry),
+        !is_valid_entry(0x0000_8000_0000_0000),
+        !is_valid_entry(0),
+{
+}
+
+/// 引理：注入事件之后不会同时存在 STI / MOV SS 中断影子
+/// 
+/// NMI 只在没有 MOV SS 阻塞时注入，其余事件的投递直接结束影子
+pub proof fn lemma_injection_ends_interrupt_shadow(cpu: ArchCpu, event: InjectedEvent)
+    requires
+        event.event_type is Nmi ==> !cpu.guest_state.interruptibility.blocking_by_mov_ss,
+    ensures
+        cpu.spec_with_injected(event).entry_event == Some(event),
+        !cpu.spec_with_injected(event).guest_state.interruptibility.blocking_by_sti,
+        !cpu.spec_with_injected(event).guest_state.interruptibility.blocking_by_mov_ss,
+        cpu.spec_with_injected(event).guest_state.interruptibility.blocking_by_nmi
+            == cpu.guest_state.interruptibility.blocking_by_nmi,
+{
+}
+
+/// 引理：已写入的 NMI 不会被该指令的 fault 覆盖，NMI 阻塞保持为注入时的状态
+pub proof fn lemma_fault_keeps_queued_nmi(cpu: ArchCpu, vector: u8, error_code: Option<u32>)
+    requires
+        cpu.entry_event == Some(InjectedEvent::spec_nmi()),
+    ensures
+        cpu.spec_with_fault(vector, error_code).entry_event == Some(InjectedEvent::spec_nmi()),
+        cpu.spec_with_fault(vector, error_code).guest_state.interruptibility.blocking_by_nmi
+            == cpu.guest_state.interruptibility.blocking_by_nmi,
+{
+}
+
+/// 引理：CPL 取自 SS.DPL；CS.RPL 与 SS.RPL 的一致性只在非 v8086、未开启 unrestricted guest 时检查
+pub proof fn lemma_guest_cpl_from_ss(cpu: ArchCpu)
+    ensures
+        cpu.spec_guest_cpl() == spec_seg_dpl(cpu.guest_state.ss) as u8,
+        cpu.guest_state.rflags & RFLAGS_VM == 0 && !cpu.ctls.unrestricted_guest_enabled()
+            && (cpu.guest_state.ss.selector & SELECTOR_RPL) != (cpu.guest_state.cs.selector & SELECTOR_RPL)
+            ==> !cpu.guest_state_consistent(),
+        // v8086 下选择子只按 selector << 4 给出段基址，不检查 RPL
+        cpu.guest_state.rflags & RFLAGS_VM != 0 ==> (cpu.guest_state.privilege_consistent(false) <==> {
+            &&& spec_seg_dpl(cpu.guest_state.ss) == 3
+            &&& spec_seg_dpl(cpu.guest_state.cs) == 3
+        }),
+{
+}
+
+/// GETSEC 的自检：退出原因 11 经 vmexit_handler 分发到 GetsecExit，由 apply_emulation_result 注入 #UD
+/// （向量 6，不带错误码），RIP 不前进
+pub fn verify_getsec_injects_ud(cpu: &mut ArchCpu, array: &mut PerCpuArray)
+    requires
+        old(cpu).inv(),
+        old(cpu).vmcs_configured,
+        old(cpu).entry_event is None,
+        old(cpu).owned_by_current(),
+        old(cpu).core_end + ((old(cpu).cpuid + 1) * PER_CPU_SIZE) as u64 <= u64::MAX,
+        old(array).is_valid(),
+        old(array).in_range(old(cpu).cpuid),
+        spec_exit_reason() == EXIT_REASON_GETSEC,
+    ensures
+        cpu.inv(),
+        cpu.entry_event == Some(InjectedEvent::spec_exception(6, None)),
+        cpu.guest_state.rip == old(cpu).guest_state.rip,
+{
+    cpu.vmexit_handler(array);
+}
+
+/// 引理：首次 entry（VMCS 尚未 launch）时，vmlaunch 恢复的寄存器恰为期望的 guest 寄存器
+pub proof fn lemma_launch_enters_intended_regs(cpu: &ArchCpu)
+    requires
+        cpu.ready_for_entry(),
+        !cpu.vmcs_launched,
+    ensures
+        cpu.guest_regs == cpu.intended_guest_regs@,
+{
+}
+
+/// 引理：VM Exit 处理不改变 host CR0/CR3/CR4，且它们始终满足 VMX 固定位
+pub proof fn lemma_host_crs_stable(before: ArchCpu, after: ArchCpu)
+    requires
+        before.inv(),
+        before.vmcs_configured,
+        after.exit_preserved(&before),
+    ensures
+        after.spec_host_cr0() == before.spec_host_cr0(),
+        after.spec_host_cr3() == before.spec_host_cr3(),
+        after.spec_host_cr4() == before.spec_host_cr4(),
+        host_crs_fixed(after.spec_host_cr0(), after.spec_host_cr4()),
+{
+}
+
+/// 引理：未启用 unrestricted guest 时，实模式 guest 无法通过 VM entry 检查，
+/// 因而不满足 try_vmx_launch / try_vmx_resume 的前置条件 ready_for_vm_launch
+pub proof fn lemma_real_mode_requires_unrestricted(cpu: ArchCpu)
+    requires
+        cpu.guest_in_real_mode(),
+        !cpu.ctls.secondary_ctls.unrestricted_guest,
+    ensures
+        !cpu.guest_state_consistent(),
+        !cpu.ready_for_vm_launch(),
+{
+}
+
+/// 引理：两个不同 CPU 的 vCPU 分配的 VMX region 互不重叠，同一 vCPU 的 VMXON region 与 VMCS 也不同
+pub proof fn lemma_cpus_share_no_vmx_region(a: ArchCpu, b: ArchCpu)
+    requires
+        a.inv(),
+        b.inv(),
+        a.vmx_regions_allocated(),
+        b.vmx_regions_allocated(),
+        a.cpuid != b.cpuid,
+    ensures
+        a.vmxon_region.spec_frame() != a.vmcs_region.spec_frame(),
+        a.vmxon_region.spec_frame() != b.vmxon_region.spec_frame(),
+        a.vmcs_region.spec_frame() != b.vmcs_region.spec_frame(),
+        a.vmxon_region.spec_frame() != b.vmcs_region.spec_frame(),
+{
+    lemma_pool_no_double_alloc(a.cpuid, a.cpuid);
+    lemma_pool_no_double_alloc(a.cpuid, b.cpuid);
+}
+
+/// 分配顺序的自检：new() 得到的 fake_init region 不满足 idle_activate_vmx 的前置条件，
+/// 经 alloc_vmx_regions 从池中取得本 CPU 的 region 之后才能激活 VMX
+pub fn verify_fake_regions_block_activation(cpuid: usize)
+    requires
+        cpuid < MAX_CPU_NUM,
+{
+    let mut cpu = ArchCpu::new(cpuid);
+    assert(!(cpu.vmxon_region.is_allocated() && cpu.vmcs_region.is_allocated()));
+    
+    cpu.alloc_vmx_regions();
+    assert(cpu.vmxon_region.is_allocated() && cpu.vmcs_region.is_allocated());
+    assert(cpu.vmx_regions_from_pool());
+    
+    if cpu.idle_activate_vmx().is_ok() {
+        assert(cpu.vmx_on && cpu.vmcs_loaded);
+    }
+}
+
+/// 引理：最大 CPU 编号（MAX_CPU_NUM - 1）的每 CPU 区域末端仍远小于 u64::MAX，
+/// 只要 core_end 留出 MAX_CPU_NUM * PER_CPU_SIZE 的空间，idle_set_stack_top 就不会失败
+pub proof fn lemma_stack_top_max_cpu()
+    ensures
+        ((MAX_CPU_NUM - 1) + 1) * PER_CPU_SIZE == 0x800_0000,
+        spec_core_end() <= u64::MAX - 0x800_0000
+            ==> spec_core_end() + ((MAX_CPU_NUM - 1) + 1) * PER_CPU_SIZE <= u64::MAX,
+{
+    assert(((MAX_CPU_NUM - 1) + 1) * PER_CPU_SIZE == 0x800_0000) by (compute);
+}
+
+/// 引理：运行 guest 但 VMX 未开启的状态违反不变式
+pub proof fn lemma_power_on_requires_vmx(cpu: ArchCpu)
+    requires
+        cpu.power_on,
+        !cpu.vmx_on,
+    ensures
+        !cpu.inv(),
+{
+}
+
+/// 引理：activate_vmx 不会重复执行 VMXON
+/// 
+/// 激活成功之后、idle 就绪之后或正在运行 guest 的 CPU 都已处于 VMX root operation，
+/// 不满足 activate_vmx 的前置条件 !vmx_on；启动代码的重试只能发生在 VMX 仍关闭时
+pub proof fn lemma_activate_vmx_excludes_double_vmxon(cpu: ArchCpu)
+    requires
+        cpu.inv(),
+        cpu.vmx_on || cpu.power_on || cpu.ready_for_idle(),
+    ensures
+        cpu.vmx_on,
+{
+}
+
+/// 配置顺序的自检：走 launch_vm 的前两步，setup_vmcs 要求的当前 VMCS 只能来自 activate_vmx 中的 VMPTRLD
+/// 
+/// 激活成功之后 VMCS 已装入、尚未 launch，配置成功即得到已配置的 vCPU
+pub fn verify_setup_vmcs_requires_loaded(cpu: &mut ArchCpu, entry: u64, rsp: u64, ctls: VmcsControls)
+    requires
+        old(cpu).inv(),
+        !old(cpu).vmx_on,
+        !old(cpu).power_on,
+        ctls.is_valid(),
+        ctls.window_policy_holds(),
+        is_valid_entry(entry),
+        guest_rsp_aligned(rsp),
+        ctls.entry_ctls.ia32e_mode_guest == efer_lma(old(cpu).spec_guest_efer()),
+        !ctls.ept_enabled() ==> old(cpu).guest_state.cr3 != spec_current_cr3(),
+{
+    cpu.alloc_vmx_regions();
+    if cpu.activate_vmx().is_err() {
+        return;
+    }
+    // VMPTRLD 在 activate_vmx 中完成
+    assert(cpu.vmx_on && cpu.vmcs_loaded && !cpu.vmcs_launched);
+    
+    if cpu.setup_vmcs(entry, rsp, ctls).is_ok() {
+        assert(cpu.inv() && cpu.vmcs_configured && cpu.vmcs_loaded);
+    }
+}
+
+/// 引理：idle_set_stack_top 设置的栈顶下，scratch 区域与栈互不重叠，
+/// 且都落在本 CPU 的 [core_end + cpuid * PER_CPU_SIZE, host_stack_top) 内
+pub proof fn lemma_scratch_disjoint_from_stack(cpu: ArchCpu)
+    requires
+        cpu.host_stack_top == cpu.core_end + (cpu.cpuid + 1) * PER_CPU_SIZE,
+    ensures
+        cpu.per_cpu_base() == cpu.core_end + cpu.cpuid * PER_CPU_SIZE,
+        cpu.per_cpu_base() <= cpu.scratch_base(),
+        cpu.scratch_base() < cpu.scratch_top(),
+        cpu.scratch_top() <= cpu.stack_bottom(),
+        cpu.stack_bottom() < cpu.host_stack_top,
+        cpu.scratch_top() - cpu.scratch_base() == SCRATCH_SIZE,
+        cpu.host_stack_top - cpu.stack_bottom() == PER_CPU_SIZE - SCRATCH_SIZE,
+{
+    assert((cpu.cpuid + 1) * PER_CPU_SIZE == cpu.cpuid * PER_CPU_SIZE + PER_CPU_SIZE) by (nonlinear_arith);
+}
+
+/// 引理：host_stack_top 严格高于本 CPU 所有子区域（scratch、栈底），
+/// vmx_exit 中 `mov rsp, [rsp + 128]` 之后不超过栈容量的压栈都落在 [stack_bottom, host_stack_top) 内，
+/// 不会越过栈底写入 scratch 区域或前一个 CPU 的区域
+pub proof fn lemma_host_stack_top_above_regions(cpu: ArchCpu, pushes: int)
+    requires
+        cpu.host_stack_top == cpu.core_end + (cpu.cpuid + 1) * PER_CPU_SIZE,
+        0 <= pushes,
+        pushes * 8 <= PER_CPU_SIZE - SCRATCH_SIZE,
+    ensures
+        cpu.host_stack_top > cpu.scratch_base(),
+        cpu.host_stack_top > cpu.scratch_top(),
+        cpu.host_stack_top > cpu.stack_bottom(),
+        cpu.host_stack_top - pushes * 8 >= cpu.stack_bottom(),
+        cpu.host_stack_top - pushes * 8 >= cpu.core_end + cpu.cpuid * PER_CPU_SIZE + SCRATCH_SIZE,
+{
+    lemma_scratch_disjoint_from_stack(cpu);
+}
+
+/// 引理：算错的 scratch_top 若高于 host_stack_top，不可能是本 CPU 的 scratch_top
+pub proof fn lemma_scratch_top_above_stack_rejected(cpu: ArchCpu, bad_scratch_top: int)
+    requires
+        cpu.host_stack_top == cpu.core_end + (cpu.cpuid + 1) * PER_CPU_SIZE,
+        bad_scratch_top > cpu.host_stack_top,
+    ensures
+        bad_scratch_top != cpu.scratch_top(),
+{
+    lemma_host_stack_top_above_regions(cpu, 0);
+}
+
+/// 影子页表隔离的自检：guest CR3 恰为 host 页表根时 guest_cr3_installable 拒绝配置，不会到达 setup_vmcs；
+/// 检查通过后配置出的 vCPU 满足 guest_cr3_isolated
+pub fn verify_shadow_paging_rejects_host_cr3(cpu: &mut ArchCpu, entry: u64, rsp: u64, ctls: VmcsControls)
+    requires
+        old(cpu).inv(),
+        old(cpu).vmx_on,
+        old(cpu).vmcs_loaded,
+        !old(cpu).vmcs_launched,
+        !ctls.ept_enabled(),
+        ctls.is_valid(),
+        ctls.window_policy_holds(),
+        is_valid_entry(entry),
+        guest_rsp_aligned(rsp),
+        ctls.entry_ctls.ia32e_mode_guest == efer_lma(old(cpu).spec_guest_efer()),
+    ensures
+        old(cpu).guest_state.cr3 == spec_current_cr3() ==> *cpu == *old(cpu),
+{
+    if !cpu.guest_cr3_installable(&ctls) {
+        return;
+    }
+    if cpu.setup_vmcs(entry, rsp, ctls).is_ok() {
+        assert(cpu.guest_state.cr3 != cpu.spec_host_cr3());
+    }
+}
+
+/// 引理：奇数 RSP 不满足 setup_vmcs 的前置条件
+pub proof fn lemma_odd_rsp_rejected(rsp: u64)
+    requires
+        rsp % 2 == 1,
+    ensures
+        !guest_rsp_aligned(rsp),
+{
+}
+
+/// 引理：合法的 EFER 写入保持不变式与 EFER 一致性（包括 LMA 与当前 CR0.PG 一致）；
+/// IA-32e mode guest 的能力检查只看固定位，翻转它不会使控制字段失效
+pub proof fn lemma_efer_write_coherent(cpu: ArchCpu, val: u64)
+    requires
+        cpu.inv(),
+        cpu.spec_efer_write_valid(val),
+    ensures
+        cpu.spec_efer_written(val).inv(),
+        cpu.spec_efer_written(val).efer_coherent(),
+        cpu.spec_efer_written(val).ctls.entry_ctls.ia32e_mode_guest == efer_lma(val),
+{
+    let after = cpu.spec_efer_written(val);
+    assert(after.ctls.entry_ctls.spec_fixed_bits() == cpu.ctls.entry_ctls.spec_fixed_bits());
+}
+
+/// 引理：合法的 CR0 写入保持不变式与 EFER 一致性
+/// 
+/// 写入前后 CR0.PG 可以不同：开启分页且 LME 置位时 LMA 与 IA-32e mode guest 一起置位，
+/// 关闭分页时一起清零，一致性对写入后的 CR0 与 EFER 成立
+pub proof fn lemma_cr0_write_coherent(cpu: ArchCpu, val: u64)
+    requires
+        cpu.inv(),
+        spec_cr0_write_valid(val, cpu.guest_state.cr4, cpu.spec_guest_efer()),
+    ensures
+        cpu.spec_cr0_written(val).inv(),
+        cpu.spec_cr0_written(val).efer_coherent(),
+        cpu.spec_cr0_written(val).ctls.entry_ctls.ia32e_mode_guest
+            == (efer_lme(cpu.spec_guest_efer()) && val & CR0_PG != 0),
+{
+    lemma_efer_after_cr0(cpu.spec_guest_efer(), val);
+    let after = cpu.spec_cr0_written(val);
+    assert(after.ctls.entry_ctls.spec_fixed_bits() == cpu.ctls.entry_ctls.spec_fixed_bits());
+}
+
+/// 引理：三重错误不属于会返回的退出原因，vmexit_handler 遇到它不会落到 vmresume
+pub proof fn lemma_triple_fault_never_resumes()
+    ensures
+        ExitReason::spec_from_raw(2) == Some(ExitReason::TripleFault),
+        !is_handled_exit(2),
+{
+}
+
+/// 退出处理函数的统一契约
+/// 
+/// 实现者必须保持 inv() 以及 exit_preserved 中的全部状态（VMX / VMCS 状态、host 状态、所属 CPU），
+/// 漏掉任何一项都无法通过 impl 的验证；vmexit_handler 中每个会返回的退出原因都经 dispatch 调用实现了该 trait 的处理函数
+pub trait ExitHandler {
+    /// 规范函数：处理函数可以被调用的条件（例如对应的 exiting 控制已开启）
+    spec fn enabled(&self, cpu: &ArchCpu) -> bool;
+    
+    /// 规范函数：对应的指令只能在 CPL 0 执行
+    spec fn privileged(&self) -> bool;
+    
+    /// 规范函数：与 guest 状态无关的固定处理结果，处理函数此时不改变任何状态；没有时为 None
+    spec fn fixed_result(&self) -> Option<EmulationResult>;
+    
+    fn is_privileged(&self) -> (result: bool)
+        ensures
+            result == self.privileged();
+    
+    fn handle(&self, cpu: &mut ArchCpu, array: &mut PerCpuArray) -> (result: EmulationResult)
+        requires
+            old(cpu).inv(),
+            old(cpu).vmcs_configured,
+            old(cpu).owned_by_current(),
+            old(cpu).core_end + ((old(cpu).cpuid + 1) * PER_CPU_SIZE) as u64 <= u64::MAX,
+            old(array).is_valid(),
+            old(array).in_range(old(cpu).cpuid),
+            self.enabled(old(cpu)),
+        ensures
+            cpu.inv(),
+            cpu.exit_preserved(old(cpu)),
+            array.is_valid(),
+            array.num_cpus == old(array).num_cpus,
+            self.fixed_result() matches Some(r) ==> {
+                &&& result == r
+                &&& *cpu == *old(cpu)
+                &&& array.mailboxes@ == old(array).mailboxes@
+            };
+}
+
+/// CPUID 退出
+pub struct CpuidExit;
+
+impl ExitHandler for CpuidExit {
+    open spec fn enabled(&self, cpu: &ArchCpu) -> bool {
+        true
+    }
+    
+    open spec fn privileged(&self) -> bool {
+        false
+    }
+    
+    open spec fn fixed_result(&self) -> Option<EmulationResult> {
+        None
+    }
+    
+    fn is_privileged(&self) -> (result: bool) {
+        false
+    }
+    
+    fn handle(&self, cpu: &mut ArchCpu, _array: &mut PerCpuArray) -> (result: EmulationResult) {
+        cpu.handle_cpuid()
+    }
+}
+
+/// HLT 退出
+pub struct HltExit;
+
+impl ExitHandler for HltExit {
+    open spec fn enabled(&self, cpu: &ArchCpu) -> bool {
+        true
+    }
+    
+    open spec fn privileged(&self) -> bool {
+        true
+    }
+    
+    open spec fn fixed_result(&self) -> Option<EmulationResult> {
+        None
+    }
+    
+    fn is_privileged(&self) -> (result: bool) {
+        true
+    }
+    
+    fn handle(&self, cpu: &mut ArchCpu, _array: &mut PerCpuArray) -> (result: EmulationResult) {
+        cpu.handle_hlt()
+    }
+}
+
+/// RDMSR 退出
+pub struct MsrReadExit;
+
+impl ExitHandler for MsrReadExit {
+    open spec fn enabled(&self, cpu: &ArchCpu) -> bool {
+        true
+    }
+    
+    open spec fn privileged(&self) -> bool {
+        true
+    }
+    
+    open spec fn fixed_result(&self) -> Option<EmulationResult> {
+        None
+    }
+    
+    fn is_privileged(&self) -> (result: bool) {
+        true
+    }
+    
+    fn handle(&self, cpu: &mut ArchCpu, _array: &mut PerCpuArray) -> (result: EmulationResult) {
+        cpu.handle_msr_read()
+    }
+}
+
+/// WRMSR 退出
+pub struct MsrWriteExit;
+
+impl ExitHandler for MsrWriteExit {
+    open spec fn enabled(&self, cpu: &ArchCpu) -> bool {
+        true
+    }
+    
+    open spec fn privileged(&self) -> bool {
+        true
+    }
+    
+    open spec fn fixed_result(&self) -> Option<EmulationResult> {
+        None
+    }
+    
+    fn is_privileged(&self) -> (result: bool) {
+        true
+    }
+    
+    fn handle(&self, cpu: &mut ArchCpu, _array: &mut PerCpuArray) -> (result: EmulationResult) {
+        cpu.handle_msr_write()
+    }
+}
+
+/// MONITOR 退出（需要 MONITOR exiting）
+pub struct MonitorExit;
+
+impl ExitHandler for MonitorExit {
+    open spec fn enabled(&self, cpu: &ArchCpu) -> bool {
+        cpu.ctls.proc_ctls.monitor_exiting
+    }
+    
+    open spec fn privileged(&self) -> bool {
+        false
+    }
+    
+    open spec fn fixed_result(&self) -> Option<EmulationResult> {
+        None
+    }
+    
+    fn is_privileged(&self) -> (result: bool) {
+        false
+    }
+    
+    fn handle(&self, cpu: &mut ArchCpu, _array: &mut PerCpuArray) -> (result: EmulationResult) {
+        cpu.handle_monitor()
+    }
+}
+
+/// MWAIT 退出（需要 MWAIT exiting）
+pub struct MwaitExit;
+
+impl ExitHandler for MwaitExit {
+    open spec fn enabled(&self, cpu: &ArchCpu) -> bool {
+        cpu.ctls.proc_ctls.mwait_exiting
+    }
+    
+    open spec fn privileged(&self) -> bool {
+        false
+    }
+    
+    open spec fn fixed_result(&self) -> Option<EmulationResult> {
+        None
+    }
+    
+    fn is_privileged(&self) -> (result: bool) {
+        false
+    }
+    
+    fn handle(&self, cpu: &mut ArchCpu, _array: &mut PerCpuArray) -> (result: EmulationResult) {
+        cpu.handle_mwait()
+    }
+}
+
+/// NMI 退出（需要 NMI exiting）：host 的 NMI 不经 host IDT 投递，交还给 host 处理；模型不截获 guest 异常
+pub struct ExceptionNmiExit;
+
+impl ExitHandler for ExceptionNmiExit {
+    open spec fn enabled(&self, cpu: &ArchCpu) -> bool {
+        cpu.ctls.pin_ctls.nmi_exiting
+    }
+    
+    open spec fn privileged(&self) -> bool {
+        false
+    }
+    
+    open spec fn fixed_result(&self) -> Option<EmulationResult> {
+        None
+    }
+    
+    fn is_privileged(&self) -> (result: bool) {
+        false
+    }
+    
+    fn handle(&self, cpu: &mut ArchCpu, _array: &mut PerCpuArray) -> (result: EmulationResult) {
+        forward_nmi_to_host();
+        EmulationResult::Reenter
+    }
+}
+
+/// 外部中断退出（需要 external-interrupt exiting）
+pub struct ExternalInterruptExit;
+
+impl ExitHandler for ExternalInterruptExit {
+    open spec fn enabled(&self, cpu: &ArchCpu) -> bool {
+        cpu.ctls.pin_ctls.external_interrupt_exiting
+    }
+    
+    open spec fn privileged(&self) -> bool {
+        false
+    }
+    
+    open spec fn fixed_result(&self) -> Option<EmulationResult> {
+        None
+    }
+    
+    fn is_privileged(&self) -> (result: bool) {
+        false
+    }
+    
+    fn handle(&self, cpu: &mut ArchCpu, _array: &mut PerCpuArray) -> (result: EmulationResult) {
+        cpu.handle_external_interrupt()
+    }
+}
+
+/// 中断窗口退出（需要 interrupt-window exiting，窗口策略保证 external-interrupt exiting 同时开启）
+pub struct InterruptWindowExit;
+
+impl ExitHandler for InterruptWindowExit {
+    open spec fn enabled(&self, cpu: &ArchCpu) -> bool {
+        &&& cpu.ctls.proc_ctls.interrupt_window_exiting
+        &&& cpu.ctls.pin_ctls.external_interrupt_exiting
+    }
+    
+    open spec fn privileged(&self) -> bool {
+        false
+    }
+    
+    open spec fn fixed_result(&self) -> Option<EmulationResult> {
+        None
+    }
+    
+    fn is_privileged(&self) -> (result: bool) {
+        false
+    }
+    
+    fn handle(&self, cpu: &mut ArchCpu, _array: &mut PerCpuArray) -> (result: EmulationResult) {
+        cpu.handle_interrupt_window();
+        EmulationResult::Reenter
+    }
+}
+
+/// NMI 窗口退出：guest 的 IRET 解除了 NMI 阻塞
+pub struct NmiWindowExit;
+
+impl ExitHandler for NmiWindowExit {
+    open spec fn enabled(&self, cpu: &ArchCpu) -> bool {
+        cpu.ctls.tracks_virtual_nmi()
+    }
+    
+    open spec fn privileged(&self) -> bool {
+        false
+    }
+    
+    open spec fn fixed_result(&self) -> Option<EmulationResult> {
+        None
+    }
+    
+    fn is_privileged(&self) -> (result: bool) {
+        false
+    }
+    
+    fn handle(&self, cpu: &mut ArchCpu, _array: &mut PerCpuArray) -> (result: EmulationResult) {
+        cpu.handle_iret();
+        EmulationResult::Reenter
+    }
+}
+
+/// TPR below threshold 退出（需要 TPR shadow）
+pub struct TprBelowThresholdExit;
+
+impl ExitHandler for TprBelowThresholdExit {
+    open spec fn enabled(&self, cpu: &ArchCpu) -> bool {
+        cpu.ctls.proc_ctls.use_tpr_shadow
+    }
+    
+    open spec fn privileged(&self) -> bool {
+        false
+    }
+    
+    open spec fn fixed_result(&self) -> Option<EmulationResult> {
+        None
+    }
+    
+    fn is_privileged(&self) -> (result: bool) {
+        false
+    }
+    
+    fn handle(&self, cpu: &mut ArchCpu, _array: &mut PerCpuArray) -> (result: EmulationResult) {
+        cpu.handle_tpr_below_threshold();
+        EmulationResult::Reenter
+    }
+}
+
+/// SIPI 退出
+pub struct StartupIpiExit;
+
+impl ExitHandler for StartupIpiExit {
+    open spec fn enabled(&self, cpu: &ArchCpu) -> bool {
+        true
+    }
+    
+    open spec fn privileged(&self) -> bool {
+        false
+    }
+    
+    open spec fn fixed_result(&self) -> Option<EmulationResult> {
+        None
+    }
+    
+    fn is_privileged(&self) -> (result: bool) {
+        false
+    }
+    
+    fn handle(&self, cpu: &mut ArchCpu, _array: &mut PerCpuArray) -> (result: EmulationResult) {
+        // exit qualification 的 bits 7:0 为 SIPI 向量
+        let vector = (read_exit_qualification() & 0xff) as u8;
+        cpu.handle_sipi(vector);
+        EmulationResult::Reenter
+    }
+}
+
+/// 任务切换退出
+pub struct TaskSwitchExit;
+
+impl ExitHandler for TaskSwitchExit {
+    open spec fn enabled(&self, cpu: &ArchCpu) -> bool {
+        true
+    }
+    
+    open spec fn privileged(&self) -> bool {
+        false
+    }
+    
+    open spec fn fixed_result(&self) -> Option<EmulationResult> {
+        None
+    }
+    
+    fn is_privileged(&self) -> (result: bool) {
+        false
+    }
+    
+    fn handle(&self, cpu: &mut ArchCpu, _array: &mut PerCpuArray) -> (result: EmulationResult) {
+        cpu.handle_task_switch()
+    }
+}
+
+/// EPT misconfiguration 退出，永不返回
+pub struct EptMisconfigExit;
+
+impl ExitHandler for EptMisconfigExit {
+    open spec fn enabled(&self, cpu: &ArchCpu) -> bool {
+        true
+    }
+    
+    open spec fn privileged(&self) -> bool {
+        false
+    }
+    
+    open spec fn fixed_result(&self) -> Option<EmulationResult> {
+        None
+    }
+    
+    fn is_privileged(&self) -> (result: bool) {
+        false
+    }
+    
+    fn handle(&self, cpu: &mut ArchCpu, _array: &mut PerCpuArray) -> (result: EmulationResult) {
+        cpu.handle_ept_misconfig()
+    }
+}
+
+/// EPT violation 退出
+pub struct EptViolationExit;
+
+impl ExitHandler for EptViolationExit {
+    open spec fn enabled(&self, cpu: &ArchCpu) -> bool {
+        true
+    }
+    
+    open spec fn privileged(&self) -> bool {
+        false
+    }
+    
+    open spec fn fixed_result(&self) -> Option<EmulationResult> {
+        None
+    }
+    
+    fn is_privileged(&self) -> (result: bool) {
+        false
+    }
+    
+    fn handle(&self, cpu: &mut ArchCpu, _array: &mut PerCpuArray) -> (result: EmulationResult) {
+        cpu.handle_ept_violation(EXIT_REASON_EPT_VIOLATION)
+    }
+}
+
+/// MOV CR 退出（需要相应的 CR 截获）
+pub struct CrAccessExit;
+
+impl ExitHandler for CrAccessExit {
+    open spec fn enabled(&self, cpu: &ArchCpu) -> bool {
+        cpu.ctls.proc_ctls.cr3_load_exiting || cpu.ctls.cr0_mask != 0 || cpu.ctls.cr4_mask != 0
+            || cpu.ctls.proc_ctls.cr8_load_exiting || cpu.ctls.proc_ctls.cr8_store_exiting
+    }
+    
+    open spec fn privileged(&self) -> bool {
+        true
+    }
+    
+    open spec fn fixed_result(&self) -> Option<EmulationResult> {
+        None
+    }
+    
+    fn is_privileged(&self) -> (result: bool) {
+        true
+    }
+    
+    fn handle(&self, cpu: &mut ArchCpu, _array: &mut PerCpuArray) -> (result: EmulationResult) {
+        cpu.handle_cr_access()
+    }
+}
+
+/// MOV DR 退出（需要 MOV-DR exiting）
+pub struct DrAccessExit;
+
+impl ExitHandler for DrAccessExit {
+    open spec fn enabled(&self, cpu: &ArchCpu) -> bool {
+        cpu.ctls.proc_ctls.mov_dr_exiting
+    }
+    
+    open spec fn privileged(&self) -> bool {
+        true
+    }
+    
+    open spec fn fixed_result(&self) -> Option<EmulationResult> {
+        None
+    }
+    
+    fn is_privileged(&self) -> (result: bool) {
+        true
+    }
+    
+    fn handle(&self, cpu: &mut ArchCpu, _array: &mut PerCpuArray) -> (result: EmulationResult) {
+        cpu.handle_mov_dr()
+    }
+}
+
+/// GETSEC 退出（无条件）
+pub struct GetsecExit;
+
+impl ExitHandler for GetsecExit {
+    open spec fn enabled(&self, cpu: &ArchCpu) -> bool {
+        true
+    }
+    
+    open spec fn privileged(&self) -> bool {
+        false
+    }
+    
+    open spec fn fixed_result(&self) -> Option<EmulationResult> {
+        Some(EmulationResult::spec_ud())
+    }
+    
+    fn is_privileged(&self) -> (result: bool) {
+        false
+    }
+    
+    fn handle(&self, cpu: &mut ArchCpu, _array: &mut PerCpuArray) -> (result: EmulationResult) {
+        cpu.handle_getsec()
+    }
+}
+
+/// 嵌套 VMX 指令退出，is_vmxon 区分 VMXON 与其余 VMX 指令
+pub struct NestedVmxExit {
+    pub is_vmxon: bool,
+}
+
+impl ExitHandler for NestedVmxExit {
+    open spec fn enabled(&self, cpu: &ArchCpu) -> bool {
+        true
+    }
+    
+    open spec fn privileged(&self) -> bool {
+        false
+    }
+    
+    open spec fn fixed_result(&self) -> Option<EmulationResult> {
+        None
+    }
+    
+    fn is_privileged(&self) -> (result: bool) {
+        false
+    }
+    
+    fn handle(&self, cpu: &mut ArchCpu, _array: &mut PerCpuArray) -> (result: EmulationResult) {
+        cpu.handle_nested_vmx(self.is_vmxon)
+    }
+}
+
+/// x2APIC ICR 的 WRMSR 退出：目标是其他 vCPU 的信箱
+pub struct IcrWriteExit;
+
+impl ExitHandler for IcrWriteExit {
+    open spec fn enabled(&self, cpu: &ArchCpu) -> bool {
+        true
+    }
+    
+    open spec fn privileged(&self) -> bool {
+        true
+    }
+    
+    open spec fn fixed_result(&self) -> Option<EmulationResult> {
+        None
+    }
+    
+    fn is_privileged(&self) -> (result: bool) {
+        true
+    }
+    
+    fn handle(&self, cpu: &mut ArchCpu, array: &mut PerCpuArray) -> (result: EmulationResult) {
+        cpu.handle_icr_write(array)
+    }
+}
+
+/// APIC-write 退出（需要 APIC-register virtualization）
+pub struct ApicWriteExit;
+
+impl ExitHandler for ApicWriteExit {
+    open spec fn enabled(&self, cpu: &ArchCpu) -> bool {
+        cpu.ctls.apic_register_virt_enabled()
+    }
+    
+    open spec fn privileged(&self) -> bool {
+        false
+    }
+    
+    open spec fn fixed_result(&self) -> Option<EmulationResult> {
+        None
+    }
+    
+    fn is_privileged(&self) -> (result: bool) {
+        false
+    }
+    
+    fn handle(&self, cpu: &mut ArchCpu, array: &mut PerCpuArray) -> (result: EmulationResult) {
+        cpu.handle_apic_write(array)
+    }
+}
+
+/// virtual-EOI 退出（需要 virtual-interrupt delivery）
+pub struct VirtualizedEoiExit;
+
+impl ExitHandler for VirtualizedEoiExit {
+    open spec fn enabled(&self, cpu: &ArchCpu) -> bool {
+        cpu.ctls.virtual_intr_delivery_enabled()
+    }
+    
+    open spec fn privileged(&self) -> bool {
+        false
+    }
+    
+    open spec fn fixed_result(&self) -> Option<EmulationResult> {
+        None
+    }
+    
+    fn is_privileged(&self) -> (result: bool) {
+        false
+    }
+    
+    fn handle(&self, cpu: &mut ArchCpu, _array: &mut PerCpuArray) -> (result: EmulationResult) {
+        cpu.handle_virtual_eoi()
+    }
+}
+
+/// RDRAND 退出（需要 RDRAND exiting）
+pub struct RdrandExit;
+
+impl ExitHandler for RdrandExit {
+    open spec fn enabled(&self, cpu: &ArchCpu) -> bool {
+        cpu.ctls.rdrand_exiting_enabled()
+    }
+    
+    open spec fn privileged(&self) -> bool {
+        false
+    }
+    
+    open spec fn fixed_result(&self) -> Option<EmulationResult> {
+        None
+    }
+    
+    fn is_privileged(&self) -> (result: bool) {
+        false
+    }
+    
+    fn handle(&self, cpu: &mut ArchCpu, _array: &mut PerCpuArray) -> (result: EmulationResult) {
+        cpu.handle_rdrand()
+    }
+}
+
+/// RDSEED 退出（需要 RDSEED exiting）
+pub struct RdseedExit;
+
+impl ExitHandler for RdseedExit {
+    open spec fn enabled(&self, cpu: &ArchCpu) -> bool {
+        cpu.ctls.rdseed_exiting_enabled()
+    }
+    
+    open spec fn privileged(&self) -> bool {
+        false
+    }
+    
+    open spec fn fixed_result(&self) -> Option<EmulationResult> {
+        None
+    }
+    
+    fn is_privileged(&self) -> (result: bool) {
+        false
+    }
+    
+    fn handle(&self, cpu: &mut ArchCpu, _array: &mut PerCpuArray) -> (result: EmulationResult) {
+        cpu.handle_rdseed()
+    }
+}
+
+/// 引理：RDRAND / RDSEED 之后 CF 恰好表示是否取得随机数
+pub proof fn lemma_rand_rflags_cf(rflags: u64)
+    ensures
+        spec_rand_rflags(rflags, true) & RFLAGS_CF != 0,
+        spec_rand_rflags(rflags, false) & RFLAGS_CF == 0,
+{
+    assert(RFLAGS_ARITH == 0x8d5 && RFLAGS_CF == 1) by (compute_only);
+    assert(((rflags & !0x8d5u64) | 1u64) & 1u64 != 0) by (bit_vector);
+    assert(((rflags & !0x8d5u64) | 0u64) & 1u64 == 0) by (bit_vector);
+}
+
+/// CPUID 指令长度（0f a2）
+pub const VM_EXIT_INSTR_LEN_CPUID: u8 = 2;
+/// HLT 指令长度（f4）
+pub const VM_EXIT_INSTR_LEN_HLT: u8 = 1;
+/// MONITOR 指令长度（0f 01 c8）
+pub const VM_EXIT_INSTR_LEN_MONITOR: u8 = 3;
+/// MWAIT 指令长度（0f 01 c9）
+pub const VM_EXIT_INSTR_LEN_MWAIT: u8 = 3;
+/// RDMSR 指令长度（0f 32）
+pub const VM_EXIT_INSTR_LEN_RDMSR: u8 = 2;
+/// WRMSR 指令长度（0f 30）
+pub const VM_EXIT_INSTR_LEN_WRMSR: u8 = 2;
+/// x86 指令的最大长度
+pub const MAX_INSTR_LEN: u8 = 15;
+
+/// MWAIT 提示 EAX[7:4] 为目标 C-state 减一，0xF 表示 C0
+pub const MWAIT_HINT_C0: u64 = 0xf;
+
+/// 规范函数：MWAIT 的 EAX 提示请求比 C1 更深的 C-state（EAX[7:4] 不为 0 且不是 C0）
+pub open spec fn mwait_requests_deep_cstate(rax: u64) -> bool {
+    let hint = (rax >> 4u64) & 0xf;
+    hint != 0 && hint != MWAIT_HINT_C0
+}
+
+/// 规范函数：本次 VM Exit 的完整退出原因字段（bit 31 为 entry 失败，bits 15:0 为基本原因）
+pub open spec fn spec_exit_reason_raw() -> u32 {
+    spec_vmcs_field(VmcsField::ExitReason) as u32
+}
+
+/// 规范函数：本次 VM Exit 的基本退出原因（退出原因字段的 bits 15:0）
+pub open spec fn spec_exit_reason() -> u32 {
+    spec_exit_reason_raw() & EXIT_REASON_BASIC_MASK
+}
+
+/// 读取完整的 32 位退出原因字段，不截断 bit 31
+pub fn read_exit_reason() -> (result: u32)
+    ensures
+        result == spec_exit_reason_raw(),
+{
+    proof {
+        lemma_field_widths(VmcsField::ExitReason);
+    }
+    vmcs_read_field(VmcsField::ExitReason) as u32
+}
+
+/// 读取基本退出原因
+pub fn read_basic_exit_reason() -> (result: u32)
+    ensures
+        result == spec_exit_reason(),
+{
+    read_exit_reason() & EXIT_REASON_BASIC_MASK
+}
+
+/// 记录 VM entry 失败的诊断信息（日志钩子）
+#[verifier::external_body]
+pub fn log_entry_failure(reason: u32, detail: u64) {
+    // error!("VM entry failed: reason {:#x}, detail {}", reason, detail);
+}
+
+/// 规范函数：entry 失败子原因对应的致命错误
+pub open spec fn spec_entry_failure_error(kind: EntryFailureKind) -> FatalCpuError {
+    match kind {
+        EntryFailureKind::InvalidGuestState => FatalCpuError::InvalidGuestState,
+        EntryFailureKind::MsrLoading => FatalCpuError::MsrLoadFailed,
+        EntryFailureKind::MachineCheck => FatalCpuError::EntryMachineCheck,
+    }
+}
+
+pub fn entry_failure_error(kind: EntryFailureKind) -> (result: FatalCpuError)
+    ensures
+        result == spec_entry_failure_error(kind),
+{
+    match kind {
+        EntryFailureKind::InvalidGuestState => FatalCpuError::InvalidGuestState,
+        EntryFailureKind::MsrLoading => FatalCpuError::MsrLoadFailed,
+        EntryFailureKind::MachineCheck => FatalCpuError::EntryMachineCheck,
+    }
+}
+
+/// 引理：三种 entry 失败子原因落到三个不同的致命错误，日志中可以区分；
+/// handle_entry_failure 的各分支都以 cpu_fatal 结束，不会推进 RIP 或回到 guest
+pub proof fn lemma_entry_failure_handlers_distinct(a: EntryFailureKind, b: EntryFailureKind)
+    requires
+        a != b,
+    ensures
+        spec_entry_failure_error(a).spec_code() != spec_entry_failure_error(b).spec_code(),
+{
+}
+
+/// 记录未处理的退出原因（日志钩子）
+#[verifier::external_body]
+pub fn log_unhandled_exit(reason: u32) {
+    // warn!("Unhandled VM-Exit reason {}", reason);
+}
+
+/// 规范函数：当前 CPU 的 APIC ID（CPUID leaf 0xB 的 x2APIC ID）
+pub open spec fn spec_this_apic_id() -> usize {
+    spec_cpuid(CPUID_LEAF_EXT_TOPOLOGY, 0).edx as usize
+}
+
+/// 规范函数：APIC ID 到 CPU ID 的映射是恒等映射
+pub open spec fn apic_map_is_identity() -> bool {
+    spec_this_apic_id() == spec_this_cpu_id()
+}
+
+/// 获取当前 APIC ID：经 CPUID 缓存读 leaf 0xB 的 x2APIC ID
+/// 
+/// x2APIC ID 是 32 位值，上界不由硬件保证，见 lemma_apic_fits_cpu_num
+pub fn this_apic_id(cache: &mut CpuidCache) -> (result: usize)
+    requires
+        old(cache).inv(),
+    ensures
+        cache.inv(),
+        spec_this_apic_id() == result,
+{
+    cache.query(CPUID_LEAF_EXT_TOPOLOGY, 0).edx as usize
+}
+
+/// 引理：恒等映射下当前 CPU 的 APIC ID 就是所有者 ArchCpu 的 cpuid，因此小于 MAX_CPU_NUM
+/// 
+/// 上界来自 ArchCpu 的不变式而不是假设；两者都只引用 MAX_CPU_NUM，修改该常量时不会出现不一致
+pub proof fn lemma_apic_fits_cpu_num(cpu: &ArchCpu)
+    requires
+        cpu.inv(),
+        cpu.owned_by_current(),
+        apic_map_is_identity(),
+    ensures
+        spec_this_apic_id() == cpu.cpuid,
+        spec_this_apic_id() < MAX_CPU_NUM,
+{
+}
+
+/// 规范函数：当前 CPU 是 BSP（bootstrap processor）
+pub open spec fn current_cpu_is_bsp() -> bool {
+    spec_this_cpu_id() == 0
+}
+
+/// 引理：AP 上的 ArchCpu 不满足 cpu_start 的前置条件
+pub proof fn lemma_ap_cannot_start_cpus(cpu: &ArchCpu)
+    requires
+        cpu.cpuid == spec_this_cpu_id(),
+        !cpu.spec_is_bsp(),
     ensures
-        result < 256,
+        !current_cpu_is_bsp(),
 {
-    0  // todo 从 CPUID 读取
 }
 
-/// CPU 启动函数
+/// CPU 启动函数（只能由 BSP 调用）
 #[verifier::external_body]
 pub fn cpu_start(cpuid: usize, start_addr: usize, opaque: usize)
     requires
         cpuid < MAX_CPU_NUM,
+        current_cpu_is_bsp(),
 {
     // 使用 INIT-SIPI-SIPI 序列启动 AP
 }
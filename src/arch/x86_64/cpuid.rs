@@ -0,0 +1,320 @@
+// Verus-verified version of x86_64/cpuid.rs
+// CPUID 指令的硬件抽象
+
+use vstd::prelude::*;
+
+verus! {
+
+/// CPUID 指令返回的四个寄存器
+#[derive(Clone, Copy)]
+pub struct CpuIdResult {
+    pub eax: u32,
+    pub ebx: u32,
+    pub ecx: u32,
+    pub edx: u32,
+}
+
+/// 规范函数：硬件 CPUID 的返回值（纯函数：同一 leaf/subleaf 结果不变）
+pub uninterp spec fn spec_cpuid(leaf: u32, subleaf: u32) -> CpuIdResult;
+
+/// 执行 CPUID 指令（信任边界）
+#[verifier::external_body]
+pub fn cpuid(leaf: u32, subleaf: u32) -> (result: CpuIdResult)
+    ensures
+        result == spec_cpuid(leaf, subleaf),
+{
+    // 硬件操作：执行 cpuid（eax = leaf, ecx = subleaf）
+    CpuIdResult { eax: 0, ebx: 0, ecx: 0, edx: 0 }
+}
+
+/// 扩展 leaf：物理/线性地址宽度（EAX[7:0] 为 MAXPHYADDR）
+pub const CPUID_LEAF_ADDR_SIZES: u32 = 0x8000_0008;
+
+/// 规范函数：物理地址上限 2^MAXPHYADDR
+pub open spec fn spec_max_phys_addr() -> u64 {
+    let bits = spec_cpuid(CPUID_LEAF_ADDR_SIZES, 0).eax & 0xff;
+    if bits >= 64 { u64::MAX } else { 1u64 << bits }
+}
+
+pub fn max_phys_addr() -> (result: u64)
+    ensures
+        result == spec_max_phys_addr(),
+{
+    let bits = cpuid(CPUID_LEAF_ADDR_SIZES, 0).eax & 0xff;
+    if bits >= 64 { u64::MAX } else { 1u64 << bits }
+}
+
+/// 基本 leaf：处理器特性
+pub const CPUID_LEAF_FEATURES: u32 = 1;
+/// 基本 leaf：扩展拓扑枚举（EDX 为当前逻辑处理器的 x2APIC ID，与 subleaf 无关）
+pub const CPUID_LEAF_EXT_TOPOLOGY: u32 = 0xb;
+/// leaf 1 ECX：VMX、SMX（不支持嵌套虚拟化，对 guest 隐藏）
+pub const CPUID_1_ECX_VMX: u32 = 1 << 5;
+pub const CPUID_1_ECX_SMX: u32 = 1 << 6;
+/// leaf 1 ECX：hypervisor present，guest 总能看到
+pub const CPUID_1_ECX_HYPERVISOR: u32 = 1 << 31;
+
+/// 规范函数：各寄存器保留的特性位（EAX, EBX, ECX, EDX），清零的位对 guest 隐藏
+pub open spec fn spec_cpuid_mask(leaf: u32, subleaf: u32) -> (u32, u32, u32, u32) {
+    if leaf == CPUID_LEAF_FEATURES {
+        (0xffff_ffff, 0xffff_ffff, !(CPUID_1_ECX_VMX | CPUID_1_ECX_SMX), 0xffff_ffff)
+    } else {
+        (0xffff_ffff, 0xffff_ffff, 0xffff_ffff, 0xffff_ffff)
+    }
+}
+
+pub fn cpuid_mask(leaf: u32, subleaf: u32) -> (result: (u32, u32, u32, u32))
+    ensures
+        result == spec_cpuid_mask(leaf, subleaf),
+{
+    if leaf == CPUID_LEAF_FEATURES {
+        (0xffff_ffff, 0xffff_ffff, !(CPUID_1_ECX_VMX | CPUID_1_ECX_SMX), 0xffff_ffff)
+    } else {
+        (0xffff_ffff, 0xffff_ffff, 0xffff_ffff, 0xffff_ffff)
+    }
+}
+
+/// 规范函数：强制置位的 ECX 位（leaf 1 的 hypervisor present）
+pub open spec fn spec_cpuid_forced_ecx(leaf: u32) -> u32 {
+    if leaf == CPUID_LEAF_FEATURES { CPUID_1_ECX_HYPERVISOR } else { 0 }
+}
+
+/// 规范函数：对硬件结果应用特性屏蔽
+pub open spec fn spec_masked_cpuid(raw: CpuIdResult, leaf: u32, subleaf: u32) -> CpuIdResult {
+    let mask = spec_cpuid_mask(leaf, subleaf);
+    CpuIdResult {
+        eax: raw.eax & mask.0,
+        ebx: raw.ebx & mask.1,
+        ecx: (raw.ecx & mask.2) | spec_cpuid_forced_ecx(leaf),
+        edx: raw.edx & mask.3,
+    }
+}
+
+pub fn masked_cpuid(raw: CpuIdResult, leaf: u32, subleaf: u32) -> (result: CpuIdResult)
+    ensures
+        result == spec_masked_cpuid(raw, leaf, subleaf),
+{
+    let mask = cpuid_mask(leaf, subleaf);
+    let forced = if leaf == CPUID_LEAF_FEATURES { CPUID_1_ECX_HYPERVISOR } else { 0 };
+    CpuIdResult {
+        eax: raw.eax & mask.0,
+        ebx: raw.ebx & mask.1,
+        ecx: (raw.ecx & mask.2) | forced,
+        edx: raw.edx & mask.3,
+    }
+}
+
+/// hypervisor CPUID 区间 0x4000_0000 ~ 0x4000_00ff
+pub const HV_CPUID_BASE: u32 = 0x4000_0000;
+pub const HV_CPUID_FEATURES: u32 = 0x4000_0001;
+pub const HV_CPUID_RANGE_END: u32 = 0x4000_00ff;
+/// 已实现的最大 hypervisor leaf
+pub const HV_CPUID_MAX_LEAF: u32 = HV_CPUID_FEATURES;
+
+/// 厂商签名 "HVISOR\0\0\0\0\0\0"（EBX:ECX:EDX，小端）
+pub const HV_SIGNATURE_EBX: u32 = 0x5349_5648;  // "HVIS"
+pub const HV_SIGNATURE_ECX: u32 = 0x0000_524f;  // "OR\0\0"
+pub const HV_SIGNATURE_EDX: u32 = 0;
+/// leaf 0x4000_0001 的特性位图（目前没有半虚拟化特性）
+pub const HV_FEATURES: u32 = 0;
+
+/// 规范函数：leaf 落在 hypervisor CPUID 区间
+pub open spec fn is_hv_leaf(leaf: u32) -> bool {
+    HV_CPUID_BASE <= leaf && leaf <= HV_CPUID_RANGE_END
+}
+
+/// 规范函数：guest 看到的 CPUID 结果
+/// 
+/// hypervisor 区间完全由 hvisor 定义，超过 HV_CPUID_MAX_LEAF 的 leaf 返回全零，不透传硬件；
+/// 其余 leaf 透传硬件并经过特性屏蔽
+pub open spec fn spec_guest_cpuid(leaf: u32, subleaf: u32) -> CpuIdResult {
+    if leaf == HV_CPUID_BASE {
+        CpuIdResult { eax: HV_CPUID_MAX_LEAF, ebx: HV_SIGNATURE_EBX, ecx: HV_SIGNATURE_ECX, edx: HV_SIGNATURE_EDX }
+    } else if leaf == HV_CPUID_FEATURES {
+        CpuIdResult { eax: HV_FEATURES, ebx: 0, ecx: 0, edx: 0 }
+    } else if is_hv_leaf(leaf) {
+        CpuIdResult { eax: 0, ebx: 0, ecx: 0, edx: 0 }
+    } else {
+        spec_masked_cpuid(spec_cpuid(leaf, subleaf), leaf, subleaf)
+    }
+}
+
+pub fn guest_cpuid(leaf: u32, subleaf: u32) -> (result: CpuIdResult)
+    ensures
+        result == spec_guest_cpuid(leaf, subleaf),
+{
+    if leaf == HV_CPUID_BASE {
+        CpuIdResult { eax: HV_CPUID_MAX_LEAF, ebx: HV_SIGNATURE_EBX, ecx: HV_SIGNATURE_ECX, edx: HV_SIGNATURE_EDX }
+    } else if leaf == HV_CPUID_FEATURES {
+        CpuIdResult { eax: HV_FEATURES, ebx: 0, ecx: 0, edx: 0 }
+    } else if HV_CPUID_BASE <= leaf && leaf <= HV_CPUID_RANGE_END {
+        CpuIdResult { eax: 0, ebx: 0, ecx: 0, edx: 0 }
+    } else {
+        masked_cpuid(cpuid(leaf, subleaf), leaf, subleaf)
+    }
+}
+
+/// CPUID 缓存的容量（透传的 leaf/subleaf 组合数），满了之后不再缓存，也不淘汰
+pub const CPUID_CACHE_SIZE: usize = 32;
+
+/// 缓存的一项：硬件 CPUID 的原始结果
+pub struct CpuidCacheEntry {
+    pub leaf: u32,
+    pub subleaf: u32,
+    pub result: CpuIdResult,
+}
+
+/// 透传 leaf 的硬件 CPUID 结果缓存，避免重复执行 CPUID
+pub struct CpuidCache {
+    entries: Vec<CpuidCacheEntry>,
+}
+
+impl CpuidCache {
+    /// 规范函数：容量有界，且每一项都等于硬件结果
+    pub closed spec fn inv(&self) -> bool {
+        &&& self.entries.len() <= CPUID_CACHE_SIZE
+        &&& forall|i: int| 0 <= i < self.entries.len() ==>
+            #[trigger] self.entries@[i].result == spec_cpuid(self.entries@[i].leaf, self.entries@[i].subleaf)
+    }
+    
+    pub fn new() -> (result: Self)
+        ensures
+            result.inv(),
+    {
+        CpuidCache { entries: Vec::new() }
+    }
+    
+    /// 查找缓存，命中的结果等于硬件结果
+    fn lookup(&self, leaf: u32, subleaf: u32) -> (result: Option<CpuIdResult>)
+        requires
+            self.inv(),
+        ensures
+            result matches Some(res) ==> res == spec_cpuid(leaf, subleaf),
+    {
+        let mut i: usize = 0;
+        while i < self.entries.len()
+            invariant
+                self.inv(),
+                i <= self.entries.len(),
+            decreases self.entries.len() - i,
+        {
+            let entry = &self.entries[i];
+            if entry.leaf == leaf && entry.subleaf == subleaf {
+                return Some(entry.result);
+            }
+            i = i + 1;
+        }
+        None
+    }
+    
+    /// 取硬件 CPUID 结果：先查缓存，未命中时执行 CPUID 并在容量允许时缓存
+    /// 
+    /// 无论是否命中，返回值都等于 spec_cpuid，guest 看不到缓存带来的差异
+    pub fn query(&mut self, leaf: u32, subleaf: u32) -> (result: CpuIdResult)
+        requires
+            old(self).inv(),
+        ensures
+            self.inv(),
+            result == spec_cpuid(leaf, subleaf),
+    {
+        if let Some(res) = self.lookup(leaf, subleaf) {
+            return res;
+        }
+        let res = cpuid(leaf, subleaf);
+        if self.entries.len() < CPUID_CACHE_SIZE {
+            self.entries.push(CpuidCacheEntry { leaf, subleaf, result: res });
+            proof {
+                assert forall|i: int| 0 <= i < self.entries.len() implies
+                    #[trigger] self.entries@[i].result == spec_cpuid(self.entries@[i].leaf, self.entries@[i].subleaf)
+                by {
+                    if i < old(self).entries.len() {
+                        assert(self.entries@[i] == old(self).entries@[i]);
+                    }
+                }
+            }
+        }
+        res
+    }
+}
+
+/// 经缓存计算 guest 看到的 CPUID 结果，与 guest_cpuid 相同
+pub fn guest_cpuid_cached(cache: &mut CpuidCache, leaf: u32, subleaf: u32) -> (result: CpuIdResult)
+    requires
+        old(cache).inv(),
+    ensures
+        cache.inv(),
+        result == spec_guest_cpuid(leaf, subleaf),
+{
+    if leaf == HV_CPUID_BASE {
+        CpuIdResult { eax: HV_CPUID_MAX_LEAF, ebx: HV_SIGNATURE_EBX, ecx: HV_SIGNATURE_ECX, edx: HV_SIGNATURE_EDX }
+    } else if leaf == HV_CPUID_FEATURES {
+        CpuIdResult { eax: HV_FEATURES, ebx: 0, ecx: 0, edx: 0 }
+    } else if HV_CPUID_BASE <= leaf && leaf <= HV_CPUID_RANGE_END {
+        CpuIdResult { eax: 0, ebx: 0, ecx: 0, edx: 0 }
+    } else {
+        masked_cpuid(cache.query(leaf, subleaf), leaf, subleaf)
+    }
+}
+
+/// 引理：超过最大 hypervisor leaf 的区间内 leaf 返回全零
+pub proof fn lemma_hv_leaf_above_max_is_zero(leaf: u32, subleaf: u32)
+    requires
+        is_hv_leaf(leaf),
+        leaf > HV_CPUID_MAX_LEAF,
+    ensures
+        spec_guest_cpuid(leaf, subleaf) == (CpuIdResult { eax: 0, ebx: 0, ecx: 0, edx: 0 }),
+{
+}
+
+/// 引理：强制位都在屏蔽保留的范围内
+pub proof fn lemma_forced_within_mask(leaf: u32, subleaf: u32)
+    ensures
+        spec_cpuid_forced_ecx(leaf) & !spec_cpuid_mask(leaf, subleaf).2 == 0,
+{
+    if leaf == CPUID_LEAF_FEATURES {
+        assert(CPUID_1_ECX_HYPERVISOR & !!(CPUID_1_ECX_VMX | CPUID_1_ECX_SMX) == 0) by (bit_vector);
+    } else {
+        assert(0u32 & !0xffff_ffffu32 == 0) by (bit_vector);
+    }
+}
+
+/// 引理：被屏蔽的位在 guest 可见的结果中恒为 0，与硬件返回值无关
+pub proof fn lemma_masked_bits_zero(raw: CpuIdResult, leaf: u32, subleaf: u32)
+    ensures
+        ({
+            let res = spec_masked_cpuid(raw, leaf, subleaf);
+            let mask = spec_cpuid_mask(leaf, subleaf);
+            &&& res.eax & !mask.0 == 0
+            &&& res.ebx & !mask.1 == 0
+            &&& res.ecx & !mask.2 == 0
+            &&& res.edx & !mask.3 == 0
+        }),
+{
+    let mask = spec_cpuid_mask(leaf, subleaf);
+    let forced = spec_cpuid_forced_ecx(leaf);
+    lemma_forced_within_mask(leaf, subleaf);
+    let (a, b, c, d) = (raw.eax, raw.ebx, raw.ecx, raw.edx);
+    let (m0, m1, m2, m3) = (mask.0, mask.1, mask.2, mask.3);
+    assert((a & m0) & !m0 == 0) by (bit_vector);
+    assert((b & m1) & !m1 == 0) by (bit_vector);
+    assert((d & m3) & !m3 == 0) by (bit_vector);
+    assert(((c & m2) | forced) & !m2 == 0) by (bit_vector)
+        requires
+            forced & !m2 == 0;
+}
+
+/// 引理：guest 永远看不到 VMX 特性位，而 hypervisor present 位总是置位
+pub proof fn lemma_guest_never_sees_vmx(subleaf: u32)
+    ensures
+        spec_guest_cpuid(CPUID_LEAF_FEATURES, subleaf).ecx & CPUID_1_ECX_VMX == 0,
+        spec_guest_cpuid(CPUID_LEAF_FEATURES, subleaf).ecx & CPUID_1_ECX_HYPERVISOR != 0,
+{
+    let raw = spec_cpuid(CPUID_LEAF_FEATURES, subleaf);
+    let c = raw.ecx;
+    assert(((c & !(CPUID_1_ECX_VMX | CPUID_1_ECX_SMX)) | CPUID_1_ECX_HYPERVISOR) & CPUID_1_ECX_VMX == 0)
+        by (bit_vector);
+    assert(((c & !(CPUID_1_ECX_VMX | CPUID_1_ECX_SMX)) | CPUID_1_ECX_HYPERVISOR) & CPUID_1_ECX_HYPERVISOR != 0)
+        by (bit_vector);
+}
+
+} // verus!
@@ -0,0 +1,311 @@
+// Verus-verified version of x86_64/ept.rs
+// EPT（扩展页表）翻译、地址检查与 host 物理内存访问的抽象
+
+use vstd::prelude::*;
+use super::cpuid::*;
+
+verus! {
+
+pub const PAGE_SIZE: u64 = 4096;
+
+/// EPT 翻译结果（叶子表项）
+#[derive(Clone, Copy)]
+pub struct EptMapping {
+    pub hpa: u64,  // gpa 对应的 host 物理地址（保留页内偏移）
+    pub read: bool,
+    pub write: bool,
+    pub exec: bool,
+}
+
+/// 规范函数：EPT 将 guest 物理地址翻译为 host 物理地址
+pub uninterp spec fn spec_ept_translate(gpa: u64) -> Option<EptMapping>;
+
+/// 遍历 EPT 页表（信任边界）
+#[verifier::external_body]
+pub fn ept_translate(gpa: u64) -> (result: Option<EptMapping>)
+    ensures
+        result == spec_ept_translate(gpa),
+{
+    // 页表操作：从 EPTP 开始逐级查询
+    None
+}
+
+/// 规范函数：按 guest 页表把取指的线性地址 la 翻译为 guest 物理地址
+/// 
+/// cr3 为页表根，cr4 / efer 决定分页层级，cpl 为 3 时还要求各级 U/S 位；
+/// 表项不存在或不可执行（NX）时为 None
+pub uninterp spec fn spec_guest_fetch_walk(cr3: u64, cr4: u64, efer: u64, cpl: u8, la: u64) -> Option<u64>;
+
+/// 遍历 guest 页表（信任边界）
+#[verifier::external_body]
+pub fn guest_fetch_walk(cr3: u64, cr4: u64, efer: u64, cpl: u8, la: u64) -> (result: Option<u64>)
+    ensures
+        result == spec_guest_fetch_walk(cr3, cr4, efer, cpl, la),
+{
+    // 页表操作：从 cr3 开始逐级查询，各级表项位于 guest 物理内存，先经 EPT 翻译再读取
+    None
+}
+
+/// guest 内存：gpa [gpa_base, gpa_base + size) 线性对应 hpa [hpa_base, hpa_base + size)
+/// 
+/// zone 创建时从 host 保留的连续物理内存，按需（第一次访问时）映射进 EPT，权限总是 RWX
+#[derive(Clone, Copy)]
+pub struct GuestMemoryRegion {
+    pub gpa_base: u64,
+    pub hpa_base: u64,
+    pub size: u64,
+}
+
+impl GuestMemoryRegion {
+    /// 规范函数：gpa 属于 guest 内存
+    pub open spec fn contains(&self, gpa: u64) -> bool {
+        self.gpa_base <= gpa && gpa - self.gpa_base < self.size
+    }
+    
+    /// 规范函数：gpa 对应的 host 物理地址
+    pub open spec fn spec_hpa_of(&self, gpa: u64) -> u64 {
+        (self.hpa_base + (gpa - self.gpa_base)) as u64
+    }
+}
+
+/// 规范函数：本 zone 的 guest 内存
+pub uninterp spec fn spec_guest_memory() -> GuestMemoryRegion;
+
+#[verifier::external_body]
+pub fn guest_memory() -> (result: GuestMemoryRegion)
+    ensures
+        result == spec_guest_memory(),
+        result.gpa_base % PAGE_SIZE == 0,
+        result.hpa_base % PAGE_SIZE == 0,
+        result.gpa_base + result.size <= u64::MAX,
+        result.hpa_base + result.size <= u64::MAX,
+{
+    GuestMemoryRegion { gpa_base: 0, hpa_base: 0x4000_0000, size: 0x1000_0000 }  // 示例值
+}
+
+/// 把 gpa 所在 4 KiB 页的叶子表项写入 EPT 并执行 INVEPT（信任边界）
+/// 
+/// 只接受 well_formed 的表项，因此写入之后不会出现 EPT misconfiguration
+#[verifier::external_body]
+pub fn ept_install_leaf(gpa: u64, entry: u64)
+    requires
+        well_formed(entry, spec_max_phys_addr()),
+{
+    // 页表操作：从 EPTP 开始逐级查找（缺失的中间级按需分配），写入叶子表项；INVEPT single-context
+}
+
+/// 规范函数：hypervisor 能否为 gpa 补上映射
+/// 
+/// guest 内存总是映射为 RWX，违例的访问类型不影响结果；gpa 不属于 guest 内存（例如未模拟的 MMIO）
+/// 或对应的页帧超出 MAXPHYADDR 时不能修复
+pub open spec fn spec_ept_fault_resolvable(gpa: u64) -> bool {
+    let mem = spec_guest_memory();
+    &&& mem.contains(gpa)
+    &&& mem.spec_hpa_of(gpa) & EPT_ADDR_MASK < spec_max_phys_addr()
+}
+
+/// 为 EPT violation 建立映射
+/// 
+/// 返回 false 表示 gpa 无法映射，EPT 没有变化
+pub fn resolve_ept_fault(gpa: u64) -> (result: bool)
+    ensures
+        result == spec_ept_fault_resolvable(gpa),
+{
+    let mem = guest_memory();
+    if gpa < mem.gpa_base || gpa - mem.gpa_base >= mem.size {
+        return false;
+    }
+    let hpa = mem.hpa_base + (gpa - mem.gpa_base);
+    match map_page(hpa, true, true, true, max_phys_addr()) {
+        Some(entry) => {
+            ept_install_leaf(gpa, entry);
+            true
+        },
+        None => false,
+    }
+}
+
+pub const EPT_READ: u64 = 1 << 0;
+pub const EPT_WRITE: u64 = 1 << 1;
+pub const EPT_EXEC: u64 = 1 << 2;
+/// 叶子表项 bits 5:3 为 EPT 内存类型
+pub const EPT_MEMTYPE_SHIFT: u64 = 3;
+pub const EPT_MEMTYPE_MASK: u64 = 0x7 << 3;
+pub const EPT_MEMTYPE_UC: u64 = 0;
+pub const EPT_MEMTYPE_WB: u64 = 6;
+pub const EPT_ADDR_MASK: u64 = 0x000f_ffff_ffff_f000;
+
+/// 规范函数：EPT 内存类型合法（2、3、7 保留）
+pub open spec fn spec_ept_memtype_valid(memtype: u64) -> bool {
+    memtype == 0 || memtype == 1 || memtype == 4 || memtype == 5 || memtype == 6
+}
+
+/// 规范函数：EPT 叶子表项格式正确，不会引起 EPT misconfiguration（SDM 29.3.3.1）
+/// 
+/// - 可写必须可读
+/// - 存在的表项内存类型不能取保留值
+/// - 页帧地址低于 MAXPHYADDR
+pub open spec fn well_formed(entry: u64, max_phys: u64) -> bool {
+    &&& entry & EPT_WRITE != 0 ==> entry & EPT_READ != 0
+    &&& entry & (EPT_READ | EPT_WRITE | EPT_EXEC) != 0 ==> {
+        &&& spec_ept_memtype_valid((entry & EPT_MEMTYPE_MASK) >> EPT_MEMTYPE_SHIFT)
+        &&& entry & EPT_ADDR_MASK < max_phys
+    }
+}
+
+/// 构造映射 hpa 的 4 KiB 叶子表项（写回内存类型）
+/// 
+/// 可写不可读或页帧越界的请求被拒绝，返回的表项总是 well_formed
+pub fn map_page(hpa: u64, read: bool, write: bool, exec: bool, max_phys: u64) -> (result: Option<u64>)
+    ensures
+        result matches Some(entry) ==> well_formed(entry, max_phys),
+        result is None <==> ((write && !read) || (hpa & EPT_ADDR_MASK) >= max_phys),
+{
+    if (write && !read) || (hpa & EPT_ADDR_MASK) >= max_phys {
+        return None;
+    }
+    let r: u64 = if read { EPT_READ } else { 0 };
+    let w: u64 = if write { EPT_WRITE } else { 0 };
+    let x: u64 = if exec { EPT_EXEC } else { 0 };
+    let addr = hpa & EPT_ADDR_MASK;
+    let entry = addr | (EPT_MEMTYPE_WB << EPT_MEMTYPE_SHIFT) | r | w | x;
+    proof {
+        assert(addr & 0x000f_ffff_ffff_f000u64 == addr) by (bit_vector)
+            requires addr == hpa & 0x000f_ffff_ffff_f000u64;
+        lemma_map_page_well_formed(addr, r, w, x, max_phys);
+    }
+    Some(entry)
+}
+
+/// 引理：map_page 拼出的表项格式正确，EPT misconfiguration 在正确的代码中不可达
+pub proof fn lemma_map_page_well_formed(addr: u64, r: u64, w: u64, x: u64, max_phys: u64)
+    requires
+        addr & EPT_ADDR_MASK == addr,
+        addr < max_phys,
+        r == 0 || r == EPT_READ,
+        w == 0 || w == EPT_WRITE,
+        x == 0 || x == EPT_EXEC,
+        w != 0 ==> r != 0,
+    ensures
+        well_formed(addr | (EPT_MEMTYPE_WB << EPT_MEMTYPE_SHIFT) | r | w | x, max_phys),
+{
+    let entry = addr | (EPT_MEMTYPE_WB << EPT_MEMTYPE_SHIFT) | r | w | x;
+    assert(EPT_MEMTYPE_WB << EPT_MEMTYPE_SHIFT == 0x30) by (compute_only);
+    assert(EPT_MEMTYPE_MASK == 0x38) by (compute_only);
+    assert(addr & 0x000f_ffff_ffff_f000u64 == addr);
+    assert({
+        let e = addr | 0x30u64 | r | w | x;
+        &&& (e & 0x38u64) >> 3u64 == 6
+        &&& e & 0x000f_ffff_ffff_f000u64 == addr
+        &&& e & 2u64 == w
+        &&& e & 1u64 == r
+    }) by (bit_vector)
+        requires
+            addr & 0x000f_ffff_ffff_f000u64 == addr,
+            r == 0 || r == 1,
+            w == 0 || w == 2,
+            x == 0 || x == 4;
+    assert(entry & EPT_ADDR_MASK == addr);
+    assert(entry & EPT_ADDR_MASK < max_phys);
+    assert(spec_ept_memtype_valid((entry & EPT_MEMTYPE_MASK) >> EPT_MEMTYPE_SHIFT));
+    assert(entry & EPT_WRITE != 0 ==> entry & EPT_READ != 0);
+}
+
+/// 规范函数：合法的访存宽度（字节）
+pub open spec fn is_valid_access_size(size: u8) -> bool {
+    size == 1 || size == 2 || size == 4 || size == 8
+}
+
+/// 规范函数：访问 [addr, addr + size) 不跨 4 KiB 页
+pub open spec fn within_page(addr: u64, size: u8) -> bool {
+    (addr % PAGE_SIZE) + (size as u64) <= PAGE_SIZE
+}
+
+/// 规范函数：48 位线性地址的规范形式（bit 63:47 全相同）
+pub open spec fn spec_is_canonical(addr: u64) -> bool {
+    addr < 0x0000_8000_0000_0000 || addr >= 0xffff_8000_0000_0000
+}
+
+pub fn is_canonical(addr: u64) -> (result: bool)
+    ensures
+        result == spec_is_canonical(addr),
+{
+    addr < 0x0000_8000_0000_0000 || addr >= 0xffff_8000_0000_0000
+}
+
+/// 规范函数：线性地址在当前分页宽度下是规范地址
+/// 
+/// 4 级分页为 48 位（bit 63:47 相同），LA57 的 5 级分页为 57 位（bit 63:56 相同）
+pub open spec fn spec_is_canonical_for(addr: u64, la57: bool) -> bool {
+    if la57 {
+        addr < 0x0100_0000_0000_0000 || addr >= 0xff00_0000_0000_0000
+    } else {
+        spec_is_canonical(addr)
+    }
+}
+
+pub fn is_canonical_for(addr: u64, la57: bool) -> (result: bool)
+    ensures
+        result == spec_is_canonical_for(addr, la57),
+{
+    if la57 {
+        addr < 0x0100_0000_0000_0000 || addr >= 0xff00_0000_0000_0000
+    } else {
+        is_canonical(addr)
+    }
+}
+
+/// 规范函数：gpa 落在 guest 物理地址宽度内（低于 MAXPHYADDR）
+/// 
+/// GPA 不是线性地址，没有规范形式的要求；超出 MAXPHYADDR 的 GPA 无法经 EPT 翻译
+pub open spec fn spec_gpa_in_range(gpa: u64) -> bool {
+    gpa < spec_max_phys_addr()
+}
+
+pub fn gpa_in_range(gpa: u64) -> (result: bool)
+    ensures
+        result == spec_gpa_in_range(gpa),
+{
+    gpa < max_phys_addr()
+}
+
+/// 规范函数：guest 可读该地址（GPA 低于 MAXPHYADDR 且 EPT 映射可读）
+pub open spec fn spec_guest_readable(gpa: u64) -> bool {
+    &&& spec_gpa_in_range(gpa)
+    &&& spec_ept_translate(gpa) is Some
+    &&& spec_ept_translate(gpa).unwrap().read
+}
+
+/// 规范函数：size 字节的值零扩展到 u64 后高位为 0
+pub open spec fn zero_extended(val: u64, size: u8) -> bool {
+    &&& (size == 1 ==> val <= 0xff)
+    &&& (size == 2 ==> val <= 0xffff)
+    &&& (size == 4 ==> val <= 0xffff_ffff)
+}
+
+/// 规范函数：host 物理内存的内容
+pub uninterp spec fn spec_host_phys_read(hpa: u64, size: u8) -> u64;
+
+/// 读 host 物理内存（信任边界）
+#[verifier::external_body]
+pub fn host_phys_read(hpa: u64, size: u8) -> (result: u64)
+    requires
+        is_valid_access_size(size),
+    ensures
+        result == spec_host_phys_read(hpa, size),
+        zero_extended(result, size),
+{
+    // 硬件操作：按 size 读取并零扩展
+    0
+}
+
+/// 写 host 物理内存（信任边界）
+#[verifier::external_body]
+pub fn host_phys_write(hpa: u64, val: u64, size: u8)
+    requires
+        is_valid_access_size(size),
+{
+    // 硬件操作：按 size 写入 val 的低位字节
+}
+
+} // verus!
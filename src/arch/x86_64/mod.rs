@@ -1,3 +1,15 @@
+pub mod apic;
 pub mod cpu;
+pub mod cpuid;
+pub mod ept;
+pub mod msr;
+pub mod vmcs;
+pub mod vmx;
 
-pub use cpu::*;
\ No newline at end of file
+pub use apic::*;
+pub use cpu::*;
+pub use cpuid::*;
+pub use ept::*;
+pub use msr::*;
+pub use vmcs::*;
+pub use vmx::*;
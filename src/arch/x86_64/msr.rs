@@ -0,0 +1,299 @@
+// Verus-verified version of x86_64/msr.rs
+// guest 可见的 MSR 编号与 RDMSR/WRMSR 的寄存器约定
+
+use vstd::prelude::*;
+
+verus! {
+
+pub const IA32_APIC_BASE: u32 = 0x1b;
+pub const IA32_FEATURE_CONTROL: u32 = 0x3a;
+pub const IA32_SYSENTER_CS: u32 = 0x174;
+pub const IA32_SYSENTER_ESP: u32 = 0x175;
+pub const IA32_SYSENTER_EIP: u32 = 0x176;
+pub const IA32_PAT: u32 = 0x277;
+pub const IA32_TSC_DEADLINE: u32 = 0x6e0;
+pub const IA32_VMX_PINBASED_CTLS: u32 = 0x481;
+pub const IA32_VMX_PROCBASED_CTLS: u32 = 0x482;
+pub const IA32_VMX_EXIT_CTLS: u32 = 0x483;
+pub const IA32_VMX_ENTRY_CTLS: u32 = 0x484;
+pub const IA32_VMX_PROCBASED_CTLS2: u32 = 0x48b;
+/// x2APIC EOI 寄存器（只写，写入值必须为 0）
+pub const IA32_X2APIC_EOI: u32 = 0x80b;
+/// x2APIC SVR
+pub const IA32_X2APIC_SVR: u32 = 0x80f;
+/// x2APIC ICR（64 位，目标 APIC ID 位于 bits 63:32）
+pub const IA32_X2APIC_ICR: u32 = 0x830;
+/// x2APIC LVT：timer、thermal、performance、LINT0、LINT1、error 依次为 0x832-0x837
+pub const IA32_X2APIC_LVT_TIMER: u32 = 0x832;
+pub const IA32_X2APIC_LVT_ERROR: u32 = 0x837;
+pub const IA32_EFER: u32 = 0xc000_0080;
+pub const IA32_STAR: u32 = 0xc000_0081;
+pub const IA32_LSTAR: u32 = 0xc000_0082;
+pub const IA32_CSTAR: u32 = 0xc000_0083;
+pub const IA32_FMASK: u32 = 0xc000_0084;
+pub const IA32_FS_BASE: u32 = 0xc000_0100;
+pub const IA32_GS_BASE: u32 = 0xc000_0101;
+pub const IA32_KERNEL_GS_BASE: u32 = 0xc000_0102;
+
+pub const EFER_SCE: u64 = 1 << 0;
+pub const EFER_LME: u64 = 1 << 8;
+pub const EFER_LMA: u64 = 1 << 10;
+pub const EFER_NXE: u64 = 1 << 11;
+/// SCE、LME、LMA、NXE 以外的位保留
+pub const EFER_RESERVED: u64 = !(EFER_SCE | EFER_LME | EFER_LMA | EFER_NXE);
+
+pub open spec fn efer_lme(efer: u64) -> bool {
+    efer & EFER_LME != 0
+}
+
+pub open spec fn efer_lma(efer: u64) -> bool {
+    efer & EFER_LMA != 0
+}
+
+pub const FEATURE_CONTROL_LOCK: u64 = 1 << 0;
+pub const FEATURE_CONTROL_VMX_OUTSIDE_SMX: u64 = 1 << 2;
+
+/// 规范函数：host 的 IA32_FEATURE_CONTROL（由固件设置并锁定）
+pub uninterp spec fn spec_host_feature_control() -> u64;
+
+#[verifier::external_body]
+pub fn read_host_feature_control() -> (result: u64)
+    ensures
+        result == spec_host_feature_control(),
+{
+    0  // 硬件操作：rdmsr IA32_FEATURE_CONTROL
+}
+
+/// 规范函数：IA32_FEATURE_CONTROL 允许 SMX 之外的 VMXON（已锁定且 bit 2 置位），否则 VMXON #GP
+pub open spec fn spec_vmxon_allowed(fc: u64) -> bool {
+    &&& fc & FEATURE_CONTROL_LOCK != 0
+    &&& fc & FEATURE_CONTROL_VMX_OUTSIDE_SMX != 0
+}
+
+pub fn vmxon_allowed(fc: u64) -> (result: bool)
+    ensures
+        result == spec_vmxon_allowed(fc),
+{
+    fc & FEATURE_CONTROL_LOCK != 0 && fc & FEATURE_CONTROL_VMX_OUTSIDE_SMX != 0
+}
+
+/// 引理：固件未锁定或未开启 VMX 时 VMXON 被排除
+pub proof fn lemma_vmxon_gated_by_feature_control()
+    ensures
+        !spec_vmxon_allowed(0),
+        !spec_vmxon_allowed(FEATURE_CONTROL_VMX_OUTSIDE_SMX),
+        !spec_vmxon_allowed(FEATURE_CONTROL_LOCK),
+        spec_vmxon_allowed(FEATURE_CONTROL_LOCK | FEATURE_CONTROL_VMX_OUTSIDE_SMX),
+{
+    assert(FEATURE_CONTROL_VMX_OUTSIDE_SMX & FEATURE_CONTROL_LOCK == 0) by (compute_only);
+    assert(FEATURE_CONTROL_LOCK & FEATURE_CONTROL_VMX_OUTSIDE_SMX == 0) by (compute_only);
+    assert((FEATURE_CONTROL_LOCK | FEATURE_CONTROL_VMX_OUTSIDE_SMX) & FEATURE_CONTROL_LOCK != 0) by (compute_only);
+    assert((FEATURE_CONTROL_LOCK | FEATURE_CONTROL_VMX_OUTSIDE_SMX) & FEATURE_CONTROL_VMX_OUTSIDE_SMX != 0)
+        by (compute_only);
+}
+
+/// IA32_PAT 的复位值：PA0~PA7 = WB、WT、UC-、UC，重复两遍
+pub const PAT_DEFAULT: u64 = 0x0007_0406_0007_0406;
+
+/// 规范函数：PAT 的第 i 个内存类型字节
+pub open spec fn spec_pat_entry(pat: u64, i: u64) -> u64 {
+    (pat >> ((8 * i) as u64)) & 0xff
+}
+
+/// 规范函数：PAT 内存类型合法（0 = UC、1 = WC、4 = WT、5 = WP、6 = WB、7 = UC-；2、3 与 8~255 保留）
+pub open spec fn spec_pat_type_valid(ty: u64) -> bool {
+    ty == 0 || ty == 1 || ty == 4 || ty == 5 || ty == 6 || ty == 7
+}
+
+/// 规范函数：写入 IA32_PAT 的值合法（8 个字节都是合法内存类型）
+pub open spec fn spec_pat_valid(pat: u64) -> bool {
+    forall|i: u64| i < 8 ==> #[trigger] spec_pat_type_valid(spec_pat_entry(pat, i))
+}
+
+pub fn pat_valid(pat: u64) -> (result: bool)
+    ensures
+        result == spec_pat_valid(pat),
+{
+    let mut i: u64 = 0;
+    while i < 8
+        invariant
+            i <= 8,
+            forall|j: u64| j < i ==> #[trigger] spec_pat_type_valid(spec_pat_entry(pat, j)),
+        decreases 8 - i,
+    {
+        let ty = (pat >> (8 * i)) & 0xff;
+        if !(ty == 0 || ty == 1 || ty == 4 || ty == 5 || ty == 6 || ty == 7) {
+            assert(!spec_pat_type_valid(spec_pat_entry(pat, i)));
+            return false;
+        }
+        i = i + 1;
+    }
+    true
+}
+
+/// 引理：任一字节为保留内存类型的 PAT 写入非法
+pub proof fn lemma_pat_reserved_byte_rejected(pat: u64, i: u64)
+    requires
+        i < 8,
+        spec_pat_entry(pat, i) == 2 || spec_pat_entry(pat, i) == 3 || spec_pat_entry(pat, i) >= 8,
+    ensures
+        !spec_pat_valid(pat),
+{
+    assert(!spec_pat_type_valid(spec_pat_entry(pat, i)));
+}
+
+/// IA32_FMASK bits 63:32 保留
+pub const FMASK_RESERVED: u64 = 0xffff_ffff_0000_0000;
+
+/// 规范函数：LSTAR / CSTAR 是 SYSCALL 的目标 RIP，写入值必须是规范地址
+pub open spec fn is_syscall_target_msr(msr: u32) -> bool {
+    msr == IA32_LSTAR || msr == IA32_CSTAR
+}
+
+/// VMCS guest IA32_SYSENTER_CS 字段只有 32 位，高 32 位写入无处保存
+pub const SYSENTER_CS_RESERVED: u64 = 0xffff_ffff_0000_0000;
+
+/// 规范函数：写入 IA32_SYSENTER_CS 的值合法
+/// 
+/// 与 STAR 相同，硬件不检查其中的选择子；只有无处保存的高 32 位必须为 0
+pub open spec fn spec_sysenter_cs_valid(val: u64) -> bool {
+    val & SYSENTER_CS_RESERVED == 0
+}
+
+pub fn sysenter_cs_valid(val: u64) -> (result: bool)
+    ensures
+        result == spec_sysenter_cs_valid(val),
+{
+    val & SYSENTER_CS_RESERVED == 0
+}
+
+/// 规范函数：SYSENTER_ESP / SYSENTER_EIP（natural-width 字段，与 guest 是否处于 IA-32e 模式无关，
+/// 写入值必须是规范地址，否则 WRMSR #GP，VM entry 也会检查）
+pub open spec fn is_sysenter_target_msr(msr: u32) -> bool {
+    msr == IA32_SYSENTER_ESP || msr == IA32_SYSENTER_EIP
+}
+
+pub open spec fn is_x2apic_lvt_msr(msr: u32) -> bool {
+    IA32_X2APIC_LVT_TIMER <= msr <= IA32_X2APIC_LVT_ERROR
+}
+
+/// 规范函数：写 msr 可能改变另一个 MSR other 的读出值
+/// 
+/// LVT timer 的模式决定 IA32_TSC_DEADLINE 读出 deadline 还是 0；
+/// IA32_APIC_BASE 的模式决定 x2APIC 的 SVR / LVT 是否可访问
+pub open spec fn spec_msr_write_affects(msr: u32, other: u32) -> bool {
+    ||| msr == IA32_X2APIC_LVT_TIMER && other == IA32_TSC_DEADLINE
+    ||| msr == IA32_APIC_BASE && (other == IA32_X2APIC_SVR || is_x2apic_lvt_msr(other))
+}
+
+/// 引理：合法的 SYSENTER_CS 截断为 32 位字段后值不变，0 总是可以写入
+pub proof fn lemma_sysenter_cs_fits_field(val: u64)
+    requires
+        spec_sysenter_cs_valid(val),
+    ensures
+        (val as u32) as u64 == val,
+        spec_sysenter_cs_valid(0),
+{
+    assert(val & 0xffff_ffff_0000_0000 == 0 ==> (val as u32) as u64 == val) by (bit_vector);
+    assert(0u64 & SYSENTER_CS_RESERVED == 0) by (bit_vector);
+}
+
+/// 规范函数：host 的 IA32_EFER
+pub uninterp spec fn spec_host_efer() -> u64;
+
+/// 规范函数：IA32_VMX_PINBASED_CTLS 能力 MSR
+pub uninterp spec fn spec_vmx_pin_ctls_cap() -> u64;
+
+#[verifier::external_body]
+pub fn read_vmx_pin_ctls_cap() -> (result: u64)
+    ensures
+        result == spec_vmx_pin_ctls_cap(),
+{
+    0  // 硬件操作：rdmsr IA32_VMX_PINBASED_CTLS
+}
+
+/// 规范函数：IA32_VMX_PROCBASED_CTLS 能力 MSR
+pub uninterp spec fn spec_vmx_proc_ctls_cap() -> u64;
+
+#[verifier::external_body]
+pub fn read_vmx_proc_ctls_cap() -> (result: u64)
+    ensures
+        result == spec_vmx_proc_ctls_cap(),
+{
+    0  // 硬件操作：rdmsr IA32_VMX_PROCBASED_CTLS
+}
+
+/// 规范函数：IA32_VMX_EXIT_CTLS 能力 MSR
+pub uninterp spec fn spec_vmx_exit_ctls_cap() -> u64;
+
+#[verifier::external_body]
+pub fn read_vmx_exit_ctls_cap() -> (result: u64)
+    ensures
+        result == spec_vmx_exit_ctls_cap(),
+{
+    0  // 硬件操作：rdmsr IA32_VMX_EXIT_CTLS
+}
+
+/// 规范函数：IA32_VMX_ENTRY_CTLS 能力 MSR
+pub uninterp spec fn spec_vmx_entry_ctls_cap() -> u64;
+
+#[verifier::external_body]
+pub fn read_vmx_entry_ctls_cap() -> (result: u64)
+    ensures
+        result == spec_vmx_entry_ctls_cap(),
+{
+    0  // 硬件操作：rdmsr IA32_VMX_ENTRY_CTLS
+}
+
+/// 规范函数：IA32_VMX_PROCBASED_CTLS2 能力 MSR
+pub uninterp spec fn spec_vmx_proc_ctls2_cap() -> u64;
+
+#[verifier::external_body]
+pub fn read_vmx_proc_ctls2_cap() -> (result: u64)
+    ensures
+        result == spec_vmx_proc_ctls2_cap(),
+{
+    0  // 硬件操作：rdmsr IA32_VMX_PROCBASED_CTLS2
+}
+
+/// 规范函数：WRMSR 写入的值 EDX:EAX
+pub open spec fn spec_msr_value(rax: u64, rdx: u64) -> u64 {
+    ((rdx & 0xffff_ffff) << 32u64) | (rax & 0xffff_ffff)
+}
+
+pub fn msr_value(rax: u64, rdx: u64) -> (result: u64)
+    ensures
+        result == spec_msr_value(rax, rdx),
+{
+    ((rdx & 0xffff_ffff) << 32u64) | (rax & 0xffff_ffff)
+}
+
+/// 规范函数：FS/GS 段基址相关的 MSR（写入值必须是规范地址）
+pub open spec fn is_segment_base_msr(msr: u32) -> bool {
+    msr == IA32_FS_BASE || msr == IA32_GS_BASE || msr == IA32_KERNEL_GS_BASE
+}
+
+/// 规范函数：MSR 可以出现在 VM-entry MSR-load 区域中（SDM 26.4）
+/// 
+/// FS / GS base 只能经 guest-state 区域的段基址字段装入，x2APIC MSR 也不允许，
+/// 违反时 VM entry 以 MSR 装入失败退出
+pub open spec fn spec_msr_load_allowed(msr: u32) -> bool {
+    &&& msr != IA32_FS_BASE
+    &&& msr != IA32_GS_BASE
+    &&& !(0x800 <= msr <= 0x8ff)
+}
+
+/// 引理：guest 的 FS / GS base 不能经 MSR 自动装入列表往返
+/// 
+/// 它们由 VMCS 的 guest FS / GS base 字段（guest_state.fs.base / gs.base）在每次 exit 时保存、
+/// 每次 entry 时装入，不需要也不允许放进 MSR-load 区域
+pub proof fn lemma_fsgs_base_not_autoloaded()
+    ensures
+        !spec_msr_load_allowed(IA32_FS_BASE),
+        !spec_msr_load_allowed(IA32_GS_BASE),
+        !spec_msr_load_allowed(IA32_X2APIC_ICR),
+        spec_msr_load_allowed(IA32_KERNEL_GS_BASE),
+{
+}
+
+} // verus!
@@ -0,0 +1,1221 @@
+// Verus-verified version of x86_64/vmcs.rs
+// VMCS guest-state 区域的模型
+
+use vstd::prelude::*;
+use super::ept::*;
+use super::msr::*;
+
+verus! {
+
+/// guest 段寄存器（selector / base / limit / access rights）
+#[derive(Clone, Copy)]
+pub struct SegmentRegister {
+    pub selector: u16,
+    pub base: u64,
+    pub limit: u32,
+    pub access_rights: u32,
+}
+
+impl SegmentRegister {
+    pub fn new() -> (result: Self)
+        ensures
+            result.selector == 0,
+            result.base == 0,
+    {
+        SegmentRegister { selector: 0, base: 0, limit: 0xffff, access_rights: 0 }
+    }
+}
+
+/// guest 描述符表寄存器（GDTR / IDTR）
+#[derive(Clone, Copy)]
+pub struct DescriptorTable {
+    pub base: u64,
+    /// VMCS 中为 32 位字段，bits 31:16 必须为 0
+    pub limit: u32,
+}
+
+impl DescriptorTable {
+    /// 复位值：base 0，limit 0xffff
+    pub fn new() -> (result: Self)
+        ensures
+            result.is_valid(),
+    {
+        DescriptorTable { base: 0, limit: 0xffff }
+    }
+    
+    /// 规范函数：满足 VM entry 检查（SDM 26.3.1.3：base 规范，limit 不超过 16 位）
+    pub open spec fn is_valid(&self) -> bool {
+        &&& spec_is_canonical(self.base)
+        &&& self.limit <= 0xffff
+    }
+}
+
+/// 段 access rights：bit 16 为 unusable
+pub const SEG_AR_UNUSABLE: u32 = 1 << 16;
+/// 段 access rights：类型（bits 3:0）、S（bit 4）、P（bit 7）
+pub const SEG_AR_TYPE_MASK: u32 = 0xf;
+pub const SEG_AR_S: u32 = 1 << 4;
+pub const SEG_AR_P: u32 = 1 << 7;
+/// 段 access rights：L（bit 13），IA-32e 模式下的 64 位代码段
+pub const SEG_AR_L: u32 = 1 << 13;
+/// 段 access rights：DPL（bits 6:5），SS.DPL 即当前特权级
+pub const SEG_AR_DPL_SHIFT: u32 = 5;
+pub const SEG_AR_DPL_MASK: u32 = 3;
+/// 代码段类型的 conforming 位
+pub const SEG_TYPE_CONFORMING: u32 = 1 << 2;
+/// 系统段类型：LDT
+pub const SEG_TYPE_LDT: u32 = 2;
+/// 数据段类型：可读写、已访问（unrestricted guest 下 CS 可以是这种类型）
+pub const SEG_TYPE_DATA_RW_ACCESSED: u32 = 3;
+/// 段选择子的 RPL（bits 1:0）
+pub const SELECTOR_RPL: u16 = 3;
+
+/// 规范函数：段的 DPL
+pub open spec fn spec_seg_dpl(seg: SegmentRegister) -> u32 {
+    (seg.access_rights >> SEG_AR_DPL_SHIFT) & SEG_AR_DPL_MASK
+}
+
+pub fn seg_dpl(seg: &SegmentRegister) -> (result: u32)
+    ensures
+        result == spec_seg_dpl(*seg),
+{
+    (seg.access_rights >> SEG_AR_DPL_SHIFT) & SEG_AR_DPL_MASK
+}
+/// 复位后的 LDTR access rights（present，LDT）
+pub const LDTR_AR_RESET: u32 = SEG_AR_P | SEG_TYPE_LDT;
+
+/// 规范函数：LDTR 满足 VM entry 检查（SDM 26.3.1.2）
+/// 
+/// 不可用（null）的 LDTR 不做其余检查；可用时 selector 的 TI 位为 0，
+/// 类型为 LDT、S 为 0、P 为 1，base 规范
+pub open spec fn ldtr_valid(ldtr: SegmentRegister) -> bool {
+    ldtr.access_rights & SEG_AR_UNUSABLE != 0 || {
+        &&& ldtr.selector & 0x4 == 0
+        &&& ldtr.access_rights & SEG_AR_TYPE_MASK == SEG_TYPE_LDT
+        &&& ldtr.access_rights & SEG_AR_S == 0
+        &&& ldtr.access_rights & SEG_AR_P != 0
+        &&& spec_is_canonical(ldtr.base)
+    }
+}
+
+pub fn check_ldtr(ldtr: &SegmentRegister) -> (result: bool)
+    ensures
+        result == ldtr_valid(*ldtr),
+{
+    ldtr.access_rights & SEG_AR_UNUSABLE != 0 || (
+        ldtr.selector & 0x4 == 0
+        && ldtr.access_rights & SEG_AR_TYPE_MASK == SEG_TYPE_LDT
+        && ldtr.access_rights & SEG_AR_S == 0
+        && ldtr.access_rights & SEG_AR_P != 0
+        && is_canonical(ldtr.base)
+    )
+}
+
+/// guest 活动状态（VMCS guest activity state 字段）
+pub enum ActivityState {
+    Active,
+    Hlt,
+    Shutdown,
+    WaitForSipi,
+}
+
+/// guest 可中断性状态（VMCS guest interruptibility state 字段）
+#[derive(Clone, Copy)]
+pub struct Interruptibility {
+    pub blocking_by_sti: bool,
+    pub blocking_by_mov_ss: bool,
+    /// NMI 投递后到 IRET 之前阻塞后续 NMI（virtual NMIs 下为虚拟 NMI 阻塞）
+    pub blocking_by_nmi: bool,
+    /// SMM 中阻塞 SMI（模型不支持 SMM，VM entry 时必须为 0）
+    pub blocking_by_smi: bool,
+}
+
+impl Interruptibility {
+    pub fn new() -> (result: Self)
+        ensures
+            !result.blocking_by_sti,
+            !result.blocking_by_mov_ss,
+            !result.blocking_by_nmi,
+            !result.blocking_by_smi,
+    {
+        Interruptibility {
+            blocking_by_sti: false,
+            blocking_by_mov_ss: false,
+            blocking_by_nmi: false,
+            blocking_by_smi: false,
+        }
+    }
+}
+
+/// VMCS guest-state 区域在模型中的影子
+pub struct GuestState {
+    pub rip: u64,
+    pub rsp: u64,
+    pub rflags: u64,
+    pub activity: ActivityState,
+    pub interruptibility: Interruptibility,
+    pub cr0: u64,
+    /// CR0 read shadow：cr0_mask 覆盖的位（包括 PG）guest 读到的是这里的值
+    pub cr0_shadow: u64,
+    /// guest CR3（影子页表模式下为 hypervisor 的影子页表根）
+    pub cr3: u64,
+    /// guest CR4 字段（VMX operation 要求 VMXE 置位，由 host 保持）
+    pub cr4: u64,
+    /// CR4 read shadow：cr4_mask 覆盖的位（包括 VMXE）guest 读到的是这里的值
+    pub cr4_shadow: u64,
+    /// guest IA32_EFER 字段（load / save EFER 控制开启时由硬件使用）
+    pub efer: u64,
+    /// guest IA32_PAT 字段（load PAT on entry 时由硬件装入）
+    pub pat: u64,
+    /// PAE 分页下 CPU 从 CR3 缓存的 4 个 PDPTE
+    pub pdptes: [u64; 4],
+    
+    pub cs: SegmentRegister,
+    pub ss: SegmentRegister,
+    pub ds: SegmentRegister,
+    pub es: SegmentRegister,
+    pub fs: SegmentRegister,
+    pub gs: SegmentRegister,
+    pub ldtr: SegmentRegister,
+    pub gdtr: DescriptorTable,
+    pub idtr: DescriptorTable,
+    
+    /// SWAPGS 交换用的影子 GS base（不在 VMCS 中，由 hypervisor 保存）
+    pub kernel_gs_base: u64,
+    /// SYSCALL / SYSRET 使用的 STAR、LSTAR、CSTAR、FMASK（不在 VMCS 中，由 hypervisor 保存）
+    pub star: u64,
+    pub lstar: u64,
+    pub cstar: u64,
+    pub fmask: u64,
+    /// guest IA32_SYSENTER_CS（32 位字段）、ESP、EIP（natural-width 字段）
+    pub sysenter_cs: u32,
+    pub sysenter_esp: u64,
+    pub sysenter_eip: u64,
+    /// DR0-DR3 与 DR6（不在 VMCS 中，由 hypervisor 保存）
+    pub drs: [u64; 4],
+    pub dr6: u64,
+    /// guest DR7 字段
+    pub dr7: u64,
+    /// guest pending debug exceptions 字段的 BS 位：entry 后由硬件投递单步 #DB
+    pub pending_dbg_bs: bool,
+}
+
+impl GuestState {
+    pub fn new() -> (result: Self)
+        ensures
+            result.rip == 0,
+            result.rsp == 0,
+            result.activity is Active,
+            !result.interruptibility.blocking_by_nmi,
+            !result.interruptibility.blocking_by_smi,
+            result.cr0 == 0,
+            result.cr0_shadow == 0,
+            result.cr4 == CR4_VMXE,
+            result.cr4_shadow == 0,
+            result.efer == 0,
+            result.descriptor_tables_valid(),
+    {
+        let mut ldtr = SegmentRegister::new();
+        ldtr.access_rights = SEG_AR_UNUSABLE;
+        assert(SEG_AR_UNUSABLE & SEG_AR_UNUSABLE != 0) by (bit_vector);
+        GuestState {
+            rip: 0,
+            rsp: 0,
+            rflags: RFLAGS_RESERVED_1,
+            activity: ActivityState::Active,
+            interruptibility: Interruptibility::new(),
+            cr0: 0,
+            cr0_shadow: 0,
+            cr3: 0,
+            cr4: CR4_VMXE,
+            cr4_shadow: 0,
+            efer: 0,
+            pat: PAT_DEFAULT,
+            pdptes: [0; 4],
+            cs: SegmentRegister::new(),
+            ss: SegmentRegister::new(),
+            ds: SegmentRegister::new(),
+            es: SegmentRegister::new(),
+            fs: SegmentRegister::new(),
+            gs: SegmentRegister::new(),
+            ldtr,
+            gdtr: DescriptorTable::new(),
+            idtr: DescriptorTable::new(),
+            kernel_gs_base: 0,
+            star: 0,
+            lstar: 0,
+            cstar: 0,
+            fmask: 0,
+            sysenter_cs: 0,
+            sysenter_esp: 0,
+            sysenter_eip: 0,
+            drs: [0; 4],
+            dr6: DR6_FIXED_1,
+            dr7: DR7_FIXED_1,
+            pending_dbg_bs: false,
+        }
+    }
+}
+
+pub const CR0_PE: u64 = 1 << 0;
+pub const CR0_NW: u64 = 1 << 29;
+pub const CR0_CD: u64 = 1 << 30;
+pub const CR0_PG: u64 = 1 << 31;
+pub const CR4_DE: u64 = 1 << 3;
+pub const CR4_PAE: u64 = 1 << 5;
+pub const CR4_LA57: u64 = 1 << 12;
+pub const CR4_VMXE: u64 = 1 << 13;
+pub const CR4_PCIDE: u64 = 1 << 17;
+pub const CR4_SMEP: u64 = 1 << 20;
+pub const CR4_SMAP: u64 = 1 << 21;
+
+/// DR6.BS：单步引起的 #DB
+pub const DR6_BS: u64 = 1 << 14;
+/// DR6 / DR7 中读出恒为 1 的位
+pub const DR6_FIXED_1: u64 = 0xffff_0ff0;
+pub const DR7_FIXED_1: u64 = 1 << 10;
+/// DR6 / DR7 中读出恒为 0 的位（bits 63:32 另行检查）
+pub const DR6_FIXED_0: u64 = 1 << 12;
+pub const DR7_FIXED_0: u64 = 0xd800;
+/// MOV to DR6 / DR7 时必须为 0 的位，否则 #GP
+pub const DR_HIGH_RESERVED: u64 = 0xffff_ffff_0000_0000;
+
+/// 规范函数：MOV DR 实际访问的调试寄存器
+/// 
+/// CR4.DE = 0 时 DR4 / DR5 是 DR6 / DR7 的别名；CR4.DE = 1 时访问它们产生 #UD（返回 None）
+pub open spec fn spec_dr_alias(dr: u8, cr4: u64) -> Option<u8> {
+    if dr == 4 || dr == 5 {
+        if cr4 & CR4_DE != 0 { None } else { Some((dr + 2) as u8) }
+    } else {
+        Some(dr)
+    }
+}
+
+pub fn dr_alias(dr: u8, cr4: u64) -> (result: Option<u8>)
+    requires
+        dr < 8,
+    ensures
+        result == spec_dr_alias(dr, cr4),
+        result matches Some(i) ==> i < 8 && i != 4 && i != 5,
+{
+    if dr == 4 || dr == 5 {
+        if cr4 & CR4_DE != 0 { None } else { Some(dr + 2) }
+    } else {
+        Some(dr)
+    }
+}
+
+/// 规范函数：写入 DR6 / DR7 后寄存器中的值（固定位被强制）
+pub open spec fn spec_dr6_written(val: u64) -> u64 {
+    (val | DR6_FIXED_1) & !DR6_FIXED_0
+}
+
+pub open spec fn spec_dr7_written(val: u64) -> u64 {
+    (val | DR7_FIXED_1) & !DR7_FIXED_0
+}
+
+/// 引理：DR4 / DR5 只在 CR4.DE 清零时映射到 DR6 / DR7，其余编号不受 CR4.DE 影响
+pub proof fn lemma_dr_alias_depends_on_de(cr4: u64)
+    ensures
+        cr4 & CR4_DE == 0 ==> spec_dr_alias(4, cr4) == Some(6u8) && spec_dr_alias(5, cr4) == Some(7u8),
+        cr4 & CR4_DE != 0 ==> spec_dr_alias(4, cr4) is None && spec_dr_alias(5, cr4) is None,
+        forall|dr: u8| dr < 8 && dr != 4 && dr != 5 ==> #[trigger] spec_dr_alias(dr, cr4) == Some(dr),
+{
+}
+
+/// guest 的分页模式（SDM 4.1.1）
+pub enum PagingMode {
+    /// CR0.PG = 0
+    Disabled,
+    /// 32 位分页
+    Bits32,
+    Pae,
+    /// 4 级分页（EFER.LMA = 1）
+    Level4,
+}
+
+/// 规范函数：由 CR0 / CR4 / EFER 推出的分页模式
+pub open spec fn spec_paging_mode(cr0: u64, cr4: u64, efer: u64) -> PagingMode {
+    if cr0 & CR0_PG == 0 {
+        PagingMode::Disabled
+    } else if efer_lma(efer) {
+        PagingMode::Level4
+    } else if cr4 & CR4_PAE != 0 {
+        PagingMode::Pae
+    } else {
+        PagingMode::Bits32
+    }
+}
+
+/// 规范函数：guest CR4 与 CR0 / EFER 一致
+/// 
+/// - SMEP / SMAP 只在开启分页时使用
+/// - IA-32e 模式（EFER.LMA）下不能清除 PAE
+/// - PCIDE 只能在 IA-32e 模式下置位
+/// - VMX operation 中 VMXE 是 fixed-1 位（SDM 26.3.1.1），guest 经 read shadow 看到的是清零的值
+pub open spec fn spec_cr4_valid(cr4: u64, cr0: u64, efer: u64) -> bool {
+    &&& cr4 & (CR4_SMEP | CR4_SMAP) != 0 ==> cr0 & CR0_PG != 0
+    &&& efer_lma(efer) ==> cr4 & CR4_PAE != 0
+    &&& cr4 & CR4_PCIDE != 0 ==> efer_lma(efer)
+    &&& cr4 & CR4_VMXE != 0
+}
+
+pub fn cr4_valid(cr4: u64, cr0: u64, efer: u64) -> (result: bool)
+    ensures
+        result == spec_cr4_valid(cr4, cr0, efer),
+{
+    let lma = efer & EFER_LMA != 0;
+    (cr4 & (CR4_SMEP | CR4_SMAP) == 0 || cr0 & CR0_PG != 0)
+        && (!lma || cr4 & CR4_PAE != 0)
+        && (cr4 & CR4_PCIDE == 0 || lma)
+        && cr4 & CR4_VMXE != 0
+}
+
+/// 规范函数：guest 视角的 CR4（mask 覆盖的位取自 read shadow）
+pub open spec fn spec_guest_visible_cr4(cr4: u64, shadow: u64, mask: u64) -> u64 {
+    (cr4 & !mask) | (shadow & mask)
+}
+
+/// 规范函数：guest 的 MOV to CR4 写入值合法
+/// 
+/// 不支持嵌套虚拟化，guest 不能置位 VMXE；写入后真实 CR4 为 val | VMXE，read shadow 为 val
+pub open spec fn spec_cr4_write_valid(val: u64, cr0: u64, efer: u64) -> bool {
+    &&& val & CR4_VMXE == 0
+    &&& spec_cr4_valid(val | CR4_VMXE, cr0, efer)
+}
+
+pub fn cr4_write_valid(val: u64, cr0: u64, efer: u64) -> (result: bool)
+    ensures
+        result == spec_cr4_write_valid(val, cr0, efer),
+{
+    val & CR4_VMXE == 0 && cr4_valid(val | CR4_VMXE, cr0, efer)
+}
+
+/// 引理：CR4 写入之后真实 CR4 保持 VMXE，而 VMXE 归 host 所有时 guest 读回的正是写入值
+pub proof fn lemma_cr4_write_hides_vmxe(val: u64, mask: u64)
+    requires
+        val & CR4_VMXE == 0,
+        mask & CR4_VMXE != 0,
+    ensures
+        (val | CR4_VMXE) & CR4_VMXE != 0,
+        spec_guest_visible_cr4(val | CR4_VMXE, val, mask) == val,
+{
+    assert((val | 0x2000u64) & 0x2000u64 != 0) by (bit_vector);
+    assert(val & 0x2000u64 == 0 && mask & 0x2000u64 != 0
+        ==> ((val | 0x2000u64) & !mask) | (val & mask) == val) by (bit_vector);
+}
+
+/// 引理：IA-32e 模式下清除 CR4.PAE 的写入必然非法
+pub proof fn lemma_clear_pae_in_long_mode_rejected(cr4: u64, cr0: u64, efer: u64)
+    requires
+        efer_lma(efer),
+        cr4 & CR4_PAE == 0,
+    ensures
+        !spec_cr4_valid(cr4, cr0, efer),
+{
+}
+
+/// 规范函数：guest 的 MOV to CR0 写入值合法（否则 #GP(0)）
+/// 
+/// - bits 63:32 保留
+/// - 开启分页必须同时开启保护模式
+/// - NW 置位时 CD 必须置位
+/// - EFER.LME 置位时开启分页即进入 IA-32e 模式，要求 CR4.PAE
+pub open spec fn spec_cr0_write_valid(val: u64, cr4: u64, efer: u64) -> bool {
+    &&& val >> 32u64 == 0
+    &&& val & CR0_PG != 0 ==> val & CR0_PE != 0
+    &&& val & CR0_NW != 0 ==> val & CR0_CD != 0
+    &&& val & CR0_PG != 0 && efer_lme(efer) ==> cr4 & CR4_PAE != 0
+}
+
+pub fn cr0_write_valid(val: u64, cr4: u64, efer: u64) -> (result: bool)
+    ensures
+        result == spec_cr0_write_valid(val, cr4, efer),
+{
+    val >> 32u64 == 0
+        && (val & CR0_PG == 0 || val & CR0_PE != 0)
+        && (val & CR0_NW == 0 || val & CR0_CD != 0)
+        && (val & CR0_PG == 0 || efer & EFER_LME == 0 || cr4 & CR4_PAE != 0)
+}
+
+/// 规范函数：写入 CR0 之后的 EFER
+/// 
+/// EFER.LMA 由处理器维护为 LME && CR0.PG（SDM 9.8.5），切换 CR0.PG 即进入或离开 IA-32e 模式
+pub open spec fn spec_efer_after_cr0(efer: u64, cr0: u64) -> u64 {
+    if efer_lme(efer) && cr0 & CR0_PG != 0 {
+        efer | EFER_LMA
+    } else {
+        efer & !EFER_LMA
+    }
+}
+
+pub fn efer_after_cr0(efer: u64, cr0: u64) -> (result: u64)
+    ensures
+        result == spec_efer_after_cr0(efer, cr0),
+{
+    if efer & EFER_LME != 0 && cr0 & CR0_PG != 0 {
+        efer | EFER_LMA
+    } else {
+        efer & !EFER_LMA
+    }
+}
+
+/// 引理：写入 CR0 之后 LME 不变，LMA 恰为 LME && CR0.PG
+pub proof fn lemma_efer_after_cr0(efer: u64, cr0: u64)
+    ensures
+        efer_lme(spec_efer_after_cr0(efer, cr0)) == efer_lme(efer),
+        efer_lma(spec_efer_after_cr0(efer, cr0)) == (efer_lme(efer) && cr0 & CR0_PG != 0),
+{
+    assert((efer | EFER_LMA) & EFER_LMA != 0) by (bit_vector);
+    assert((efer & !EFER_LMA) & EFER_LMA == 0) by (bit_vector);
+    assert((efer | EFER_LMA) & EFER_LME == efer & EFER_LME) by (bit_vector);
+    assert((efer & !EFER_LMA) & EFER_LME == efer & EFER_LME) by (bit_vector);
+}
+
+/// CR3 bits 11:0：PCIDE = 1 时为 PCID，否则只有 PWT / PCD 有意义
+pub const CR3_PCID_MASK: u64 = 0xfff;
+/// PAE 分页下 CR3 bits 31:5 是 32 字节对齐的 PDPT 地址，只有 bits 4:0 不属于地址
+pub const CR3_PAE_LOW_MASK: u64 = 0x1f;
+pub const CR3_PWT: u64 = 1 << 3;
+pub const CR3_PCD: u64 = 1 << 4;
+/// MOV to CR3 的 bit 63：PCIDE = 1 时表示不刷新 TLB，不写入 CR3
+pub const CR3_NO_FLUSH: u64 = 1 << 63;
+
+/// 规范函数：MOV to CR3 的写入值合法（保留位为 0）
+/// 
+/// 页表根地址不能超过 MAXPHYADDR，不属于地址的低位随分页模式而定：
+/// - PCIDE = 1（只在 IA-32e 模式下）：bit 63 是刷新提示，bits 11:0 为任意 PCID
+/// - PAE 分页（pae）：bits 4:0 只允许 PWT / PCD，bits 11:5 属于 PDPT 地址
+/// - 其他模式：bits 11:0 只允许 PWT / PCD，bit 63 也属于保留位
+pub open spec fn spec_cr3_write_valid(val: u64, cr4: u64, pae: bool, max_phys: u64) -> bool {
+    if cr4 & CR4_PCIDE != 0 {
+        val & !(CR3_NO_FLUSH | CR3_PCID_MASK) < max_phys
+    } else if pae {
+        &&& val & !CR3_PAE_LOW_MASK < max_phys
+        &&& val & CR3_PAE_LOW_MASK & !(CR3_PWT | CR3_PCD) == 0
+    } else {
+        &&& val & !CR3_PCID_MASK < max_phys
+        &&& val & CR3_PCID_MASK & !(CR3_PWT | CR3_PCD) == 0
+    }
+}
+
+pub fn cr3_write_valid(val: u64, cr4: u64, pae: bool, max_phys: u64) -> (result: bool)
+    ensures
+        result == spec_cr3_write_valid(val, cr4, pae, max_phys),
+{
+    if cr4 & CR4_PCIDE != 0 {
+        val & !(CR3_NO_FLUSH | CR3_PCID_MASK) < max_phys
+    } else if pae {
+        val & !CR3_PAE_LOW_MASK < max_phys && val & CR3_PAE_LOW_MASK & !(CR3_PWT | CR3_PCD) == 0
+    } else {
+        val & !CR3_PCID_MASK < max_phys && val & CR3_PCID_MASK & !(CR3_PWT | CR3_PCD) == 0
+    }
+}
+
+/// 规范函数：MOV to CR3 之后 CR3 的值（刷新提示位不保留）
+pub open spec fn spec_cr3_loaded(val: u64, cr4: u64) -> u64 {
+    if cr4 & CR4_PCIDE != 0 { val & !CR3_NO_FLUSH } else { val }
+}
+
+pub fn cr3_loaded(val: u64, cr4: u64) -> (result: u64)
+    ensures
+        result == spec_cr3_loaded(val, cr4),
+{
+    if cr4 & CR4_PCIDE != 0 { val & !CR3_NO_FLUSH } else { val }
+}
+
+/// 引理：PCIDE = 0 且不是 PAE 分页时 bits 11:0 中 PWT / PCD 以外的位非零，写入必然非法
+pub proof fn lemma_pcid_without_pcide_rejected(val: u64, cr4: u64, max_phys: u64)
+    requires
+        cr4 & CR4_PCIDE == 0,
+        val & 0xfe7 != 0,
+    ensures
+        !spec_cr3_write_valid(val, cr4, false, max_phys),
+{
+    assert(CR3_PCID_MASK == 0xfff && CR3_PWT | CR3_PCD == 0x18) by (compute_only);
+    assert(val & 0xfe7 != 0 ==> val & 0xfffu64 & !0x18u64 != 0) by (bit_vector);
+}
+
+/// 引理：PAE 分页下 32 字节对齐（bits 11:5 可以非零）且低于 MAXPHYADDR 的 PDPT 地址总是合法
+pub proof fn lemma_pae_cr3_bits_11_5_accepted(val: u64, cr4: u64, max_phys: u64)
+    requires
+        cr4 & CR4_PCIDE == 0,
+        val & 0x1f == 0,
+        val < max_phys,
+    ensures
+        spec_cr3_write_valid(val, cr4, true, max_phys),
+        spec_cr3_write_valid(0x1000_0fe0, cr4, true, 0x1_0000_0000),
+{
+    assert(CR3_PAE_LOW_MASK == 0x1f && CR3_PWT | CR3_PCD == 0x18) by (compute_only);
+    assert(val & 0x1fu64 == 0 ==> val & !0x1fu64 == val && val & 0x1fu64 & !0x18u64 == 0) by (bit_vector);
+    assert(0x1000_0fe0u64 & !0x1fu64 == 0x1000_0fe0u64 && 0x1000_0fe0u64 & 0x1fu64 & !0x18u64 == 0) by (bit_vector);
+}
+
+pub const PDPTE_P: u64 = 1 << 0;
+/// PDPTE 的保留位：bits 2:1、8:5 与 bit 63
+pub const PDPTE_RESERVED: u64 = 0x1e6 | (1 << 63);
+pub const PDPTE_ADDR_MASK: u64 = 0x7fff_ffff_ffff_f000;
+
+/// 规范函数：PDPTE 合法（存在的表项保留位为 0，页目录地址低于 MAXPHYADDR）
+pub open spec fn spec_pdpte_valid(pdpte: u64, max_phys: u64) -> bool {
+    pdpte & PDPTE_P != 0 ==> {
+        &&& pdpte & PDPTE_RESERVED == 0
+        &&& pdpte & PDPTE_ADDR_MASK < max_phys
+    }
+}
+
+pub fn pdpte_valid(pdpte: u64, max_phys: u64) -> (result: bool)
+    ensures
+        result == spec_pdpte_valid(pdpte, max_phys),
+{
+    pdpte & PDPTE_P == 0 || (pdpte & PDPTE_RESERVED == 0 && pdpte & PDPTE_ADDR_MASK < max_phys)
+}
+
+/// 规范函数：4 个 PDPTE 都合法
+pub open spec fn spec_pdptes_valid(pdptes: [u64; 4], max_phys: u64) -> bool {
+    forall|i: int| 0 <= i < 4 ==> spec_pdpte_valid(pdptes[i], max_phys)
+}
+
+/// 规范函数：PAE 分页下 CR3 指向的 PDPT 中第 index 个表项（guest 物理地址 (CR3 & 0xffff_ffe0) + 8 * index）
+pub uninterp spec fn spec_guest_pdpte(cr3: u64, index: usize) -> u64;
+
+#[verifier::external_body]
+pub fn read_guest_pdpte(cr3: u64, index: usize) -> (result: u64)
+    requires
+        index < 4,
+    ensures
+        result == spec_guest_pdpte(cr3, index),
+{
+    0  // 硬件操作：读取 guest 物理内存中的 PDPT
+}
+
+/// 规范函数：从 CR3 装入的 4 个 PDPTE 都合法
+pub open spec fn spec_loaded_pdptes_valid(cr3: u64, max_phys: u64) -> bool {
+    forall|i: int| 0 <= i < 4 ==> spec_pdpte_valid(spec_guest_pdpte(cr3, i as usize), max_phys)
+}
+
+/// 按 CR3 装入 PDPTE，任一表项非法时返回 None
+pub fn load_pdptes(cr3: u64, max_phys: u64) -> (result: Option<[u64; 4]>)
+    ensures
+        result is Some <==> spec_loaded_pdptes_valid(cr3, max_phys),
+        result matches Some(pdptes) ==> forall|i: int| 0 <= i < 4
+            ==> pdptes[i] == spec_guest_pdpte(cr3, i as usize),
+{
+    let mut pdptes: [u64; 4] = [0; 4];
+    let mut i: usize = 0;
+    while i < 4
+        invariant
+            i <= 4,
+            forall|j: int| 0 <= j < i ==> pdptes[j] == spec_guest_pdpte(cr3, j as usize),
+            forall|j: int| 0 <= j < i ==> spec_pdpte_valid(spec_guest_pdpte(cr3, j as usize), max_phys),
+        decreases 4 - i,
+    {
+        let pdpte = read_guest_pdpte(cr3, i);
+        if !pdpte_valid(pdpte, max_phys) {
+            return None;
+        }
+        pdptes.set(i, pdpte);
+        i = i + 1;
+    }
+    Some(pdptes)
+}
+
+/// 引理：存在的 PDPTE 置位 bit 1（保留位）时非法
+pub proof fn lemma_pdpte_reserved_bit_rejected(pdpte: u64, max_phys: u64)
+    requires
+        pdpte & PDPTE_P != 0,
+        pdpte & 0x2 != 0,
+    ensures
+        !spec_pdpte_valid(pdpte, max_phys),
+{
+    assert(pdpte & 0x2 != 0 ==> pdpte & (0x1e6u64 | (1u64 << 63u64)) != 0) by (bit_vector);
+}
+
+/// 规范函数：hypervisor 当前的 CR0 / CR3 / CR4
+pub uninterp spec fn spec_current_cr0() -> u64;
+pub uninterp spec fn spec_current_cr3() -> u64;
+pub uninterp spec fn spec_current_cr4() -> u64;
+
+/// 读取 hypervisor 当前的 CR3（信任边界）
+#[verifier::external_body]
+pub fn current_cr3() -> (result: u64)
+    ensures
+        result == spec_current_cr3(),
+{
+    0  // 硬件操作：mov rax, cr3
+}
+
+/// 读取 hypervisor 当前的段选择子（信任边界），field 为对应的 host 选择子字段
+#[verifier::external_body]
+pub fn current_host_selector(field: VmcsField) -> (result: u64)
+    ensures
+        result <= 0xffff,
+{
+    0  // 硬件操作：mov ax, es / cs / ss / ds / fs / gs，或 str ax
+}
+
+/// 规范函数：host CR 满足 VMX 运行的固定位要求（CR0.PE、CR0.PG、CR4.VMXE 置位）
+pub open spec fn host_crs_fixed(cr0: u64, cr4: u64) -> bool {
+    &&& cr0 & CR0_PE != 0
+    &&& cr0 & CR0_PG != 0
+    &&& cr4 & CR4_VMXE != 0
+}
+
+/// 规范函数：实模式段基址等于 selector << 4
+pub open spec fn real_mode_segment(seg: SegmentRegister) -> bool {
+    seg.base == (seg.selector as u64) << 4u64
+}
+
+impl SegmentRegister {
+    /// 按实模式规则由 selector 计算段基址
+    pub fn load_real_mode_base(&mut self)
+        ensures
+            real_mode_segment(*self),
+            self.selector == old(self).selector,
+            self.limit == old(self).limit,
+            self.access_rights == old(self).access_rights,
+    {
+        self.base = (self.selector as u64) << 4u64;
+    }
+}
+
+impl GuestState {
+    /// 规范函数：guest 处于实模式（CR0.PE 清零）
+    pub open spec fn in_real_mode(&self) -> bool {
+        self.cr0 & CR0_PE == 0
+    }
+    
+    /// 规范函数：所有段寄存器都满足实模式基址规则
+    pub open spec fn real_mode_segments(&self) -> bool {
+        &&& real_mode_segment(self.cs)
+        &&& real_mode_segment(self.ss)
+        &&& real_mode_segment(self.ds)
+        &&& real_mode_segment(self.es)
+        &&& real_mode_segment(self.fs)
+        &&& real_mode_segment(self.gs)
+    }
+    
+    /// 按实模式规则设置全部段基址
+    pub fn load_real_mode_segments(&mut self)
+        ensures
+            self.real_mode_segments(),
+            self.rip == old(self).rip,
+            self.rsp == old(self).rsp,
+            self.cr0 == old(self).cr0,
+            self.efer == old(self).efer,
+            self.activity == old(self).activity,
+            self.interruptibility == old(self).interruptibility,
+    {
+        self.cs.load_real_mode_base();
+        self.ss.load_real_mode_base();
+        self.ds.load_real_mode_base();
+        self.es.load_real_mode_base();
+        self.fs.load_real_mode_base();
+        self.gs.load_real_mode_base();
+    }
+}
+
+impl GuestState {
+    /// 规范函数：GDTR / IDTR / LDTR 满足 VM entry 检查
+    pub open spec fn descriptor_tables_valid(&self) -> bool {
+        &&& self.gdtr.is_valid()
+        &&& self.idtr.is_valid()
+        &&& ldtr_valid(self.ldtr)
+    }
+    
+    pub fn check_descriptor_tables(&self) -> (result: bool)
+        ensures
+            result == self.descriptor_tables_valid(),
+    {
+        is_canonical(self.gdtr.base) && self.gdtr.limit <= 0xffff
+            && is_canonical(self.idtr.base) && self.idtr.limit <= 0xffff
+            && check_ldtr(&self.ldtr)
+    }
+}
+
+impl GuestState {
+    /// 规范函数：guest 当前特权级，即 SS 的 DPL（VMX 从 guest SS access rights 取 CPL，SDM 27.3.1.5）
+    /// 
+    /// 实模式下 SS.DPL 为 0，virtual-8086 模式下为 3
+    pub open spec fn spec_cpl(&self) -> u8 {
+        spec_seg_dpl(self.ss) as u8
+    }
+    
+    pub fn cpl(&self) -> (result: u8)
+        ensures
+            result == self.spec_cpl(),
+    {
+        seg_dpl(&self.ss) as u8
+    }
+    
+    /// 规范函数：CS / SS 的 RPL、DPL 满足 VM entry 检查（SDM 26.3.1.2）
+    /// 
+    /// - virtual-8086 模式：CS、SS 的 DPL 都为 3，不检查 RPL
+    /// - 未开启 unrestricted guest：SS.RPL 等于 CS.RPL，SS.DPL 等于 SS.RPL
+    /// - 实模式或 CS 为可读写数据段（type 3，只在 unrestricted guest 下出现）：SS.DPL 为 0，CS.DPL 为 0
+    /// - CS 为非 conforming 代码段时 CS.DPL 等于 SS.DPL，conforming 代码段时不高于 SS.DPL
+    pub open spec fn privilege_consistent(&self, unrestricted: bool) -> bool {
+        let ss_dpl = spec_seg_dpl(self.ss);
+        let cs_dpl = spec_seg_dpl(self.cs);
+        let cs_data = self.cs.access_rights & SEG_AR_TYPE_MASK == SEG_TYPE_DATA_RW_ACCESSED;
+        if self.rflags & RFLAGS_VM != 0 {
+            &&& ss_dpl == 3
+            &&& cs_dpl == 3
+        } else {
+            &&& !unrestricted ==> (self.ss.selector & SELECTOR_RPL) == (self.cs.selector & SELECTOR_RPL)
+            &&& !unrestricted ==> ss_dpl == (self.ss.selector & SELECTOR_RPL) as u32
+            &&& (self.in_real_mode() || cs_data) ==> ss_dpl == 0
+            &&& cs_data ==> cs_dpl == 0
+            &&& !cs_data && self.cs.access_rights & SEG_TYPE_CONFORMING != 0 ==> cs_dpl <= ss_dpl
+            &&& !cs_data && self.cs.access_rights & SEG_TYPE_CONFORMING == 0 ==> cs_dpl == ss_dpl
+        }
+    }
+    
+    pub fn check_privilege(&self, unrestricted: bool) -> (result: bool)
+        ensures
+            result == self.privilege_consistent(unrestricted),
+    {
+        let ss_dpl = seg_dpl(&self.ss);
+        let cs_dpl = seg_dpl(&self.cs);
+        if self.rflags & RFLAGS_VM != 0 {
+            return ss_dpl == 3 && cs_dpl == 3;
+        }
+        let cs_data = self.cs.access_rights & SEG_AR_TYPE_MASK == SEG_TYPE_DATA_RW_ACCESSED;
+        let ss_rpl = self.ss.selector & SELECTOR_RPL;
+        let rpl_ok = unrestricted || (ss_rpl == self.cs.selector & SELECTOR_RPL && ss_dpl == ss_rpl as u32);
+        let ss_ok = !(self.cr0 & CR0_PE == 0 || cs_data) || ss_dpl == 0;
+        let cs_ok = if cs_data {
+            cs_dpl == 0
+        } else if self.cs.access_rights & SEG_TYPE_CONFORMING != 0 {
+            cs_dpl <= ss_dpl
+        } else {
+            cs_dpl == ss_dpl
+        };
+        rpl_ok && ss_ok && cs_ok
+    }
+}
+
+/// RFLAGS.TF：单步
+pub const RFLAGS_TF: u64 = 1 << 8;
+/// RFLAGS.IF：可屏蔽中断使能
+pub const RFLAGS_IF: u64 = 1 << 9;
+/// RFLAGS.VM：virtual-8086 模式
+pub const RFLAGS_VM: u64 = 1 << 17;
+/// RFLAGS 的 bit 1 恒为 1
+pub const RFLAGS_RESERVED_1: u64 = 1 << 1;
+
+impl GuestState {
+    /// 规范函数：guest 当前可接收可屏蔽中断（IF 置位且没有 STI / MOV SS 中断影子）
+    pub open spec fn interruptible(&self) -> bool {
+        &&& self.rflags & RFLAGS_IF != 0
+        &&& !self.interruptibility.blocking_by_sti
+        &&& !self.interruptibility.blocking_by_mov_ss
+    }
+}
+
+/// 规范函数：guest 入口地址非零且为规范地址（入口 RIP 不要求对齐）
+pub open spec fn is_valid_entry(entry: u64) -> bool {
+    &&& entry != 0
+    &&& spec_is_canonical(entry)
+}
+
+/// 规范函数：guest RSP 满足 x86_64 ABI 的 16 字节对齐
+pub open spec fn guest_rsp_aligned(rsp: u64) -> bool {
+    rsp % 16 == 0
+}
+
+/// 复位后的 CS selector / base 与 IP
+/// 
+/// 硬件复位时 CS.base 特殊地取 0xffff_0000；模型中实模式段基址恒为 selector << 4（real_mode_segments），
+/// 因此 CS.base 取 0xf_0000，首条指令位于复位向量在 1MB 以下的别名 0xf_fff0
+pub const RESET_CS_SELECTOR: u16 = 0xf000;
+pub const RESET_CS_BASE: u64 = 0xf_0000;
+pub const RESET_RIP: u64 = 0xfff0;
+/// 复位后的 CR0（CD | NW | ET）
+pub const RESET_CR0: u64 = 0x6000_0010;
+
+impl GuestState {
+    /// INIT/RESET 后的 guest 状态（实模式，从复位向量开始执行）
+    pub fn reset() -> (result: Self)
+        ensures
+            result.rip == RESET_RIP,
+            result.rsp == 0,
+            result.activity is Active,
+            !result.interruptibility.blocking_by_nmi,
+            !result.interruptibility.blocking_by_smi,
+            result.cr0 == RESET_CR0,
+            result.cr0_shadow == RESET_CR0,
+            result.cr4 == CR4_VMXE,
+            result.cr4_shadow == 0,
+            result.efer == 0,
+            result.pat == PAT_DEFAULT,
+            result.dr6 == DR6_FIXED_1,
+            result.dr7 == DR7_FIXED_1,
+            result.in_real_mode(),
+            result.cs.selector == RESET_CS_SELECTOR,
+            result.cs.base == RESET_CS_BASE,
+            result.real_mode_segments(),
+            result.descriptor_tables_valid(),
+    {
+        let mut cs = SegmentRegister::new();
+        cs.selector = RESET_CS_SELECTOR;
+        cs.base = RESET_CS_BASE;
+        let mut ldtr = SegmentRegister::new();
+        ldtr.access_rights = LDTR_AR_RESET;
+        let result = GuestState {
+            rip: RESET_RIP,
+            rsp: 0,
+            rflags: RFLAGS_RESERVED_1,
+            activity: ActivityState::Active,
+            interruptibility: Interruptibility::new(),
+            cr0: RESET_CR0,
+            cr0_shadow: RESET_CR0,
+            cr3: 0,
+            cr4: CR4_VMXE,
+            cr4_shadow: 0,
+            efer: 0,
+            pat: PAT_DEFAULT,
+            pdptes: [0; 4],
+            cs,
+            ss: SegmentRegister::new(),
+            ds: SegmentRegister::new(),
+            es: SegmentRegister::new(),
+            fs: SegmentRegister::new(),
+            gs: SegmentRegister::new(),
+            ldtr,
+            gdtr: DescriptorTable::new(),
+            idtr: DescriptorTable::new(),
+            kernel_gs_base: 0,
+            star: 0,
+            lstar: 0,
+            cstar: 0,
+            fmask: 0,
+            sysenter_cs: 0,
+            sysenter_esp: 0,
+            sysenter_eip: 0,
+            drs: [0; 4],
+            dr6: DR6_FIXED_1,
+            dr7: DR7_FIXED_1,
+            pending_dbg_bs: false,
+        };
+        assert(RESET_CR0 & CR0_PE == 0) by (bit_vector);
+        assert(RESET_CS_BASE == (RESET_CS_SELECTOR as u64) << 4u64) by (compute_only);
+        assert((0u16 as u64) << 4u64 == 0) by (compute_only);
+        assert(LDTR_AR_RESET & SEG_AR_TYPE_MASK == SEG_TYPE_LDT) by (bit_vector);
+        assert(LDTR_AR_RESET & SEG_AR_S == 0) by (bit_vector);
+        assert(LDTR_AR_RESET & SEG_AR_P != 0) by (bit_vector);
+        assert(0u16 & 0x4 == 0) by (bit_vector);
+        result
+    }
+}
+
+/// VMCS 字段宽度（字段编码 bits 14:13）
+pub enum FieldWidth {
+    Bits16,
+    Bits64,
+    Bits32,
+    /// natural width（64 位 host 上为 64 位）
+    Natural,
+}
+
+/// 规范函数：由字段编码得到宽度
+pub open spec fn field_width(enc: u32) -> FieldWidth {
+    let w = (enc >> 13u32) & 0x3;
+    if w == 0 {
+        FieldWidth::Bits16
+    } else if w == 1 {
+        FieldWidth::Bits64
+    } else if w == 2 {
+        FieldWidth::Bits32
+    } else {
+        FieldWidth::Natural
+    }
+}
+
+/// 规范函数：值能放进该宽度的字段
+pub open spec fn fits_width(val: u64, width: FieldWidth) -> bool {
+    match width {
+        FieldWidth::Bits16 => val <= 0xffff,
+        FieldWidth::Bits32 => val <= 0xffff_ffff,
+        FieldWidth::Bits64 | FieldWidth::Natural => true,
+    }
+}
+
+/// 模型中使用的 VMCS 字段（SDM Appendix B）
+#[derive(Clone, Copy)]
+pub enum VmcsField {
+    GuestCsSelector,
+    VmcsLinkPointer,
+    GuestIa32Efer,
+    EptPointer,
+    PinBasedCtls,
+    ProcBasedCtls,
+    ExitCtls,
+    EntryCtls,
+    EntryIntrInfo,
+    TprThreshold,
+    SecondaryCtls,
+    VmInstructionError,
+    ExitReason,
+    ExitInstrLen,
+    ExitInstrInfo,
+    GuestInterruptibility,
+    GuestActivityState,
+    GuestCr0,
+    GuestCr3,
+    GuestCr4,
+    GuestRsp,
+    GuestRip,
+    HostEsSelector,
+    HostCsSelector,
+    HostSsSelector,
+    HostDsSelector,
+    HostFsSelector,
+    HostGsSelector,
+    HostTrSelector,
+    HostCr0,
+    HostCr3,
+    HostCr4,
+    HostRsp,
+    HostRip,
+}
+
+impl VmcsField {
+    /// 规范函数：字段编码
+    pub open spec fn spec_encoding(self) -> u32 {
+        match self {
+            VmcsField::GuestCsSelector => 0x0802,
+            VmcsField::VmcsLinkPointer => 0x2800,
+            VmcsField::GuestIa32Efer => 0x2806,
+            VmcsField::EptPointer => 0x201a,
+            VmcsField::PinBasedCtls => 0x4000,
+            VmcsField::ProcBasedCtls => 0x4002,
+            VmcsField::ExitCtls => 0x400c,
+            VmcsField::EntryCtls => 0x4012,
+            VmcsField::EntryIntrInfo => 0x4016,
+            VmcsField::TprThreshold => 0x401c,
+            VmcsField::SecondaryCtls => 0x401e,
+            VmcsField::VmInstructionError => 0x4400,
+            VmcsField::ExitReason => 0x4402,
+            VmcsField::ExitInstrLen => 0x440c,
+            VmcsField::ExitInstrInfo => 0x440e,
+            VmcsField::GuestInterruptibility => 0x4824,
+            VmcsField::GuestActivityState => 0x4826,
+            VmcsField::GuestCr0 => 0x6800,
+            VmcsField::GuestCr3 => 0x6802,
+            VmcsField::GuestCr4 => 0x6804,
+            VmcsField::GuestRsp => 0x681c,
+            VmcsField::GuestRip => 0x681e,
+            VmcsField::HostEsSelector => 0x0c00,
+            VmcsField::HostCsSelector => 0x0c02,
+            VmcsField::HostSsSelector => 0x0c04,
+            VmcsField::HostDsSelector => 0x0c06,
+            VmcsField::HostFsSelector => 0x0c08,
+            VmcsField::HostGsSelector => 0x0c0a,
+            VmcsField::HostTrSelector => 0x0c0c,
+            VmcsField::HostCr0 => 0x6c00,
+            VmcsField::HostCr3 => 0x6c02,
+            VmcsField::HostCr4 => 0x6c04,
+            VmcsField::HostRsp => 0x6c14,
+            VmcsField::HostRip => 0x6c16,
+        }
+    }
+    
+    pub fn encoding(&self) -> (result: u32)
+        ensures
+            result == self.spec_encoding(),
+    {
+        match *self {
+            VmcsField::GuestCsSelector => 0x0802,
+            VmcsField::VmcsLinkPointer => 0x2800,
+            VmcsField::GuestIa32Efer => 0x2806,
+            VmcsField::EptPointer => 0x201a,
+            VmcsField::PinBasedCtls => 0x4000,
+            VmcsField::ProcBasedCtls => 0x4002,
+            VmcsField::ExitCtls => 0x400c,
+            VmcsField::EntryCtls => 0x4012,
+            VmcsField::EntryIntrInfo => 0x4016,
+            VmcsField::TprThreshold => 0x401c,
+            VmcsField::SecondaryCtls => 0x401e,
+            VmcsField::VmInstructionError => 0x4400,
+            VmcsField::ExitReason => 0x4402,
+            VmcsField::ExitInstrLen => 0x440c,
+            VmcsField::ExitInstrInfo => 0x440e,
+            VmcsField::GuestInterruptibility => 0x4824,
+            VmcsField::GuestActivityState => 0x4826,
+            VmcsField::GuestCr0 => 0x6800,
+            VmcsField::GuestCr3 => 0x6802,
+            VmcsField::GuestCr4 => 0x6804,
+            VmcsField::GuestRsp => 0x681c,
+            VmcsField::GuestRip => 0x681e,
+            VmcsField::HostEsSelector => 0x0c00,
+            VmcsField::HostCsSelector => 0x0c02,
+            VmcsField::HostSsSelector => 0x0c04,
+            VmcsField::HostDsSelector => 0x0c06,
+            VmcsField::HostFsSelector => 0x0c08,
+            VmcsField::HostGsSelector => 0x0c0a,
+            VmcsField::HostTrSelector => 0x0c0c,
+            VmcsField::HostCr0 => 0x6c00,
+            VmcsField::HostCr3 => 0x6c02,
+            VmcsField::HostCr4 => 0x6c04,
+            VmcsField::HostRsp => 0x6c14,
+            VmcsField::HostRip => 0x6c16,
+        }
+    }
+    
+    /// 规范函数：字段的预期宽度（与 SDM 中的字段类型一致）
+    pub open spec fn spec_expected_width(self) -> FieldWidth {
+        match self {
+            VmcsField::GuestCsSelector => FieldWidth::Bits16,
+            VmcsField::VmcsLinkPointer => FieldWidth::Bits64,
+            VmcsField::GuestIa32Efer => FieldWidth::Bits64,
+            VmcsField::EptPointer => FieldWidth::Bits64,
+            VmcsField::PinBasedCtls => FieldWidth::Bits32,
+            VmcsField::ProcBasedCtls => FieldWidth::Bits32,
+            VmcsField::ExitCtls => FieldWidth::Bits32,
+            VmcsField::EntryCtls => FieldWidth::Bits32,
+            VmcsField::EntryIntrInfo => FieldWidth::Bits32,
+            VmcsField::TprThreshold => FieldWidth::Bits32,
+            VmcsField::SecondaryCtls => FieldWidth::Bits32,
+            VmcsField::VmInstructionError => FieldWidth::Bits32,
+            VmcsField::ExitReason => FieldWidth::Bits32,
+            VmcsField::ExitInstrLen => FieldWidth::Bits32,
+            VmcsField::ExitInstrInfo => FieldWidth::Bits32,
+            VmcsField::GuestInterruptibility => FieldWidth::Bits32,
+            VmcsField::GuestActivityState => FieldWidth::Bits32,
+            VmcsField::GuestCr0 => FieldWidth::Natural,
+            VmcsField::GuestCr3 => FieldWidth::Natural,
+            VmcsField::GuestCr4 => FieldWidth::Natural,
+            VmcsField::GuestRsp => FieldWidth::Natural,
+            VmcsField::GuestRip => FieldWidth::Natural,
+            VmcsField::HostEsSelector => FieldWidth::Bits16,
+            VmcsField::HostCsSelector => FieldWidth::Bits16,
+            VmcsField::HostSsSelector => FieldWidth::Bits16,
+            VmcsField::HostDsSelector => FieldWidth::Bits16,
+            VmcsField::HostFsSelector => FieldWidth::Bits16,
+            VmcsField::HostGsSelector => FieldWidth::Bits16,
+            VmcsField::HostTrSelector => FieldWidth::Bits16,
+            VmcsField::HostCr0 => FieldWidth::Natural,
+            VmcsField::HostCr3 => FieldWidth::Natural,
+            VmcsField::HostCr4 => FieldWidth::Natural,
+            VmcsField::HostRsp => FieldWidth::Natural,
+            VmcsField::HostRip => FieldWidth::Natural,
+        }
+    }
+}
+
+/// 引理：每个字段编码的 bits 14:13 与预期宽度一致
+pub proof fn lemma_field_widths(f: VmcsField)
+    ensures
+        field_width(f.spec_encoding()) == f.spec_expected_width(),
+{
+    match f {
+        VmcsField::GuestCsSelector => assert(field_width(0x0802) == FieldWidth::Bits16) by (compute_only),
+        VmcsField::VmcsLinkPointer => assert(field_width(0x2800) == FieldWidth::Bits64) by (compute_only),
+        VmcsField::GuestIa32Efer => assert(field_width(0x2806) == FieldWidth::Bits64) by (compute_only),
+        VmcsField::EptPointer => assert(field_width(0x201a) == FieldWidth::Bits64) by (compute_only),
+        VmcsField::PinBasedCtls => assert(field_width(0x4000) == FieldWidth::Bits32) by (compute_only),
+        VmcsField::ProcBasedCtls => assert(field_width(0x4002) == FieldWidth::Bits32) by (compute_only),
+        VmcsField::ExitCtls => assert(field_width(0x400c) == FieldWidth::Bits32) by (compute_only),
+        VmcsField::EntryCtls => assert(field_width(0x4012) == FieldWidth::Bits32) by (compute_only),
+        VmcsField::EntryIntrInfo => assert(field_width(0x4016) == FieldWidth::Bits32) by (compute_only),
+        VmcsField::TprThreshold => assert(field_width(0x401c) == FieldWidth::Bits32) by (compute_only),
+        VmcsField::SecondaryCtls => assert(field_width(0x401e) == FieldWidth::Bits32) by (compute_only),
+        VmcsField::VmInstructionError => assert(field_width(0x4400) == FieldWidth::Bits32) by (compute_only),
+        VmcsField::ExitReason => assert(field_width(0x4402) == FieldWidth::Bits32) by (compute_only),
+        VmcsField::ExitInstrLen => assert(field_width(0x440c) == FieldWidth::Bits32) by (compute_only),
+        VmcsField::ExitInstrInfo => assert(field_width(0x440e) == FieldWidth::Bits32) by (compute_only),
+        VmcsField::GuestInterruptibility => assert(field_width(0x4824) == FieldWidth::Bits32) by (compute_only),
+        VmcsField::GuestActivityState => assert(field_width(0x4826) == FieldWidth::Bits32) by (compute_only),
+        VmcsField::GuestCr0 => assert(field_width(0x6800) == FieldWidth::Natural) by (compute_only),
+        VmcsField::GuestCr3 => assert(field_width(0x6802) == FieldWidth::Natural) by (compute_only),
+        VmcsField::GuestCr4 => assert(field_width(0x6804) == FieldWidth::Natural) by (compute_only),
+        VmcsField::GuestRsp => assert(field_width(0x681c) == FieldWidth::Natural) by (compute_only),
+        VmcsField::GuestRip => assert(field_width(0x681e) == FieldWidth::Natural) by (compute_only),
+        VmcsField::HostEsSelector => assert(field_width(0x0c00) == FieldWidth::Bits16) by (compute_only),
+        VmcsField::HostCsSelector => assert(field_width(0x0c02) == FieldWidth::Bits16) by (compute_only),
+        VmcsField::HostSsSelector => assert(field_width(0x0c04) == FieldWidth::Bits16) by (compute_only),
+        VmcsField::HostDsSelector => assert(field_width(0x0c06) == FieldWidth::Bits16) by (compute_only),
+        VmcsField::HostFsSelector => assert(field_width(0x0c08) == FieldWidth::Bits16) by (compute_only),
+        VmcsField::HostGsSelector => assert(field_width(0x0c0a) == FieldWidth::Bits16) by (compute_only),
+        VmcsField::HostTrSelector => assert(field_width(0x0c0c) == FieldWidth::Bits16) by (compute_only),
+        VmcsField::HostCr0 => assert(field_width(0x6c00) == FieldWidth::Natural) by (compute_only),
+        VmcsField::HostCr3 => assert(field_width(0x6c02) == FieldWidth::Natural) by (compute_only),
+        VmcsField::HostCr4 => assert(field_width(0x6c04) == FieldWidth::Natural) by (compute_only),
+        VmcsField::HostRsp => assert(field_width(0x6c14) == FieldWidth::Natural) by (compute_only),
+        VmcsField::HostRip => assert(field_width(0x6c16) == FieldWidth::Natural) by (compute_only),
+    }
+}
+
+/// 规范函数：VM entry 前必须写过的字段编码
+/// 
+/// guest RIP/RSP/CR0/CR3/CR4、host RIP/RSP/CR 与段选择子、
+/// pin / primary / secondary 执行控制，以及 exit / entry 控制
+pub open spec fn required_fields() -> Set<u32> {
+    set![
+        VmcsField::GuestRip.spec_encoding(),
+        VmcsField::GuestRsp.spec_encoding(),
+        VmcsField::GuestCr0.spec_encoding(),
+        VmcsField::GuestCr3.spec_encoding(),
+        VmcsField::GuestCr4.spec_encoding(),
+        VmcsField::HostRip.spec_encoding(),
+        VmcsField::HostRsp.spec_encoding(),
+        VmcsField::HostCr0.spec_encoding(),
+        VmcsField::HostCr3.spec_encoding(),
+        VmcsField::HostCr4.spec_encoding(),
+        VmcsField::HostEsSelector.spec_encoding(),
+        VmcsField::HostCsSelector.spec_encoding(),
+        VmcsField::HostSsSelector.spec_encoding(),
+        VmcsField::HostDsSelector.spec_encoding(),
+        VmcsField::HostFsSelector.spec_encoding(),
+        VmcsField::HostGsSelector.spec_encoding(),
+        VmcsField::HostTrSelector.spec_encoding(),
+        VmcsField::PinBasedCtls.spec_encoding(),
+        VmcsField::ProcBasedCtls.spec_encoding(),
+        VmcsField::SecondaryCtls.spec_encoding(),
+        VmcsField::ExitCtls.spec_encoding(),
+        VmcsField::EntryCtls.spec_encoding(),
+    ]
+}
+
+/// 引理：漏写 host RIP 时写入集合不可能覆盖 required_fields
+pub proof fn lemma_missing_host_rip_incomplete(written: Set<u32>)
+    requires
+        !written.contains(VmcsField::HostRip.spec_encoding()),
+    ensures
+        !required_fields().subset_of(written),
+{
+    assert(required_fields().contains(VmcsField::HostRip.spec_encoding()));
+}
+
+/// 规范函数：VMCS 字段的当前值
+pub uninterp spec fn spec_vmcs_field(field: VmcsField) -> u64;
+
+/// VMREAD（信任边界）
+#[verifier::external_body]
+pub fn vmcs_read_field(field: VmcsField) -> (result: u64)
+    ensures
+        result == spec_vmcs_field(field),
+        fits_width(result, field_width(field.spec_encoding())),
+{
+    0  // 硬件操作：VMREAD field.encoding()
+}
+
+/// VMWRITE（信任边界）：写入值必须放得进字段宽度
+#[verifier::external_body]
+pub fn vmcs_write_field(field: VmcsField, val: u64)
+    requires
+        fits_width(val, field_width(field.spec_encoding())),
+{
+    // 硬件操作：VMWRITE field.encoding(), val
+}
+
+/// 写 VMCS guest RIP 字段
+pub fn vmcs_write_guest_rip(rip: u64) {
+    proof {
+        lemma_field_widths(VmcsField::GuestRip);
+    }
+    vmcs_write_field(VmcsField::GuestRip, rip);
+}
+
+} // verus!
@@ -0,0 +1,2114 @@
+// Verus-verified version of x86_64/vmx.rs
+// VMX 相关的数据模型：VM Exit 原因等
+
+use vstd::prelude::*;
+use super::apic::VectorBitmap;
+use super::msr::*;
+use super::vmcs::CR4_VMXE;
+
+verus! {
+
+/// VM Exit 基本原因（SDM Appendix C）
+pub enum ExitReason {
+    ExceptionNmi,
+    ExternalInterrupt,
+    TripleFault,
+    InitSignal,
+    StartupIpi,
+    IoSmi,
+    OtherSmi,
+    InterruptWindow,
+    NmiWindow,
+    TaskSwitch,
+    Cpuid,
+    Getsec,
+    Hlt,
+    Invd,
+    Invlpg,
+    Rdpmc,
+    Rdtsc,
+    Rsm,
+    Vmcall,
+    Vmclear,
+    Vmlaunch,
+    Vmptrld,
+    Vmptrst,
+    Vmread,
+    Vmresume,
+    Vmwrite,
+    Vmxoff,
+    Vmxon,
+    CrAccess,
+    DrAccess,
+    IoInstruction,
+    MsrRead,
+    MsrWrite,
+    InvalidGuestState,
+    MsrLoadFail,
+    Mwait,
+    MonitorTrapFlag,
+    Monitor,
+    Pause,
+    MceDuringEntry,
+    TprBelowThreshold,
+    ApicAccess,
+    VirtualizedEoi,
+    GdtrIdtrAccess,
+    LdtrTrAccess,
+    EptViolation,
+    EptMisconfig,
+    Invept,
+    Rdtscp,
+    PreemptionTimer,
+    Invvpid,
+    Wbinvd,
+    Xsetbv,
+    ApicWrite,
+    Rdrand,
+    Invpcid,
+    Vmfunc,
+    Encls,
+    Rdseed,
+    PmlFull,
+    Xsaves,
+    Xrstors,
+}
+
+/// 基本退出原因编号的个数（0..=64）
+pub const NUM_EXIT_REASONS: usize = 65;
+
+impl ExitReason {
+    /// 规范函数：原始编号到退出原因的映射（未定义的编号为 None）
+    pub open spec fn spec_from_raw(raw: u32) -> Option<ExitReason> {
+        match raw {
+            0 => Some(ExitReason::ExceptionNmi),
+            1 => Some(ExitReason::ExternalInterrupt),
+            2 => Some(ExitReason::TripleFault),
+            3 => Some(ExitReason::InitSignal),
+            4 => Some(ExitReason::StartupIpi),
+            5 => Some(ExitReason::IoSmi),
+            6 => Some(ExitReason::OtherSmi),
+            7 => Some(ExitReason::InterruptWindow),
+            8 => Some(ExitReason::NmiWindow),
+            9 => Some(ExitReason::TaskSwitch),
+            10 => Some(ExitReason::Cpuid),
+            11 => Some(ExitReason::Getsec),
+            12 => Some(ExitReason::Hlt),
+            13 => Some(ExitReason::Invd),
+            14 => Some(ExitReason::Invlpg),
+            15 => Some(ExitReason::Rdpmc),
+            16 => Some(ExitReason::Rdtsc),
+            17 => Some(ExitReason::Rsm),
+            18 => Some(ExitReason::Vmcall),
+            19 => Some(ExitReason::Vmclear),
+            20 => Some(ExitReason::Vmlaunch),
+            21 => Some(ExitReason::Vmptrld),
+            22 => Some(ExitReason::Vmptrst),
+            23 => Some(ExitReason::Vmread),
+            24 => Some(ExitReason::Vmresume),
+            25 => Some(ExitReason::Vmwrite),
+            26 => Some(ExitReason::Vmxoff),
+            27 => Some(ExitReason::Vmxon),
+            28 => Some(ExitReason::CrAccess),
+            29 => Some(ExitReason::DrAccess),
+            30 => Some(ExitReason::IoInstruction),
+            31 => Some(ExitReason::MsrRead),
+            32 => Some(ExitReason::MsrWrite),
+            33 => Some(ExitReason::InvalidGuestState),
+            34 => Some(ExitReason::MsrLoadFail),
+            36 => Some(ExitReason::Mwait),
+            37 => Some(ExitReason::MonitorTrapFlag),
+            39 => Some(ExitReason::Monitor),
+            40 => Some(ExitReason::Pause),
+            41 => Some(ExitReason::MceDuringEntry),
+            43 => Some(ExitReason::TprBelowThreshold),
+            44 => Some(ExitReason::ApicAccess),
+            45 => Some(ExitReason::VirtualizedEoi),
+            46 => Some(ExitReason::GdtrIdtrAccess),
+            47 => Some(ExitReason::LdtrTrAccess),
+            48 => Some(ExitReason::EptViolation),
+            49 => Some(ExitReason::EptMisconfig),
+            50 => Some(ExitReason::Invept),
+            51 => Some(ExitReason::Rdtscp),
+            52 => Some(ExitReason::PreemptionTimer),
+            53 => Some(ExitReason::Invvpid),
+            54 => Some(ExitReason::Wbinvd),
+            55 => Some(ExitReason::Xsetbv),
+            56 => Some(ExitReason::ApicWrite),
+            57 => Some(ExitReason::Rdrand),
+            58 => Some(ExitReason::Invpcid),
+            59 => Some(ExitReason::Vmfunc),
+            60 => Some(ExitReason::Encls),
+            61 => Some(ExitReason::Rdseed),
+            62 => Some(ExitReason::PmlFull),
+            63 => Some(ExitReason::Xsaves),
+            64 => Some(ExitReason::Xrstors),
+            _ => None,
+        }
+    }
+
+    /// 从 VMCS 读出的原始编号解码退出原因
+    pub fn from_raw(raw: u32) -> (result: Option<ExitReason>)
+        ensures
+            result == Self::spec_from_raw(raw),
+    {
+        match raw {
+            0 => Some(ExitReason::ExceptionNmi),
+            1 => Some(ExitReason::ExternalInterrupt),
+            2 => Some(ExitReason::TripleFault),
+            3 => Some(ExitReason::InitSignal),
+            4 => Some(ExitReason::StartupIpi),
+            5 => Some(ExitReason::IoSmi),
+            6 => Some(ExitReason::OtherSmi),
+            7 => Some(ExitReason::InterruptWindow),
+            8 => Some(ExitReason::NmiWindow),
+            9 => Some(ExitReason::TaskSwitch),
+            10 => Some(ExitReason::Cpuid),
+            11 => Some(ExitReason::Getsec),
+            12 => Some(ExitReason::Hlt),
+            13 => Some(ExitReason::Invd),
+            14 => Some(ExitReason::Invlpg),
+            15 => Some(ExitReason::Rdpmc),
+            16 => Some(ExitReason::Rdtsc),
+            17 => Some(ExitReason::Rsm),
+            18 => Some(ExitReason::Vmcall),
+            19 => Some(ExitReason::Vmclear),
+            20 => Some(ExitReason::Vmlaunch),
+            21 => Some(ExitReason::Vmptrld),
+            22 => Some(ExitReason::Vmptrst),
+            23 => Some(ExitReason::Vmread),
+            24 => Some(ExitReason::Vmresume),
+            25 => Some(ExitReason::Vmwrite),
+            26 => Some(ExitReason::Vmxoff),
+            27 => Some(ExitReason::Vmxon),
+            28 => Some(ExitReason::CrAccess),
+            29 => Some(ExitReason::DrAccess),
+            30 => Some(ExitReason::IoInstruction),
+            31 => Some(ExitReason::MsrRead),
+            32 => Some(ExitReason::MsrWrite),
+            33 => Some(ExitReason::InvalidGuestState),
+            34 => Some(ExitReason::MsrLoadFail),
+            36 => Some(ExitReason::Mwait),
+            37 => Some(ExitReason::MonitorTrapFlag),
+            39 => Some(ExitReason::Monitor),
+            40 => Some(ExitReason::Pause),
+            41 => Some(ExitReason::MceDuringEntry),
+            43 => Some(ExitReason::TprBelowThreshold),
+            44 => Some(ExitReason::ApicAccess),
+            45 => Some(ExitReason::VirtualizedEoi),
+            46 => Some(ExitReason::GdtrIdtrAccess),
+            47 => Some(ExitReason::LdtrTrAccess),
+            48 => Some(ExitReason::EptViolation),
+            49 => Some(ExitReason::EptMisconfig),
+            50 => Some(ExitReason::Invept),
+            51 => Some(ExitReason::Rdtscp),
+            52 => Some(ExitReason::PreemptionTimer),
+            53 => Some(ExitReason::Invvpid),
+            54 => Some(ExitReason::Wbinvd),
+            55 => Some(ExitReason::Xsetbv),
+            56 => Some(ExitReason::ApicWrite),
+            57 => Some(ExitReason::Rdrand),
+            58 => Some(ExitReason::Invpcid),
+            59 => Some(ExitReason::Vmfunc),
+            60 => Some(ExitReason::Encls),
+            61 => Some(ExitReason::Rdseed),
+            62 => Some(ExitReason::PmlFull),
+            63 => Some(ExitReason::Xsaves),
+            64 => Some(ExitReason::Xrstors),
+            _ => None,
+        }
+    }
+
+    /// 规范函数：dispatcher 已实现处理函数的退出原因
+    pub open spec fn is_supported(self) -> bool {
+        ||| self is ExceptionNmi
+        ||| self is ExternalInterrupt
+        ||| self is StartupIpi
+        ||| self is InterruptWindow
+        ||| self is TaskSwitch
+        ||| self is NmiWindow
+        ||| self is TprBelowThreshold
+        ||| self is Cpuid
+        ||| self is Getsec
+        ||| self is Hlt
+        ||| self is MsrRead
+        ||| self is MsrWrite
+        ||| self is Monitor
+        ||| self is Mwait
+        ||| self is CrAccess
+        ||| self is DrAccess
+        ||| self is Rdrand
+        ||| self is Rdseed
+        ||| self is VirtualizedEoi
+        ||| self is ApicWrite
+        ||| self is EptMisconfig
+        ||| self is EptViolation
+        ||| self.is_vmx_instruction()
+    }
+    
+    /// 规范函数：VMX 指令引起的退出（模型不支持嵌套虚拟化，统一注入异常）
+    pub open spec fn is_vmx_instruction(self) -> bool {
+        ||| self is Vmxon
+        ||| self is Vmclear
+        ||| self is Vmlaunch
+        ||| self is Vmresume
+        ||| self is Vmread
+        ||| self is Vmwrite
+        ||| self is Vmptrld
+        ||| self is Vmptrst
+        ||| self is Vmxoff
+        ||| self is Invept
+        ||| self is Invvpid
+        ||| self is Vmfunc
+    }
+    
+    /// 规范函数：dispatcher 为该退出运行已知处理函数的条件
+    /// 
+    /// 退出原因已支持，且引起该退出的控制位已开启（ExceptionNmi 还要求 info 描述的是 NMI）；
+    /// 控制位未开启却出现的退出与未支持的原因一样交给 handle_unknown
+    pub open spec fn spec_dispatch_enabled(self, ctls: VmcsControls, info: u32) -> bool {
+        match self {
+            ExitReason::ExceptionNmi => ctls.pin_ctls.nmi_exiting && spec_exit_is_nmi(info),
+            ExitReason::ExternalInterrupt => ctls.pin_ctls.external_interrupt_exiting,
+            ExitReason::InterruptWindow => ctls.proc_ctls.interrupt_window_exiting,
+            ExitReason::NmiWindow => ctls.tracks_virtual_nmi(),
+            ExitReason::TprBelowThreshold => ctls.proc_ctls.use_tpr_shadow,
+            ExitReason::EptMisconfig | ExitReason::EptViolation =>
+                ctls.proc_ctls.activate_secondary && ctls.secondary_ctls.enable_ept,
+            ExitReason::CrAccess => ctls.proc_ctls.cr3_load_exiting || ctls.cr0_mask != 0 || ctls.cr4_mask != 0
+                || ctls.proc_ctls.cr8_load_exiting || ctls.proc_ctls.cr8_store_exiting,
+            ExitReason::DrAccess => ctls.proc_ctls.mov_dr_exiting,
+            ExitReason::Monitor => ctls.proc_ctls.monitor_exiting,
+            ExitReason::Mwait => ctls.proc_ctls.mwait_exiting,
+            ExitReason::ApicWrite =>
+                ctls.proc_ctls.activate_secondary && ctls.secondary_ctls.apic_register_virtualization,
+            ExitReason::VirtualizedEoi =>
+                ctls.proc_ctls.activate_secondary && ctls.secondary_ctls.virtual_interrupt_delivery,
+            ExitReason::Rdrand => ctls.proc_ctls.activate_secondary && ctls.secondary_ctls.rdrand_exiting,
+            ExitReason::Rdseed => ctls.proc_ctls.activate_secondary && ctls.secondary_ctls.rdseed_exiting,
+            _ => self.is_supported(),
+        }
+    }
+    
+    /// 只有 ExceptionNmi 需要 VMREAD interruption information
+    pub fn dispatch_enabled(&self, ctls: &VmcsControls) -> (result: bool)
+        ensures
+            result == self.spec_dispatch_enabled(*ctls, spec_exit_interruption_info()),
+    {
+        let secondary = ctls.proc_ctls.activate_secondary;
+        match self {
+            ExitReason::ExceptionNmi =>
+                ctls.pin_ctls.nmi_exiting && exit_is_nmi(read_exit_interruption_info()),
+            ExitReason::ExternalInterrupt => ctls.pin_ctls.external_interrupt_exiting,
+            ExitReason::InterruptWindow => ctls.proc_ctls.interrupt_window_exiting,
+            ExitReason::NmiWindow => ctls.pin_ctls.nmi_exiting && ctls.pin_ctls.virtual_nmis
+                && ctls.proc_ctls.nmi_window_exiting,
+            ExitReason::TprBelowThreshold => ctls.proc_ctls.use_tpr_shadow,
+            ExitReason::EptMisconfig | ExitReason::EptViolation => secondary && ctls.secondary_ctls.enable_ept,
+            ExitReason::CrAccess => ctls.proc_ctls.cr3_load_exiting || ctls.cr0_mask != 0 || ctls.cr4_mask != 0
+                || ctls.proc_ctls.cr8_load_exiting || ctls.proc_ctls.cr8_store_exiting,
+            ExitReason::DrAccess => ctls.proc_ctls.mov_dr_exiting,
+            ExitReason::Monitor => ctls.proc_ctls.monitor_exiting,
+            ExitReason::Mwait => ctls.proc_ctls.mwait_exiting,
+            ExitReason::ApicWrite => secondary && ctls.secondary_ctls.apic_register_virtualization,
+            ExitReason::VirtualizedEoi => secondary && ctls.secondary_ctls.virtual_interrupt_delivery,
+            ExitReason::Rdrand => secondary && ctls.secondary_ctls.rdrand_exiting,
+            ExitReason::Rdseed => secondary && ctls.secondary_ctls.rdseed_exiting,
+            ExitReason::StartupIpi | ExitReason::TaskSwitch | ExitReason::Cpuid | ExitReason::Getsec
+            | ExitReason::Hlt | ExitReason::MsrRead | ExitReason::MsrWrite
+            | ExitReason::Vmxon | ExitReason::Vmclear | ExitReason::Vmlaunch | ExitReason::Vmresume
+            | ExitReason::Vmread | ExitReason::Vmwrite | ExitReason::Vmptrld | ExitReason::Vmptrst
+            | ExitReason::Vmxoff | ExitReason::Invept | ExitReason::Invvpid | ExitReason::Vmfunc => true,
+            _ => false,
+        }
+    }
+}
+
+/// 规范函数：控制字段取值满足 IA32_VMX_*_CTLS 能力 MSR
+/// 
+/// 能力 MSR 低 32 位为 allowed-0（必须为 1 的位），高 32 位为 allowed-1（允许为 1 的位）
+pub open spec fn ctls_allowed(bits: u32, cap: u64) -> bool {
+    let allowed0 = cap as u32;
+    let allowed1 = (cap >> 32u64) as u32;
+    &&& bits & allowed0 == allowed0
+    &&& bits & !allowed1 == 0
+}
+
+pub fn ctls_allowed_by(bits: u32, cap: u64) -> (result: bool)
+    ensures
+        result == ctls_allowed(bits, cap),
+{
+    let allowed0 = cap as u32;
+    let allowed1 = (cap >> 32u64) as u32;
+    bits & allowed0 == allowed0 && bits & !allowed1 == 0
+}
+
+pub const EXIT_CTLS_HOST_ADDR_SPACE_SIZE: u32 = 1 << 9;
+pub const EXIT_CTLS_ACK_INTERRUPT_ON_EXIT: u32 = 1 << 15;
+pub const EXIT_CTLS_SAVE_IA32_PAT: u32 = 1 << 18;
+pub const EXIT_CTLS_LOAD_IA32_PAT: u32 = 1 << 19;
+pub const EXIT_CTLS_SAVE_IA32_EFER: u32 = 1 << 20;
+pub const EXIT_CTLS_LOAD_IA32_EFER: u32 = 1 << 21;
+pub const EXIT_CTLS_SAVE_PREEMPTION_TIMER: u32 = 1 << 22;
+
+/// VM-exit controls
+#[derive(Clone, Copy)]
+pub struct ExitCtls {
+    pub host_addr_space_size: bool,
+    pub ack_interrupt_on_exit: bool,
+    pub save_ia32_pat: bool,
+    pub load_ia32_pat: bool,
+    pub save_ia32_efer: bool,
+    pub load_ia32_efer: bool,
+    pub save_preemption_timer: bool,
+}
+
+impl ExitCtls {
+    pub fn new() -> (result: Self)
+        ensures
+            !result.load_ia32_efer,
+            !result.save_ia32_efer,
+    {
+        ExitCtls {
+            host_addr_space_size: false,
+            ack_interrupt_on_exit: false,
+            save_ia32_pat: false,
+            load_ia32_pat: false,
+            save_ia32_efer: false,
+            load_ia32_efer: false,
+            save_preemption_timer: false,
+        }
+    }
+    
+    /// 规范函数：写入 VMCS 的 32 位编码
+    pub open spec fn spec_bits(&self) -> u32 {
+        (if self.host_addr_space_size { EXIT_CTLS_HOST_ADDR_SPACE_SIZE } else { 0 })
+            | (if self.ack_interrupt_on_exit { EXIT_CTLS_ACK_INTERRUPT_ON_EXIT } else { 0 })
+            | (if self.save_ia32_pat { EXIT_CTLS_SAVE_IA32_PAT } else { 0 })
+            | (if self.load_ia32_pat { EXIT_CTLS_LOAD_IA32_PAT } else { 0 })
+            | (if self.save_ia32_efer { EXIT_CTLS_SAVE_IA32_EFER } else { 0 })
+            | (if self.load_ia32_efer { EXIT_CTLS_LOAD_IA32_EFER } else { 0 })
+            | (if self.save_preemption_timer { EXIT_CTLS_SAVE_PREEMPTION_TIMER } else { 0 })
+    }
+    
+    pub fn bits(&self) -> (result: u32)
+        ensures
+            result == self.spec_bits(),
+    {
+        (if self.host_addr_space_size { EXIT_CTLS_HOST_ADDR_SPACE_SIZE } else { 0 })
+            | (if self.ack_interrupt_on_exit { EXIT_CTLS_ACK_INTERRUPT_ON_EXIT } else { 0 })
+            | (if self.save_ia32_pat { EXIT_CTLS_SAVE_IA32_PAT } else { 0 })
+            | (if self.load_ia32_pat { EXIT_CTLS_LOAD_IA32_PAT } else { 0 })
+            | (if self.save_ia32_efer { EXIT_CTLS_SAVE_IA32_EFER } else { 0 })
+            | (if self.load_ia32_efer { EXIT_CTLS_LOAD_IA32_EFER } else { 0 })
+            | (if self.save_preemption_timer { EXIT_CTLS_SAVE_PREEMPTION_TIMER } else { 0 })
+    }
+    
+    /// 规范函数：满足 IA32_VMX_EXIT_CTLS 能力
+    pub open spec fn is_valid(&self, cap: u64) -> bool {
+        ctls_allowed(self.spec_bits(), cap)
+    }
+    
+    /// 规范函数：与 host 的 long mode 状态一致
+    /// 
+    /// 64 位 hypervisor 退出后必须回到 64 位 host；
+    /// 若退出时加载 host EFER，则其 LMA/LME 必须与 host address-space size 一致
+    pub open spec fn host_consistent(&self, host_efer: u64) -> bool {
+        &&& self.host_addr_space_size
+        &&& self.load_ia32_efer ==> {
+            &&& efer_lma(host_efer) == self.host_addr_space_size
+            &&& efer_lme(host_efer) == self.host_addr_space_size
+        }
+    }
+}
+
+/// 引理：退出时加载 host EFER 的配置不会把 64 位 host 切回非 long mode
+pub proof fn lemma_exit_load_efer_long_mode(ctls: ExitCtls, host_efer: u64)
+    requires
+        ctls.host_consistent(host_efer),
+        ctls.load_ia32_efer,
+    ensures
+        efer_lma(host_efer),
+        efer_lme(host_efer),
+{
+}
+
+pub const ENTRY_CTLS_IA32E_MODE_GUEST: u32 = 1 << 9;
+pub const ENTRY_CTLS_LOAD_IA32_PAT: u32 = 1 << 14;
+pub const ENTRY_CTLS_LOAD_IA32_EFER: u32 = 1 << 15;
+
+/// VM-entry controls
+#[derive(Clone, Copy)]
+pub struct EntryCtls {
+    /// 随 guest EFER.LMA 变化，由 EFER 写处理同步更新
+    pub ia32e_mode_guest: bool,
+    pub load_ia32_pat: bool,
+    pub load_ia32_efer: bool,
+}
+
+impl EntryCtls {
+    pub fn new() -> (result: Self)
+        ensures
+            !result.ia32e_mode_guest,
+            !result.load_ia32_efer,
+    {
+        EntryCtls {
+            ia32e_mode_guest: false,
+            load_ia32_pat: false,
+            load_ia32_efer: false,
+        }
+    }
+    
+    /// 规范函数：除 IA-32e mode guest 外的固定位
+    pub open spec fn spec_fixed_bits(&self) -> u32 {
+        (if self.load_ia32_pat { ENTRY_CTLS_LOAD_IA32_PAT } else { 0 })
+            | (if self.load_ia32_efer { ENTRY_CTLS_LOAD_IA32_EFER } else { 0 })
+    }
+    
+    /// 规范函数：写入 VMCS 的 32 位编码
+    pub open spec fn spec_bits(&self) -> u32 {
+        (if self.ia32e_mode_guest { ENTRY_CTLS_IA32E_MODE_GUEST } else { 0 }) | self.spec_fixed_bits()
+    }
+    
+    pub fn bits(&self) -> (result: u32)
+        ensures
+            result == self.spec_bits(),
+    {
+        (if self.ia32e_mode_guest { ENTRY_CTLS_IA32E_MODE_GUEST } else { 0 })
+            | (if self.load_ia32_pat { ENTRY_CTLS_LOAD_IA32_PAT } else { 0 })
+            | (if self.load_ia32_efer { ENTRY_CTLS_LOAD_IA32_EFER } else { 0 })
+    }
+    
+    /// 规范函数：满足 IA32_VMX_ENTRY_CTLS 能力
+    /// 
+    /// IA-32e mode guest 在运行中随 guest 切换模式翻转，因此两种取值都必须被允许
+    pub open spec fn is_valid(&self, cap: u64) -> bool {
+        &&& ctls_allowed(self.spec_fixed_bits(), cap)
+        &&& ctls_allowed(self.spec_fixed_bits() | ENTRY_CTLS_IA32E_MODE_GUEST, cap)
+    }
+}
+
+pub const PIN_CTLS_EXTERNAL_INTERRUPT_EXITING: u32 = 1 << 0;
+pub const PIN_CTLS_NMI_EXITING: u32 = 1 << 3;
+pub const PIN_CTLS_VIRTUAL_NMIS: u32 = 1 << 5;
+pub const PIN_CTLS_ACTIVATE_PREEMPTION_TIMER: u32 = 1 << 6;
+
+/// Pin-based VM-execution controls
+#[derive(Clone, Copy)]
+pub struct PinCtls {
+    pub external_interrupt_exiting: bool,
+    pub nmi_exiting: bool,
+    pub virtual_nmis: bool,
+    pub activate_preemption_timer: bool,
+}
+
+impl PinCtls {
+    pub fn new() -> (result: Self) {
+        PinCtls {
+            external_interrupt_exiting: false,
+            nmi_exiting: false,
+            virtual_nmis: false,
+            activate_preemption_timer: false,
+        }
+    }
+    
+    /// 规范函数：写入 VMCS 的 32 位编码
+    pub open spec fn spec_bits(&self) -> u32 {
+        (if self.external_interrupt_exiting { PIN_CTLS_EXTERNAL_INTERRUPT_EXITING } else { 0 })
+            | (if self.nmi_exiting { PIN_CTLS_NMI_EXITING } else { 0 })
+            | (if self.virtual_nmis { PIN_CTLS_VIRTUAL_NMIS } else { 0 })
+            | (if self.activate_preemption_timer { PIN_CTLS_ACTIVATE_PREEMPTION_TIMER } else { 0 })
+    }
+    
+    pub fn bits(&self) -> (result: u32)
+        ensures
+            result == self.spec_bits(),
+    {
+        (if self.external_interrupt_exiting { PIN_CTLS_EXTERNAL_INTERRUPT_EXITING } else { 0 })
+            | (if self.nmi_exiting { PIN_CTLS_NMI_EXITING } else { 0 })
+            | (if self.virtual_nmis { PIN_CTLS_VIRTUAL_NMIS } else { 0 })
+            | (if self.activate_preemption_timer { PIN_CTLS_ACTIVATE_PREEMPTION_TIMER } else { 0 })
+    }
+    
+    /// 规范函数：满足 IA32_VMX_PINBASED_CTLS 能力
+    pub open spec fn is_valid(&self, cap: u64) -> bool {
+        ctls_allowed(self.spec_bits(), cap)
+    }
+}
+
+pub const PROC_CTLS_INTERRUPT_WINDOW_EXITING: u32 = 1 << 2;
+pub const PROC_CTLS_HLT_EXITING: u32 = 1 << 7;
+pub const PROC_CTLS_MWAIT_EXITING: u32 = 1 << 10;
+pub const PROC_CTLS_CR3_LOAD_EXITING: u32 = 1 << 15;
+pub const PROC_CTLS_CR8_LOAD_EXITING: u32 = 1 << 19;
+pub const PROC_CTLS_CR8_STORE_EXITING: u32 = 1 << 20;
+pub const PROC_CTLS_USE_TPR_SHADOW: u32 = 1 << 21;
+pub const PROC_CTLS_NMI_WINDOW_EXITING: u32 = 1 << 22;
+pub const PROC_CTLS_MOV_DR_EXITING: u32 = 1 << 23;
+pub const PROC_CTLS_MONITOR_EXITING: u32 = 1 << 29;
+pub const PROC_CTLS_ACTIVATE_SECONDARY: u32 = 1 << 31;
+
+/// Primary processor-based VM-execution controls
+#[derive(Clone, Copy)]
+pub struct ProcCtls {
+    pub interrupt_window_exiting: bool,
+    pub hlt_exiting: bool,
+    pub mwait_exiting: bool,
+    pub cr3_load_exiting: bool,
+    pub cr8_load_exiting: bool,
+    pub cr8_store_exiting: bool,
+    pub use_tpr_shadow: bool,
+    pub nmi_window_exiting: bool,
+    pub mov_dr_exiting: bool,
+    pub monitor_exiting: bool,
+    pub activate_secondary: bool,
+}
+
+impl ProcCtls {
+    pub fn new() -> (result: Self)
+        ensures
+            !result.interrupt_window_exiting,
+    {
+        ProcCtls {
+            interrupt_window_exiting: false,
+            hlt_exiting: false,
+            mwait_exiting: false,
+            cr3_load_exiting: false,
+            cr8_load_exiting: false,
+            cr8_store_exiting: false,
+            use_tpr_shadow: false,
+            nmi_window_exiting: false,
+            mov_dr_exiting: false,
+            monitor_exiting: false,
+            activate_secondary: false,
+        }
+    }
+    
+    /// 规范函数：写入 VMCS 的 32 位编码
+    pub open spec fn spec_bits(&self) -> u32 {
+        (if self.interrupt_window_exiting { PROC_CTLS_INTERRUPT_WINDOW_EXITING } else { 0 })
+            | (if self.hlt_exiting { PROC_CTLS_HLT_EXITING } else { 0 })
+            | (if self.mwait_exiting { PROC_CTLS_MWAIT_EXITING } else { 0 })
+            | (if self.cr3_load_exiting { PROC_CTLS_CR3_LOAD_EXITING } else { 0 })
+            | (if self.cr8_load_exiting { PROC_CTLS_CR8_LOAD_EXITING } else { 0 })
+            | (if self.cr8_store_exiting { PROC_CTLS_CR8_STORE_EXITING } else { 0 })
+            | (if self.use_tpr_shadow { PROC_CTLS_USE_TPR_SHADOW } else { 0 })
+            | (if self.nmi_window_exiting { PROC_CTLS_NMI_WINDOW_EXITING } else { 0 })
+            | (if self.mov_dr_exiting { PROC_CTLS_MOV_DR_EXITING } else { 0 })
+            | (if self.monitor_exiting { PROC_CTLS_MONITOR_EXITING } else { 0 })
+            | (if self.activate_secondary { PROC_CTLS_ACTIVATE_SECONDARY } else { 0 })
+    }
+    
+    pub fn bits(&self) -> (result: u32)
+        ensures
+            result == self.spec_bits(),
+    {
+        (if self.interrupt_window_exiting { PROC_CTLS_INTERRUPT_WINDOW_EXITING } else { 0 })
+            | (if self.hlt_exiting { PROC_CTLS_HLT_EXITING } else { 0 })
+            | (if self.mwait_exiting { PROC_CTLS_MWAIT_EXITING } else { 0 })
+            | (if self.cr3_load_exiting { PROC_CTLS_CR3_LOAD_EXITING } else { 0 })
+            | (if self.cr8_load_exiting { PROC_CTLS_CR8_LOAD_EXITING } else { 0 })
+            | (if self.cr8_store_exiting { PROC_CTLS_CR8_STORE_EXITING } else { 0 })
+            | (if self.use_tpr_shadow { PROC_CTLS_USE_TPR_SHADOW } else { 0 })
+            | (if self.nmi_window_exiting { PROC_CTLS_NMI_WINDOW_EXITING } else { 0 })
+            | (if self.mov_dr_exiting { PROC_CTLS_MOV_DR_EXITING } else { 0 })
+            | (if self.monitor_exiting { PROC_CTLS_MONITOR_EXITING } else { 0 })
+            | (if self.activate_secondary { PROC_CTLS_ACTIVATE_SECONDARY } else { 0 })
+    }
+    
+    /// 规范函数：满足 IA32_VMX_PROCBASED_CTLS 能力
+    pub open spec fn is_valid(&self, cap: u64) -> bool {
+        ctls_allowed(self.spec_bits(), cap)
+    }
+}
+
+pub const SEC_CTLS_VIRTUALIZE_APIC_ACCESSES: u32 = 1 << 0;
+pub const SEC_CTLS_ENABLE_EPT: u32 = 1 << 1;
+pub const SEC_CTLS_ENABLE_RDTSCP: u32 = 1 << 3;
+pub const SEC_CTLS_ENABLE_VPID: u32 = 1 << 5;
+pub const SEC_CTLS_UNRESTRICTED_GUEST: u32 = 1 << 7;
+pub const SEC_CTLS_APIC_REGISTER_VIRT: u32 = 1 << 8;
+pub const SEC_CTLS_VIRTUAL_INTR_DELIVERY: u32 = 1 << 9;
+pub const SEC_CTLS_RDRAND_EXITING: u32 = 1 << 11;
+pub const SEC_CTLS_RDSEED_EXITING: u32 = 1 << 16;
+
+/// Secondary processor-based VM-execution controls
+#[derive(Clone, Copy)]
+pub struct SecondaryCtls {
+    pub virtualize_apic_accesses: bool,
+    pub enable_ept: bool,
+    pub enable_rdtscp: bool,
+    pub enable_vpid: bool,
+    pub unrestricted_guest: bool,
+    pub apic_register_virtualization: bool,
+    pub virtual_interrupt_delivery: bool,
+    pub rdrand_exiting: bool,
+    pub rdseed_exiting: bool,
+}
+
+impl SecondaryCtls {
+    pub fn new() -> (result: Self)
+        ensures
+            !result.unrestricted_guest,
+    {
+        SecondaryCtls {
+            virtualize_apic_accesses: false,
+            enable_ept: false,
+            enable_rdtscp: false,
+            enable_vpid: false,
+            unrestricted_guest: false,
+            apic_register_virtualization: false,
+            virtual_interrupt_delivery: false,
+            rdrand_exiting: false,
+            rdseed_exiting: false,
+        }
+    }
+    
+    /// 规范函数：写入 VMCS 的 32 位编码
+    pub open spec fn spec_bits(&self) -> u32 {
+        (if self.virtualize_apic_accesses { SEC_CTLS_VIRTUALIZE_APIC_ACCESSES } else { 0 })
+            | (if self.enable_ept { SEC_CTLS_ENABLE_EPT } else { 0 })
+            | (if self.enable_rdtscp { SEC_CTLS_ENABLE_RDTSCP } else { 0 })
+            | (if self.enable_vpid { SEC_CTLS_ENABLE_VPID } else { 0 })
+            | (if self.unrestricted_guest { SEC_CTLS_UNRESTRICTED_GUEST } else { 0 })
+            | (if self.apic_register_virtualization { SEC_CTLS_APIC_REGISTER_VIRT } else { 0 })
+            | (if self.virtual_interrupt_delivery { SEC_CTLS_VIRTUAL_INTR_DELIVERY } else { 0 })
+            | (if self.rdrand_exiting { SEC_CTLS_RDRAND_EXITING } else { 0 })
+            | (if self.rdseed_exiting { SEC_CTLS_RDSEED_EXITING } else { 0 })
+    }
+    
+    pub fn bits(&self) -> (result: u32)
+        ensures
+            result == self.spec_bits(),
+    {
+        (if self.virtualize_apic_accesses { SEC_CTLS_VIRTUALIZE_APIC_ACCESSES } else { 0 })
+            | (if self.enable_ept { SEC_CTLS_ENABLE_EPT } else { 0 })
+            | (if self.enable_rdtscp { SEC_CTLS_ENABLE_RDTSCP } else { 0 })
+            | (if self.enable_vpid { SEC_CTLS_ENABLE_VPID } else { 0 })
+            | (if self.unrestricted_guest { SEC_CTLS_UNRESTRICTED_GUEST } else { 0 })
+            | (if self.apic_register_virtualization { SEC_CTLS_APIC_REGISTER_VIRT } else { 0 })
+            | (if self.virtual_interrupt_delivery { SEC_CTLS_VIRTUAL_INTR_DELIVERY } else { 0 })
+            | (if self.rdrand_exiting { SEC_CTLS_RDRAND_EXITING } else { 0 })
+            | (if self.rdseed_exiting { SEC_CTLS_RDSEED_EXITING } else { 0 })
+    }
+    
+    /// 规范函数：满足 IA32_VMX_PROCBASED_CTLS2 能力
+    pub open spec fn is_valid(&self, cap: u64) -> bool {
+        ctls_allowed(self.spec_bits(), cap)
+    }
+}
+
+/// 写入 VMCS 的全部 VM-execution / VM-exit 控制字段
+#[derive(Clone, Copy)]
+pub struct VmcsControls {
+    pub pin_ctls: PinCtls,
+    pub proc_ctls: ProcCtls,
+    pub secondary_ctls: SecondaryCtls,
+    pub exit_ctls: ExitCtls,
+    pub entry_ctls: EntryCtls,
+    /// TPR threshold（仅 bits 3:0 有效，use TPR shadow 时使用）
+    pub tpr_threshold: u8,
+    /// CR0 guest/host mask：置位的位归 host 所有，guest 写 CR0 改动这些位时产生退出
+    pub cr0_mask: u64,
+    /// CR4 guest/host mask：置位的位归 host 所有，guest 写 CR4 改动这些位时产生退出
+    pub cr4_mask: u64,
+}
+
+impl VmcsControls {
+    pub fn new() -> (result: Self)
+        ensures
+            !result.efer_in_vmcs(),
+            !result.entry_ctls.ia32e_mode_guest,
+            result.window_policy_holds(),
+    {
+        VmcsControls {
+            pin_ctls: PinCtls::new(),
+            proc_ctls: ProcCtls::new(),
+            secondary_ctls: SecondaryCtls::new(),
+            exit_ctls: ExitCtls::new(),
+            entry_ctls: EntryCtls::new(),
+            tpr_threshold: 0,
+            cr0_mask: 0,
+            cr4_mask: 0,
+        }
+    }
+    
+    /// 规范函数：各控制字段都满足硬件能力，且与 host 状态一致
+    pub open spec fn is_valid(&self) -> bool {
+        &&& self.pin_ctls.is_valid(spec_vmx_pin_ctls_cap())
+        &&& self.proc_ctls.is_valid(spec_vmx_proc_ctls_cap())
+        &&& self.proc_ctls.activate_secondary
+            ==> self.secondary_ctls.is_valid(spec_vmx_proc_ctls2_cap())
+        // SDM 26.2.1.1：unrestricted guest 依赖 EPT
+        &&& self.secondary_ctls.unrestricted_guest ==> self.secondary_ctls.enable_ept
+        &&& self.exit_ctls.is_valid(spec_vmx_exit_ctls_cap())
+        &&& self.exit_ctls.host_consistent(spec_host_efer())
+        &&& self.entry_ctls.is_valid(spec_vmx_entry_ctls_cap())
+        // SDM 26.2.1.1：virtual NMIs 依赖 NMI exiting，NMI-window exiting 依赖 virtual NMIs
+        &&& self.pin_ctls.virtual_nmis ==> self.pin_ctls.nmi_exiting
+        &&& self.proc_ctls.nmi_window_exiting ==> self.pin_ctls.virtual_nmis
+        // SDM 26.2.1.2：保存 preemption timer 依赖激活 preemption timer
+        &&& self.exit_ctls.save_preemption_timer ==> self.pin_ctls.activate_preemption_timer
+        // SDM 26.2.1.1：TPR threshold 的 bits 31:4 必须为 0
+        &&& self.proc_ctls.use_tpr_shadow ==> self.tpr_threshold <= 0xf
+        // SDM 26.2.1.1：APIC-register virtualization 与 virtual-interrupt delivery 依赖 TPR shadow，
+        // virtual-interrupt delivery 还依赖 external-interrupt exiting
+        &&& (self.secondary_ctls.apic_register_virtualization || self.secondary_ctls.virtual_interrupt_delivery)
+            ==> self.proc_ctls.use_tpr_shadow
+        &&& self.secondary_ctls.virtual_interrupt_delivery ==> self.pin_ctls.external_interrupt_exiting
+        // guest CR4.VMXE 必须保持置位，只能归 host 所有并经 read shadow 对 guest 隐藏
+        &&& self.cr4_mask & CR4_VMXE != 0
+        // CR0.PG 归 host 所有：切换分页时由 hypervisor 同步更新 EFER.LMA 与 IA-32e mode guest
+        &&& self.cr0_mask & CR0_PG != 0
+    }
+    
+    /// 规范函数：本 hypervisor 对事件窗口的策略（不是 SDM 的控制字段检查）
+    /// 
+    /// 只有 host 截获外部中断、由 hypervisor 注入中断时，中断窗口退出才有意义
+    pub open spec fn window_policy_holds(&self) -> bool {
+        self.proc_ctls.interrupt_window_exiting ==> self.pin_ctls.external_interrupt_exiting
+    }
+    
+    /// 规范函数：guest EFER 经 VMCS 的 guest IA32_EFER 字段进出
+    pub open spec fn efer_in_vmcs(&self) -> bool {
+        self.entry_ctls.load_ia32_efer || self.exit_ctls.save_ia32_efer
+    }
+    
+    /// 规范函数：EPT 实际生效（否则为影子页表模式）
+    pub open spec fn ept_enabled(&self) -> bool {
+        self.proc_ctls.activate_secondary && self.secondary_ctls.enable_ept
+    }
+    
+    /// 规范函数：unrestricted guest 实际生效（secondary controls 已激活）
+    pub open spec fn unrestricted_guest_enabled(&self) -> bool {
+        self.proc_ctls.activate_secondary && self.secondary_ctls.unrestricted_guest
+    }
+    
+    /// 规范函数：APIC-register virtualization 生效（guest 写 APIC 寄存器产生 APIC-write 退出）
+    pub open spec fn apic_register_virt_enabled(&self) -> bool {
+        self.proc_ctls.activate_secondary && self.secondary_ctls.apic_register_virtualization
+    }
+    
+    /// 规范函数：virtual-interrupt delivery 生效（EOI-exit bitmap 中的向量产生 virtual-EOI 退出）
+    pub open spec fn virtual_intr_delivery_enabled(&self) -> bool {
+        self.proc_ctls.activate_secondary && self.secondary_ctls.virtual_interrupt_delivery
+    }
+    
+    /// 规范函数：RDRAND 会产生 VM exit
+    pub open spec fn rdrand_exiting_enabled(&self) -> bool {
+        self.proc_ctls.activate_secondary && self.secondary_ctls.rdrand_exiting
+    }
+    
+    /// 规范函数：RDSEED 会产生 VM exit
+    pub open spec fn rdseed_exiting_enabled(&self) -> bool {
+        self.proc_ctls.activate_secondary && self.secondary_ctls.rdseed_exiting
+    }
+    
+    /// 规范函数：guest 的 IRET 解除 NMI 阻塞时会产生 NMI-window 退出
+    pub open spec fn tracks_virtual_nmi(&self) -> bool {
+        &&& self.pin_ctls.nmi_exiting
+        &&& self.pin_ctls.virtual_nmis
+        &&& self.proc_ctls.nmi_window_exiting
+    }
+}
+
+/// 引理：满足窗口策略的配置下，中断窗口处理运行时 external-interrupt exiting 一定已开启
+pub proof fn lemma_interrupt_window_requires_ext_exiting(ctls: VmcsControls)
+    requires
+        ctls.window_policy_holds(),
+        ctls.proc_ctls.interrupt_window_exiting,
+    ensures
+        ctls.pin_ctls.external_interrupt_exiting,
+{
+}
+
+/// VMX 指令失败的类型
+pub enum VmxError {
+    /// 没有当前 VMCS（RFLAGS.CF = 1），不可重试
+    VMfailInvalid,
+    /// 有当前 VMCS，错误号写入 VM-instruction error 字段（RFLAGS.ZF = 1）
+    VMfailValid,
+}
+
+impl VmxError {
+    /// 规范函数：该失败是否可以重试
+    pub open spec fn spec_is_recoverable(&self) -> bool {
+        self is VMfailValid
+    }
+}
+
+pub const RFLAGS_CF: u64 = 1 << 0;
+pub const RFLAGS_PF: u64 = 1 << 2;
+pub const RFLAGS_AF: u64 = 1 << 4;
+pub const RFLAGS_ZF: u64 = 1 << 6;
+pub const RFLAGS_SF: u64 = 1 << 7;
+pub const RFLAGS_OF: u64 = 1 << 11;
+/// 算术状态标志 CF/PF/AF/ZF/SF/OF
+pub const RFLAGS_ARITH: u64 = RFLAGS_CF | RFLAGS_PF | RFLAGS_AF | RFLAGS_ZF | RFLAGS_SF | RFLAGS_OF;
+
+/// 规范函数：由 VMX 指令执行后的 RFLAGS 判断结果（SDM 31.2）
+/// 
+/// CF = 1 为 VMfailInvalid，ZF = 1 为 VMfailValid，否则成功
+pub open spec fn spec_vmx_result(rflags: u64) -> Result<(), VmxError> {
+    if rflags & RFLAGS_CF != 0 {
+        Err(VmxError::VMfailInvalid)
+    } else if rflags & RFLAGS_ZF != 0 {
+        Err(VmxError::VMfailValid)
+    } else {
+        Ok(())
+    }
+}
+
+pub fn vmx_result(rflags: u64) -> (result: Result<(), VmxError>)
+    ensures
+        result == spec_vmx_result(rflags),
+{
+    if rflags & RFLAGS_CF != 0 {
+        Err(VmxError::VMfailInvalid)
+    } else if rflags & RFLAGS_ZF != 0 {
+        Err(VmxError::VMfailValid)
+    } else {
+        Ok(())
+    }
+}
+
+/// VM-instruction error 编号（SDM 31.4）
+pub const VM_ERROR_VMLAUNCH_NONCLEAR_VMCS: u64 = 4;
+pub const VM_ERROR_VMRESUME_NONLAUNCHED_VMCS: u64 = 5;
+
+/// 规范函数：VMfailValid 的错误号是否可恢复
+/// 
+/// 只有 launch 状态与指令不匹配可以通过 VMCLEAR 后重试，其余错误（控制域、host 状态等）重试无意义
+pub open spec fn spec_vm_error_recoverable(err: u64) -> bool {
+    err == VM_ERROR_VMLAUNCH_NONCLEAR_VMCS || err == VM_ERROR_VMRESUME_NONLAUNCHED_VMCS
+}
+
+pub fn vm_error_recoverable(err: u64) -> (result: bool)
+    ensures
+        result == spec_vm_error_recoverable(err),
+{
+    err == VM_ERROR_VMLAUNCH_NONCLEAR_VMCS || err == VM_ERROR_VMRESUME_NONLAUNCHED_VMCS
+}
+
+/// 引理：两种失败由 RFLAGS 的不同位区分，VMfailInvalid 永远不可恢复
+pub proof fn lemma_vmx_fail_kinds_distinct(rflags: u64)
+    ensures
+        rflags & RFLAGS_CF != 0 ==> spec_vmx_result(rflags) == Err::<(), VmxError>(VmxError::VMfailInvalid),
+        rflags & RFLAGS_CF == 0 && rflags & RFLAGS_ZF != 0
+            ==> spec_vmx_result(rflags) == Err::<(), VmxError>(VmxError::VMfailValid),
+        !VmxError::VMfailInvalid.spec_is_recoverable(),
+{
+}
+
+/// 异常向量号
+pub const EXCEPTION_DB: u8 = 1;
+pub const EXCEPTION_NMI: u8 = 2;
+pub const EXCEPTION_UD: u8 = 6;
+pub const EXCEPTION_GP: u8 = 13;
+pub const EXCEPTION_PF: u8 = 14;
+
+/// 指令模拟的结果
+pub enum EmulationResult {
+    /// 指令完成，guest RIP 前进 len 字节
+    Advance(u8),
+    /// 向 guest 注入异常
+    InjectFault { vector: u8, error_code: Option<u32> },
+    /// 不修改 RIP，直接重新进入 guest
+    Reenter,
+}
+
+impl EmulationResult {
+    /// 规范函数：#GP(0)
+    pub open spec fn spec_gp() -> EmulationResult {
+        EmulationResult::InjectFault { vector: EXCEPTION_GP, error_code: Some(0) }
+    }
+    
+    pub fn gp() -> (result: EmulationResult)
+        ensures
+            result == Self::spec_gp(),
+    {
+        EmulationResult::InjectFault { vector: EXCEPTION_GP, error_code: Some(0) }
+    }
+    
+    /// 规范函数：#UD
+    pub open spec fn spec_ud() -> EmulationResult {
+        EmulationResult::InjectFault { vector: EXCEPTION_UD, error_code: None }
+    }
+    
+    pub fn ud() -> (result: EmulationResult)
+        ensures
+            result == Self::spec_ud(),
+    {
+        EmulationResult::InjectFault { vector: EXCEPTION_UD, error_code: None }
+    }
+}
+
+/// 注入事件的类型（VM-entry interruption-information bits 10:8）
+pub enum EventType {
+    ExternalInterrupt,
+    Nmi,
+    HardwareException,
+    SoftwareInterrupt,
+}
+
+/// VM entry 时注入 guest 的事件
+pub struct InjectedEvent {
+    pub event_type: EventType,
+    pub vector: u8,
+    pub error_code: Option<u32>,
+}
+
+/// 规范函数：本次 entry 的事件能把 Hlt 状态的 guest 唤醒（外部中断、NMI）
+/// 
+/// SMI 与 INIT 不经 interruption-information 注入：SMI 由 handle_smi 停住 vCPU，
+/// INIT 使 guest 进入 wait-for-SIPI，二者都不会让 guest 直接回到 Active
+pub open spec fn spec_wakes_hlt(event: Option<InjectedEvent>) -> bool {
+    event is Some && (event.unwrap().event_type is ExternalInterrupt || event.unwrap().event_type is Nmi)
+}
+
+impl InjectedEvent {
+    /// 规范函数：硬件异常事件
+    pub open spec fn spec_exception(vector: u8, error_code: Option<u32>) -> InjectedEvent {
+        InjectedEvent { event_type: EventType::HardwareException, vector, error_code }
+    }
+    
+    /// 规范函数：NMI 事件（向量固定为 2）
+    pub open spec fn spec_nmi() -> InjectedEvent {
+        InjectedEvent { event_type: EventType::Nmi, vector: EXCEPTION_NMI, error_code: None }
+    }
+    
+    pub fn nmi() -> (result: InjectedEvent)
+        ensures
+            result == Self::spec_nmi(),
+    {
+        InjectedEvent { event_type: EventType::Nmi, vector: EXCEPTION_NMI, error_code: None }
+    }
+}
+
+/// VM-exit instruction-information 字段（SDM 28.2.5）
+/// 
+/// 1:0 scaling，9:7 地址宽度，10 寄存器/内存操作数，17:15 段寄存器，
+/// 21:18 index（22 为无效位），26:23 base（27 为无效位），31:28 第二个寄存器操作数
+#[derive(Clone, Copy)]
+pub struct VmxInstrInfo {
+    pub raw: u32,
+}
+
+/// 段寄存器编号（instruction-information 17:15）
+pub const SEG_ES: u8 = 0;
+pub const SEG_CS: u8 = 1;
+pub const SEG_SS: u8 = 2;
+pub const SEG_DS: u8 = 3;
+pub const SEG_FS: u8 = 4;
+pub const SEG_GS: u8 = 5;
+
+impl VmxInstrInfo {
+    pub open spec fn spec_scaling(&self) -> u8 {
+        (self.raw & 0x3) as u8
+    }
+    
+    /// 0 = 16 位，1 = 32 位，2 = 64 位
+    pub open spec fn spec_address_size(&self) -> u8 {
+        ((self.raw >> 7u32) & 0x7) as u8
+    }
+    
+    pub open spec fn spec_is_register(&self) -> bool {
+        (self.raw >> 10u32) & 0x1 != 0
+    }
+    
+    pub open spec fn spec_segment(&self) -> u8 {
+        ((self.raw >> 15u32) & 0x7) as u8
+    }
+    
+    pub open spec fn spec_index_reg(&self) -> Option<u8> {
+        if (self.raw >> 22u32) & 0x1 != 0 {
+            None
+        } else {
+            Some(((self.raw >> 18u32) & 0xf) as u8)
+        }
+    }
+    
+    pub open spec fn spec_base_reg(&self) -> Option<u8> {
+        if (self.raw >> 27u32) & 0x1 != 0 {
+            None
+        } else {
+            Some(((self.raw >> 23u32) & 0xf) as u8)
+        }
+    }
+    
+    pub open spec fn spec_reg2(&self) -> u8 {
+        ((self.raw >> 28u32) & 0xf) as u8
+    }
+    
+    /// 规范函数：字段取值合法（地址宽度与段寄存器编号在定义范围内）
+    pub open spec fn is_well_formed(&self) -> bool {
+        &&& self.spec_address_size() <= 2
+        &&& self.spec_segment() <= SEG_GS
+    }
+    
+    /// 规范函数：寄存器操作数形式下，内存寻址相关字段必须是“无效/未使用”编码
+    pub open spec fn register_form_unused(&self) -> bool {
+        self.spec_is_register() ==> {
+            &&& self.spec_index_reg() is None
+            &&& self.spec_base_reg() is None
+            &&& self.spec_scaling() == 0
+        }
+    }
+    
+    /// 解码 instruction-information 字段，非法编码返回 None
+    pub fn decode(raw: u32) -> (result: Option<VmxInstrInfo>)
+        ensures
+            result is Some <==> (VmxInstrInfo { raw }.is_well_formed()
+                && VmxInstrInfo { raw }.register_form_unused()),
+            result is Some ==> result.unwrap().raw == raw,
+    {
+        let info = VmxInstrInfo { raw };
+        if info.address_size() > 2 || info.segment() > SEG_GS {
+            return None;
+        }
+        if info.is_register()
+            && (info.index_reg().is_some() || info.base_reg().is_some() || info.scaling() != 0)
+        {
+            return None;
+        }
+        Some(info)
+    }
+    
+    pub fn scaling(&self) -> (result: u8)
+        ensures
+            result == self.spec_scaling(),
+            result <= 3,
+    {
+        let raw = self.raw;
+        assert(raw & 0x3 <= 0x3) by (bit_vector);
+        (self.raw & 0x3) as u8
+    }
+    
+    pub fn address_size(&self) -> (result: u8)
+        ensures
+            result == self.spec_address_size(),
+    {
+        ((self.raw >> 7u32) & 0x7) as u8
+    }
+    
+    pub fn is_register(&self) -> (result: bool)
+        ensures
+            result == self.spec_is_register(),
+    {
+        (self.raw >> 10u32) & 0x1 != 0
+    }
+    
+    pub fn segment(&self) -> (result: u8)
+        ensures
+            result == self.spec_segment(),
+    {
+        ((self.raw >> 15u32) & 0x7) as u8
+    }
+    
+    pub fn index_reg(&self) -> (result: Option<u8>)
+        ensures
+            result == self.spec_index_reg(),
+            result is Some ==> result.unwrap() < 16,
+    {
+        let raw = self.raw;
+        assert((raw >> 18u32) & 0xf <= 0xf) by (bit_vector);
+        if (self.raw >> 22u32) & 0x1 != 0 {
+            None
+        } else {
+            Some(((self.raw >> 18u32) & 0xf) as u8)
+        }
+    }
+    
+    pub fn base_reg(&self) -> (result: Option<u8>)
+        ensures
+            result == self.spec_base_reg(),
+            result is Some ==> result.unwrap() < 16,
+    {
+        let raw = self.raw;
+        assert((raw >> 23u32) & 0xf <= 0xf) by (bit_vector);
+        if (self.raw >> 27u32) & 0x1 != 0 {
+            None
+        } else {
+            Some(((self.raw >> 23u32) & 0xf) as u8)
+        }
+    }
+    
+    pub fn reg2(&self) -> (result: u8)
+        ensures
+            result == self.spec_reg2(),
+            result < 16,
+    {
+        let raw = self.raw;
+        assert((raw >> 28u32) & 0xf <= 0xf) by (bit_vector);
+        ((self.raw >> 28u32) & 0xf) as u8
+    }
+}
+
+/// 规范函数：本次退出的 exit qualification
+pub uninterp spec fn spec_exit_qualification() -> u64;
+
+#[verifier::external_body]
+pub fn read_exit_qualification() -> (result: u64)
+    ensures
+        result == spec_exit_qualification(),
+{
+    0  // 硬件操作：VMREAD EXIT_QUALIFICATION
+}
+
+/// EPT violation 退出的 exit qualification（SDM 28.2.1 Table 28-7）
+#[derive(Clone, Copy)]
+pub struct EptViolationQual {
+    /// bits 2:0：引起违例的访问是读 / 写 / 取指
+    pub read: bool,
+    pub write: bool,
+    pub exec: bool,
+    /// bit 7：guest linear-address 字段有效
+    pub gla_valid: bool,
+    /// bit 12：NMI unblocking due to IRET，引起违例的是一条已经解除 NMI 阻塞的 IRET
+    pub nmi_unblocking: bool,
+}
+
+/// 规范函数：解码 EPT violation 的 exit qualification
+pub open spec fn spec_decode_ept_violation(qual: u64) -> EptViolationQual {
+    EptViolationQual {
+        read: qual & 0x1 != 0,
+        write: (qual >> 1u64) & 0x1 != 0,
+        exec: (qual >> 2u64) & 0x1 != 0,
+        gla_valid: (qual >> 7u64) & 0x1 != 0,
+        nmi_unblocking: (qual >> 12u64) & 0x1 != 0,
+    }
+}
+
+pub fn decode_ept_violation(qual: u64) -> (result: EptViolationQual)
+    ensures
+        result == spec_decode_ept_violation(qual),
+{
+    EptViolationQual {
+        read: qual & 0x1 != 0,
+        write: (qual >> 1u64) & 0x1 != 0,
+        exec: (qual >> 2u64) & 0x1 != 0,
+        gla_valid: (qual >> 7u64) & 0x1 != 0,
+        nmi_unblocking: (qual >> 12u64) & 0x1 != 0,
+    }
+}
+
+/// 规范函数：本次退出报告 guest 的 IRET 已解除 NMI 阻塞却没有执行完（NMI unblocking due to IRET）
+/// 
+/// 异常退出在 exit interruption-information bit 12 报告，EPT violation 与 PML full 在 exit qualification
+/// bit 12 报告；该位只在 IDT-vectoring information 无效时有意义
+pub open spec fn spec_iret_unblocked_nmi(reason: u32, qual: u64, intr_info: u32, idt_info: u32) -> bool {
+    &&& (idt_info >> 31u32) & 0x1 == 0
+    &&& match ExitReason::spec_from_raw(reason) {
+        Some(ExitReason::ExceptionNmi) => (intr_info >> 31u32) & 0x1 == 1 && (intr_info >> 12u32) & 0x1 != 0,
+        Some(ExitReason::EptViolation) => spec_decode_ept_violation(qual).nmi_unblocking,
+        Some(ExitReason::PmlFull) => (qual >> 12u64) & 0x1 != 0,
+        _ => false,
+    }
+}
+
+pub fn iret_unblocked_nmi(reason: u32, qual: u64, intr_info: u32, idt_info: u32) -> (result: bool)
+    ensures
+        result == spec_iret_unblocked_nmi(reason, qual, intr_info, idt_info),
+{
+    if (idt_info >> 31u32) & 0x1 != 0 {
+        return false;
+    }
+    match ExitReason::from_raw(reason) {
+        Some(ExitReason::ExceptionNmi) => (intr_info >> 31u32) & 0x1 == 1 && (intr_info >> 12u32) & 0x1 != 0,
+        Some(ExitReason::EptViolation) => decode_ept_violation(qual).nmi_unblocking,
+        Some(ExitReason::PmlFull) => (qual >> 12u64) & 0x1 != 0,
+        _ => false,
+    }
+}
+
+/// 规范函数：重新进入 guest 时的 NMI 阻塞状态
+/// 
+/// 被中止的 IRET 已经解除了 NMI 阻塞，重新执行之前必须恢复阻塞，
+/// 否则 NMI 会在旧的 NMI 处理程序返回之前嵌套进来
+pub open spec fn spec_nmi_blocking_on_reentry(blocked: bool, reason: u32, qual: u64, intr_info: u32, idt_info: u32) -> bool {
+    blocked || spec_iret_unblocked_nmi(reason, qual, intr_info, idt_info)
+}
+
+/// 规范函数：本次退出的 guest-physical address 字段
+pub uninterp spec fn spec_guest_physical_address() -> u64;
+
+#[verifier::external_body]
+pub fn read_guest_physical_address() -> (result: u64)
+    ensures
+        result == spec_guest_physical_address(),
+{
+    0  // 硬件操作：VMREAD GUEST_PHYSICAL_ADDRESS
+}
+
+/// 引理：IRET 因 EPT violation 或异常中止时，重新执行 IRET 前 NMI 阻塞与原 IRET 执行前一致
+/// 
+/// NMI 处理程序中（IRET 之前）NMI 必然处于阻塞；IRET 解除阻塞后被打断，
+/// 退出时阻塞已清除，重新进入前恢复为阻塞，再次执行的 IRET 只解除一次
+pub proof fn lemma_iret_reexecution_keeps_nmi_blocked(qual: u64, intr_info: u32, idt_info: u32)
+    requires
+        (idt_info >> 31u32) & 0x1 == 0,
+    ensures
+        spec_decode_ept_violation(qual).nmi_unblocking
+            ==> spec_nmi_blocking_on_reentry(false, EXIT_REASON_EPT_VIOLATION, qual, intr_info, idt_info),
+        (intr_info >> 31u32) & 0x1 == 1 && (intr_info >> 12u32) & 0x1 != 0
+            ==> spec_nmi_blocking_on_reentry(false, EXIT_REASON_EXCEPTION_NMI, qual, intr_info, idt_info),
+        spec_nmi_blocking_on_reentry(true, EXIT_REASON_EPT_VIOLATION, qual, intr_info, idt_info),
+{
+}
+
+/// 任务切换的来源（task-switch exit qualification bits 31:30）
+pub enum TaskSwitchSource {
+    Call,
+    Iret,
+    Jmp,
+    TaskGate,
+}
+
+/// 任务切换退出的 exit qualification
+pub struct TaskSwitchQual {
+    pub selector: u16,  // 新 TSS 的 selector（bits 15:0）
+    pub source: TaskSwitchSource,
+}
+
+/// 规范函数：解码 task-switch exit qualification，保留位（29:16、63:32）非零时为 None
+pub open spec fn spec_decode_task_switch(qual: u64) -> Option<TaskSwitchQual> {
+    if qual & 0xffff_ffff_3fff_0000 != 0 {
+        None
+    } else {
+        let source = (qual >> 30u64) & 0x3;
+        Some(TaskSwitchQual {
+            selector: (qual & 0xffff) as u16,
+            source: if source == 0 {
+                TaskSwitchSource::Call
+            } else if source == 1 {
+                TaskSwitchSource::Iret
+            } else if source == 2 {
+                TaskSwitchSource::Jmp
+            } else {
+                TaskSwitchSource::TaskGate
+            },
+        })
+    }
+}
+
+pub fn decode_task_switch(qual: u64) -> (result: Option<TaskSwitchQual>)
+    ensures
+        result == spec_decode_task_switch(qual),
+{
+    if qual & 0xffff_ffff_3fff_0000 != 0 {
+        return None;
+    }
+    let source = (qual >> 30u64) & 0x3;
+    Some(TaskSwitchQual {
+        selector: (qual & 0xffff) as u16,
+        source: if source == 0 {
+            TaskSwitchSource::Call
+        } else if source == 1 {
+            TaskSwitchSource::Iret
+        } else if source == 2 {
+            TaskSwitchSource::Jmp
+        } else {
+            TaskSwitchSource::TaskGate
+        },
+    })
+}
+
+/// 控制寄存器访问的类型（CR-access exit qualification bits 5:4）
+#[derive(Clone, Copy)]
+pub enum CrAccessType {
+    MovToCr,
+    MovFromCr,
+    Clts,
+    Lmsw,
+}
+
+/// CR 访问退出的 exit qualification
+#[derive(Clone, Copy)]
+pub struct CrAccessQual {
+    /// bits 3:0：控制寄存器编号
+    pub cr: u8,
+    pub access_type: CrAccessType,
+    /// bits 11:8：MOV CR 的通用寄存器（指令编码中的寄存器号）
+    pub gpr: u8,
+}
+
+/// 规范函数：解码 CR 访问退出的 exit qualification
+pub open spec fn spec_decode_cr_access(qual: u64) -> CrAccessQual {
+    let access = (qual >> 4u64) & 0x3;
+    CrAccessQual {
+        cr: (qual & 0xf) as u8,
+        access_type: if access == 0 {
+            CrAccessType::MovToCr
+        } else if access == 1 {
+            CrAccessType::MovFromCr
+        } else if access == 2 {
+            CrAccessType::Clts
+        } else {
+            CrAccessType::Lmsw
+        },
+        gpr: ((qual >> 8u64) & 0xf) as u8,
+    }
+}
+
+pub fn decode_cr_access(qual: u64) -> (result: CrAccessQual)
+    ensures
+        result == spec_decode_cr_access(qual),
+        result.gpr < 16,
+{
+    let access = (qual >> 4u64) & 0x3;
+    assert((qual >> 8u64) & 0xf <= 0xf) by (bit_vector);
+    CrAccessQual {
+        cr: (qual & 0xf) as u8,
+        access_type: if access == 0 {
+            CrAccessType::MovToCr
+        } else if access == 1 {
+            CrAccessType::MovFromCr
+        } else if access == 2 {
+            CrAccessType::Clts
+        } else {
+            CrAccessType::Lmsw
+        },
+        gpr: ((qual >> 8u64) & 0xf) as u8,
+    }
+}
+
+/// MOV DR 退出的 exit qualification
+#[derive(Clone, Copy)]
+pub struct DrAccessQual {
+    /// bits 2:0：调试寄存器编号
+    pub dr: u8,
+    /// bit 4：0 = MOV to DR，1 = MOV from DR
+    pub to_dr: bool,
+    /// bits 11:8：通用寄存器（指令编码中的寄存器号）
+    pub gpr: u8,
+}
+
+/// 规范函数：解码 MOV DR 退出的 exit qualification
+pub open spec fn spec_decode_dr_access(qual: u64) -> DrAccessQual {
+    DrAccessQual {
+        dr: (qual & 0x7) as u8,
+        to_dr: (qual >> 4u64) & 0x1 == 0,
+        gpr: ((qual >> 8u64) & 0xf) as u8,
+    }
+}
+
+pub fn decode_dr_access(qual: u64) -> (result: DrAccessQual)
+    ensures
+        result == spec_decode_dr_access(qual),
+        result.dr < 8,
+        result.gpr < 16,
+{
+    assert(qual & 0x7 <= 0x7) by (bit_vector);
+    assert((qual >> 8u64) & 0xf <= 0xf) by (bit_vector);
+    DrAccessQual {
+        dr: (qual & 0x7) as u8,
+        to_dr: (qual >> 4u64) & 0x1 == 0,
+        gpr: ((qual >> 8u64) & 0xf) as u8,
+    }
+}
+
+/// 规范函数：本次退出的 instruction-information 字段
+pub uninterp spec fn spec_exit_instr_info() -> u32;
+
+#[verifier::external_body]
+pub fn read_exit_instr_info() -> (result: u32)
+    ensures
+        result == spec_exit_instr_info(),
+{
+    0  // 硬件操作：VMREAD VMX_INSTRUCTION_INFO
+}
+
+/// 规范函数：本次退出的指令长度
+pub uninterp spec fn spec_exit_instr_len() -> u8;
+
+#[verifier::external_body]
+pub fn read_exit_instr_len() -> (result: u8)
+    ensures
+        result == spec_exit_instr_len(),
+        1 <= result <= 15,
+{
+    1  // 硬件操作：VMREAD VM_EXIT_INSTRUCTION_LEN
+}
+
+/// 寄存器操作数的大小
+#[derive(Clone, Copy)]
+pub enum OperandSize {
+    Bits16,
+    Bits32,
+    Bits64,
+}
+
+/// RDRAND / RDSEED 的目的操作数（instruction-information：bits 6:3 寄存器，bits 12:11 大小）
+#[derive(Clone, Copy)]
+pub struct RandDest {
+    /// 指令编码中的寄存器号（0 = rax … 4 = rsp … 15 = r15）
+    pub reg: u8,
+    pub size: OperandSize,
+}
+
+/// 规范函数：解码 RDRAND / RDSEED 退出的 instruction-information，大小为 3 时非法
+pub open spec fn spec_decode_rand_instr_info(info: u32) -> Option<RandDest> {
+    let size = (info >> 11u32) & 0x3;
+    if size == 3 {
+        None
+    } else {
+        Some(RandDest {
+            reg: ((info >> 3u32) & 0xf) as u8,
+            size: if size == 0 {
+                OperandSize::Bits16
+            } else if size == 1 {
+                OperandSize::Bits32
+            } else {
+                OperandSize::Bits64
+            },
+        })
+    }
+}
+
+pub fn decode_rand_instr_info(info: u32) -> (result: Option<RandDest>)
+    ensures
+        result == spec_decode_rand_instr_info(info),
+        result matches Some(dest) ==> dest.reg < 16,
+{
+    let size = (info >> 11u32) & 0x3;
+    if size == 3 {
+        return None;
+    }
+    assert((info >> 3u32) & 0xf <= 0xf) by (bit_vector);
+    Some(RandDest {
+        reg: ((info >> 3u32) & 0xf) as u8,
+        size: if size == 0 {
+            OperandSize::Bits16
+        } else if size == 1 {
+            OperandSize::Bits32
+        } else {
+            OperandSize::Bits64
+        },
+    })
+}
+
+/// 规范函数：按操作数大小写寄存器后的值
+/// 
+/// 64 位整体写入；32 位写入零扩展到 64 位；16 位只替换低 16 位
+pub open spec fn spec_sized_write(old: u64, val: u64, size: OperandSize) -> u64 {
+    match size {
+        OperandSize::Bits16 => (old & !0xffffu64) | (val & 0xffff),
+        OperandSize::Bits32 => val & 0xffff_ffff,
+        OperandSize::Bits64 => val,
+    }
+}
+
+pub fn sized_write(old: u64, val: u64, size: OperandSize) -> (result: u64)
+    ensures
+        result == spec_sized_write(old, val, size),
+{
+    match size {
+        OperandSize::Bits16 => (old & !0xffffu64) | (val & 0xffff),
+        OperandSize::Bits32 => val & 0xffff_ffff,
+        OperandSize::Bits64 => val,
+    }
+}
+
+/// 规范函数：RDRAND / RDSEED 之后的 RFLAGS（清除 OF/SF/ZF/AF/PF，CF 表示是否取得随机数）
+pub open spec fn spec_rand_rflags(rflags: u64, success: bool) -> u64 {
+    (rflags & !RFLAGS_ARITH) | (if success { RFLAGS_CF } else { 0 })
+}
+
+pub fn rand_rflags(rflags: u64, success: bool) -> (result: u64)
+    ensures
+        result == spec_rand_rflags(rflags, success),
+{
+    (rflags & !RFLAGS_ARITH) | (if success { RFLAGS_CF } else { 0 })
+}
+
+/// 规范函数：host 本次为 RDRAND（seed = false）/ RDSEED（seed = true）提供的随机数，None 表示暂无熵
+pub uninterp spec fn spec_host_entropy(seed: bool) -> Option<u64>;
+
+#[verifier::external_body]
+pub fn read_host_entropy(seed: bool) -> (result: Option<u64>)
+    ensures
+        result == spec_host_entropy(seed),
+{
+    let _ = seed;
+    None  // 硬件操作：host 执行 rdrand / rdseed，CF = 0 时返回 None
+}
+
+/// 等待注入的事件
+#[derive(Clone, Copy)]
+pub enum PendingEvent {
+    Exception { vector: u8, error_code: Option<u32> },
+    Nmi,
+    Interrupt { vector: u8 },
+}
+
+impl PendingEvent {
+    /// 规范函数：写入 VM-entry interruption-information 的事件
+    pub open spec fn spec_to_injected(self) -> InjectedEvent {
+        match self {
+            PendingEvent::Exception { vector, error_code } => InjectedEvent::spec_exception(vector, error_code),
+            PendingEvent::Nmi => InjectedEvent::spec_nmi(),
+            PendingEvent::Interrupt { vector } => InjectedEvent {
+                event_type: EventType::ExternalInterrupt,
+                vector,
+                error_code: None,
+            },
+        }
+    }
+    
+    pub fn to_injected(&self) -> (result: InjectedEvent)
+        ensures
+            result == self.spec_to_injected(),
+    {
+        match *self {
+            PendingEvent::Exception { vector, error_code } => InjectedEvent {
+                event_type: EventType::HardwareException,
+                vector,
+                error_code,
+            },
+            PendingEvent::Nmi => InjectedEvent::nmi(),
+            PendingEvent::Interrupt { vector } => InjectedEvent {
+                event_type: EventType::ExternalInterrupt,
+                vector,
+                error_code: None,
+            },
+        }
+    }
+}
+
+/// entry 前对 IRR 中断的处理结果
+pub enum InjectionDecision {
+    /// 已注入待注入集合中优先级最高的事件（异常或 NMI 等）
+    InjectedPending,
+    /// NMI 被阻塞，请求 NMI 窗口退出
+    OpenNmiWindow,
+    /// 已注入该向量
+    Injected(u8),
+    /// guest 暂不可中断，请求中断窗口退出
+    OpenInterruptWindow,
+    /// 没有可投递的中断，或本次 entry 已有事件
+    Nothing,
+}
+
+/// 各类待注入事件（每类最多一个）
+#[derive(Clone, Copy)]
+pub struct PendingEvents {
+    pub exception: Option<(u8, Option<u32>)>,
+    pub nmi: bool,
+    pub interrupt: Option<u8>,
+}
+
+impl PendingEvents {
+    pub fn new() -> (result: Self)
+        ensures
+            result.next_event_to_inject() is None,
+    {
+        PendingEvents { exception: None, nmi: false, interrupt: None }
+    }
+    
+    /// 规范函数：下一个应注入的事件（异常 > NMI > 可屏蔽中断）
+    pub open spec fn next_event_to_inject(&self) -> Option<PendingEvent> {
+        if self.exception is Some {
+            let (vector, error_code) = self.exception.unwrap();
+            Some(PendingEvent::Exception { vector, error_code })
+        } else if self.nmi {
+            Some(PendingEvent::Nmi)
+        } else if self.interrupt is Some {
+            Some(PendingEvent::Interrupt { vector: self.interrupt.unwrap() })
+        } else {
+            None
+        }
+    }
+    
+    /// 规范函数：移除 next_event_to_inject 返回的事件后剩余的集合
+    pub open spec fn spec_without_next(&self) -> PendingEvents {
+        if self.exception is Some {
+            PendingEvents { exception: None, ..*self }
+        } else if self.nmi {
+            PendingEvents { nmi: false, ..*self }
+        } else {
+            PendingEvents { interrupt: None, ..*self }
+        }
+    }
+    
+    pub fn next_event(&self) -> (result: Option<PendingEvent>)
+        ensures
+            result == self.next_event_to_inject(),
+    {
+        match self.exception {
+            Some((vector, error_code)) => Some(PendingEvent::Exception { vector, error_code }),
+            None => {
+                if self.nmi {
+                    Some(PendingEvent::Nmi)
+                } else {
+                    match self.interrupt {
+                        Some(vector) => Some(PendingEvent::Interrupt { vector }),
+                        None => None,
+                    }
+                }
+            },
+        }
+    }
+    
+    /// 取出优先级最高的事件
+    pub fn take_next(&mut self) -> (result: Option<PendingEvent>)
+        ensures
+            result == old(self).next_event_to_inject(),
+            *self == old(self).spec_without_next(),
+    {
+        let next = self.next_event();
+        if self.exception.is_some() {
+            self.exception = None;
+        } else if self.nmi {
+            self.nmi = false;
+        } else {
+            self.interrupt = None;
+        }
+        next
+    }
+}
+
+/// 规范函数：IDT-vectoring information 字段描述的在途事件（SDM 28.2.4）
+/// 
+/// bit 31 有效，10:8 类型，11 错误码有效，7:0 向量。
+/// 软件中断/异常（类型 4~6）不需要重新注入：guest RIP 仍指向产生它的指令，重新执行即可
+pub open spec fn spec_idt_vectoring_event(info: u32, error_code: u32) -> Option<PendingEvent> {
+    let vector = (info & 0xff) as u8;
+    let ty = (info >> 8u32) & 0x7;
+    let ec = if (info >> 11u32) & 0x1 != 0 { Some(error_code) } else { None };
+    if (info >> 31u32) & 0x1 == 0 {
+        None
+    } else if ty == 0 {
+        Some(PendingEvent::Interrupt { vector })
+    } else if ty == 2 {
+        Some(PendingEvent::Nmi)
+    } else if ty == 3 {
+        Some(PendingEvent::Exception { vector, error_code: ec })
+    } else {
+        None
+    }
+}
+
+pub fn idt_vectoring_event(info: u32, error_code: u32) -> (result: Option<PendingEvent>)
+    ensures
+        result == spec_idt_vectoring_event(info, error_code),
+{
+    let vector = (info & 0xff) as u8;
+    let ty = (info >> 8u32) & 0x7;
+    let ec = if (info >> 11u32) & 0x1 != 0 { Some(error_code) } else { None };
+    if (info >> 31u32) & 0x1 == 0 {
+        None
+    } else if ty == 0 {
+        Some(PendingEvent::Interrupt { vector })
+    } else if ty == 2 {
+        Some(PendingEvent::Nmi)
+    } else if ty == 3 {
+        Some(PendingEvent::Exception { vector, error_code: ec })
+    } else {
+        None
+    }
+}
+
+/// 规范函数：本次退出的 IDT-vectoring information / error code 字段
+pub uninterp spec fn spec_idt_vectoring_info() -> u32;
+pub uninterp spec fn spec_idt_vectoring_error_code() -> u32;
+
+#[verifier::external_body]
+pub fn read_idt_vectoring_info() -> (result: u32)
+    ensures
+        result == spec_idt_vectoring_info(),
+{
+    0  // 硬件操作：VMREAD IDT_VECTORING_INFO
+}
+
+#[verifier::external_body]
+pub fn read_idt_vectoring_error_code() -> (result: u32)
+    ensures
+        result == spec_idt_vectoring_error_code(),
+{
+    0  // 硬件操作：VMREAD IDT_VECTORING_ERROR_CODE
+}
+
+/// 规范函数：本次退出的 VM-exit interruption information 字段
+pub uninterp spec fn spec_exit_interruption_info() -> u32;
+
+#[verifier::external_body]
+pub fn read_exit_interruption_info() -> (result: u32)
+    ensures
+        result == spec_exit_interruption_info(),
+{
+    0  // 硬件操作：VMREAD VM_EXIT_INTR_INFO
+}
+
+/// 规范函数：该字段描述的是 NMI（bit 31 有效，bits 10:8 类型为 2）
+pub open spec fn spec_exit_is_nmi(info: u32) -> bool {
+    &&& (info >> 31u32) & 0x1 == 1
+    &&& (info >> 8u32) & 0x7 == 2
+}
+
+pub fn exit_is_nmi(info: u32) -> (result: bool)
+    ensures
+        result == spec_exit_is_nmi(info),
+{
+    (info >> 31u32) & 0x1 == 1 && (info >> 8u32) & 0x7 == 2
+}
+
+/// 把 NMI exiting 截获的 NMI 交给 host 的 NMI 处理流程
+#[verifier::external_body]
+pub fn forward_nmi_to_host() {
+    // 硬件操作：int 2，经 host IDT 分发
+}
+
+/// 规范函数：acknowledge interrupt on exit 时该字段给出的外部中断向量（bit 31 有效，7:0 向量）
+pub open spec fn spec_exit_intr_vector(info: u32) -> Option<u8> {
+    if (info >> 31u32) & 0x1 == 0 {
+        None
+    } else {
+        Some((info & 0xff) as u8)
+    }
+}
+
+pub fn exit_intr_vector(info: u32) -> (result: Option<u8>)
+    ensures
+        result == spec_exit_intr_vector(info),
+{
+    if (info >> 31u32) & 0x1 == 0 {
+        None
+    } else {
+        Some((info & 0xff) as u8)
+    }
+}
+
+/// 外部中断向量的归属表：guest@[v] 为真表示 v 是分配给本 zone 设备的中断，其余向量归 host
+/// 
+/// 0..32 是异常向量，不可能是设备中断，总是归 host
+pub struct VectorOwnership {
+    pub guest: VectorBitmap,
+}
+
+impl VectorOwnership {
+    /// 所有向量都归 host
+    pub fn new() -> (result: Self)
+        ensures
+            result.inv(),
+            forall|v: u8| #[trigger] result.spec_host_owns(v),
+    {
+        VectorOwnership { guest: VectorBitmap::new() }
+    }
+    
+    /// 归属表不变式：异常向量不分配给 guest
+    pub open spec fn inv(&self) -> bool {
+        forall|v: int| 0 <= v < 32 ==> !#[trigger] self.guest@[v]
+    }
+    
+    /// 规范函数：该向量归 host 所有
+    pub open spec fn spec_host_owns(&self, vector: u8) -> bool {
+        !self.guest@[vector as int]
+    }
+    
+    pub fn host_owns(&self, vector: u8) -> (result: bool)
+        ensures
+            result == self.spec_host_owns(vector),
+    {
+        !self.guest.test(vector as usize)
+    }
+    
+    /// 把设备中断向量分配给本 zone，其余向量的归属不变
+    pub fn assign_to_guest(&mut self, vector: u8)
+        requires
+            old(self).inv(),
+            vector >= 32,
+        ensures
+            self.inv(),
+            !self.spec_host_owns(vector),
+            forall|v: u8| v != vector ==> #[trigger] self.spec_host_owns(v) == old(self).spec_host_owns(v),
+    {
+        self.guest.set(vector as usize, true);
+    }
+}
+
+/// 将已应答的外部中断交给 host 的中断处理流程
+#[verifier::external_body]
+pub fn forward_to_host(vector: u8) {
+    // 硬件操作：经 host IDT 分发该向量
+}
+
+/// #DF 的向量
+pub const DF_VECTOR: u8 = 8;
+/// #PF 的向量
+pub const PF_VECTOR: u8 = 14;
+
+/// 规范函数：contributory 异常（#DE、#TS、#NP、#SS、#GP，SDM 表 6-4）
+pub open spec fn spec_is_contributory(vector: u8) -> bool {
+    vector == 0 || (10 <= vector <= 13)
+}
+
+pub fn is_contributory(vector: u8) -> (result: bool)
+    ensures
+        result == spec_is_contributory(vector),
+{
+    vector == 0 || (10 <= vector && vector <= 13)
+}
+
+/// 规范函数：投递 first 的过程中又产生 second 时合并为 #DF（SDM 表 6-5）
+/// 
+/// contributory 之后的 contributory、#PF 之后的 contributory 或 #PF；其余组合串行处理
+pub open spec fn spec_is_double_fault(first: u8, second: u8) -> bool {
+    ||| spec_is_contributory(first) && spec_is_contributory(second)
+    ||| first == PF_VECTOR && (spec_is_contributory(second) || second == PF_VECTOR)
+}
+
+pub fn is_double_fault(first: u8, second: u8) -> (result: bool)
+    ensures
+        result == spec_is_double_fault(first, second),
+{
+    (is_contributory(first) && is_contributory(second))
+        || (first == PF_VECTOR && (is_contributory(second) || second == PF_VECTOR))
+}
+
+impl PendingEvents {
+    /// 规范函数：把事件放入待注入集合后的结果，None 表示无法排队（状态不变）
+    /// 
+    /// - 异常：槽位空闲时放入；已有异常且两者构成 #DF 时合并为 #DF(0)；
+    ///   已有 #DF（再合并即三重错误）或其余组合时槽位被占用
+    /// - NMI：硬件只锁存一个待处理 NMI，重复的 NMI 合并
+    /// - 可屏蔽中断：槽位被占用时无法排队
+    pub open spec fn spec_push(&self, event: PendingEvent) -> Option<PendingEvents> {
+        match event {
+            PendingEvent::Exception { vector, error_code } => {
+                if self.exception is None {
+                    Some(PendingEvents { exception: Some((vector, error_code)), ..*self })
+                } else if spec_is_double_fault(self.exception.unwrap().0, vector) {
+                    Some(PendingEvents { exception: Some((DF_VECTOR, Some(0u32))), ..*self })
+                } else {
+                    None
+                }
+            },
+            PendingEvent::Nmi => Some(PendingEvents { nmi: true, ..*self }),
+            PendingEvent::Interrupt { vector } => {
+                if self.interrupt is None {
+                    Some(PendingEvents { interrupt: Some(vector), ..*self })
+                } else {
+                    None
+                }
+            },
+        }
+    }
+    
+    /// 把事件放入待注入集合；无法排队时返回 Err 且状态不变
+    pub fn push(&mut self, event: PendingEvent) -> (result: Result<(), ()>)
+        ensures
+            old(self).spec_push(event) is Some ==> result.is_ok() && *self == old(self).spec_push(event).unwrap(),
+            old(self).spec_push(event) is None ==> result.is_err() && *self == *old(self),
+    {
+        match event {
+            PendingEvent::Exception { vector, error_code } => {
+                match self.exception {
+                    None => {
+                        self.exception = Some((vector, error_code));
+                    },
+                    Some((first, _)) => {
+                        if !is_double_fault(first, vector) {
+                            return Err(());
+                        }
+                        self.exception = Some((DF_VECTOR, Some(0u32)));
+                    },
+                }
+            },
+            PendingEvent::Nmi => {
+                self.nmi = true;
+            },
+            PendingEvent::Interrupt { vector } => {
+                if self.interrupt.is_some() {
+                    return Err(());
+                }
+                self.interrupt = Some(vector);
+            },
+        }
+        Ok(())
+    }
+}
+
+/// 引理：已有待注入异常时，#PF 之后的 #GP 合并为 #DF，已有 #DF 时再来的 #GP 无法排队，
+/// 已有可屏蔽中断时第二个中断无法排队
+pub proof fn lemma_push_never_overwrites(p: PendingEvents, ec: Option<u32>, v: u8)
+    ensures
+        p.exception == Some((PF_VECTOR, ec)) ==> p.spec_push(PendingEvent::Exception { vector: 13, error_code: Some(0) })
+            == Some(PendingEvents { exception: Some((DF_VECTOR, Some(0u32))), ..p }),
+        p.exception == Some((DF_VECTOR, ec)) ==> p.spec_push(PendingEvent::Exception { vector: 13, error_code: Some(0) }) is None,
+        p.interrupt is Some ==> p.spec_push(PendingEvent::Interrupt { vector: v }) is None,
+{
+}
+
+/// 引理：高优先级事件不会被低优先级事件饿死
+pub proof fn lemma_pending_priority(p: PendingEvents)
+    ensures
+        p.exception is Some ==> p.next_event_to_inject().unwrap() is Exception,
+        p.exception is None && p.nmi ==> p.next_event_to_inject() == Some(PendingEvent::Nmi),
+        p.next_event_to_inject() is Some && p.next_event_to_inject().unwrap() is Interrupt
+            ==> p.exception is None && !p.nmi,
+{
+}
+
+/// 退出原因字段 bit 31：VM entry 失败（guest 从未运行）
+pub const EXIT_REASON_ENTRY_FAILURE: u32 = 1 << 31;
+/// 退出原因字段 bits 15:0：基本退出原因
+pub const EXIT_REASON_BASIC_MASK: u32 = 0xffff;
+
+/// VM entry 失败的子原因
+#[derive(Clone, Copy)]
+pub enum EntryFailureKind {
+    /// 基本原因 33：guest-state 检查失败
+    InvalidGuestState,
+    /// 基本原因 34：VM-entry MSR-load 区域加载失败
+    MsrLoading,
+    /// 基本原因 41：entry 期间发生 machine-check
+    MachineCheck,
+}
+
+/// 规范函数：IA32_MCG_STATUS.MCIP，machine-check 正在处理中
+pub uninterp spec fn spec_mce_pending() -> bool;
+
+#[verifier::external_body]
+pub fn mce_pending() -> (result: bool)
+    ensures
+        result == spec_mce_pending(),
+{
+    false  // 硬件操作：rdmsr IA32_MCG_STATUS，取 bit 2
+}
+
+/// 规范函数：退出原因字段表示 VM entry 失败
+pub open spec fn spec_is_entry_failure(raw: u32) -> bool {
+    raw & EXIT_REASON_ENTRY_FAILURE != 0
+}
+
+pub fn is_entry_failure(raw: u32) -> (result: bool)
+    ensures
+        result == spec_is_entry_failure(raw),
+{
+    raw & EXIT_REASON_ENTRY_FAILURE != 0
+}
+
+/// 规范函数：VM entry 失败的子原因（不是 entry 失败或基本原因未知时为 None）
+pub open spec fn spec_entry_failure_kind(raw: u32) -> Option<EntryFailureKind> {
+    if !spec_is_entry_failure(raw) {
+        None
+    } else {
+        let basic = raw & EXIT_REASON_BASIC_MASK;
+        if basic == 33 {
+            Some(EntryFailureKind::InvalidGuestState)
+        } else if basic == 34 {
+            Some(EntryFailureKind::MsrLoading)
+        } else if basic == 41 {
+            Some(EntryFailureKind::MachineCheck)
+        } else {
+            None
+        }
+    }
+}
+
+pub fn entry_failure_kind(raw: u32) -> (result: Option<EntryFailureKind>)
+    ensures
+        result == spec_entry_failure_kind(raw),
+{
+    if !is_entry_failure(raw) {
+        return None;
+    }
+    let basic = raw & EXIT_REASON_BASIC_MASK;
+    if basic == 33 {
+        Some(EntryFailureKind::InvalidGuestState)
+    } else if basic == 34 {
+        Some(EntryFailureKind::MsrLoading)
+    } else if basic == 41 {
+        Some(EntryFailureKind::MachineCheck)
+    } else {
+        None
+    }
+}
+
+/// 引理：三种 entry 失败编码分别解码为三个不同的子原因
+pub proof fn lemma_entry_failure_kinds()
+    ensures
+        spec_entry_failure_kind(EXIT_REASON_ENTRY_FAILURE | 33) == Some(EntryFailureKind::InvalidGuestState),
+        spec_entry_failure_kind(EXIT_REASON_ENTRY_FAILURE | 34) == Some(EntryFailureKind::MsrLoading),
+        spec_entry_failure_kind(EXIT_REASON_ENTRY_FAILURE | 41) == Some(EntryFailureKind::MachineCheck),
+        spec_entry_failure_kind(33) is None,
+{
+    assert((EXIT_REASON_ENTRY_FAILURE | 33) & EXIT_REASON_ENTRY_FAILURE != 0) by (bit_vector);
+    assert((EXIT_REASON_ENTRY_FAILURE | 33) & EXIT_REASON_BASIC_MASK == 33) by (bit_vector);
+    assert((EXIT_REASON_ENTRY_FAILURE | 34) & EXIT_REASON_ENTRY_FAILURE != 0) by (bit_vector);
+    assert((EXIT_REASON_ENTRY_FAILURE | 34) & EXIT_REASON_BASIC_MASK == 34) by (bit_vector);
+    assert((EXIT_REASON_ENTRY_FAILURE | 41) & EXIT_REASON_ENTRY_FAILURE != 0) by (bit_vector);
+    assert((EXIT_REASON_ENTRY_FAILURE | 41) & EXIT_REASON_BASIC_MASK == 41) by (bit_vector);
+    assert(33u32 & EXIT_REASON_ENTRY_FAILURE == 0) by (bit_vector);
+}
+
+/// 规范函数：原始退出编号是否会被某个已知处理函数处理
+pub open spec fn is_handled_exit(raw: u32) -> bool {
+    match ExitReason::spec_from_raw(raw) {
+        Some(reason) => reason.is_supported(),
+        None => false,
+    }
+}
+
+pub const EXIT_REASON_EXCEPTION_NMI: u32 = 0;
+pub const EXIT_REASON_EXTERNAL_INTERRUPT: u32 = 1;
+pub const EXIT_REASON_GETSEC: u32 = 11;
+pub const EXIT_REASON_EPT_VIOLATION: u32 = 48;
+
+/// 规范函数：退出走快速路径
+/// 
+/// 最频繁的外部中断退出，未开启 acknowledge interrupt on exit，interruption information 无效
+/// （中断仍留在 host 的 LAPIC 中，没有需要交给 guest 的向量）
+pub open spec fn spec_is_fast_path_exit(raw: u32, ctls: VmcsControls, info: u32) -> bool {
+    &&& raw == EXIT_REASON_EXTERNAL_INTERRUPT
+    &&& ctls.pin_ctls.external_interrupt_exiting
+    &&& !ctls.exit_ctls.ack_interrupt_on_exit
+    &&& spec_exit_intr_vector(info) is None
+}
+
+/// 只在前三个条件成立时才 VMREAD interruption information
+pub fn is_fast_path_exit(raw: u32, ctls: &VmcsControls) -> (result: bool)
+    ensures
+        result == spec_is_fast_path_exit(raw, *ctls, spec_exit_interruption_info()),
+{
+    if raw != EXIT_REASON_EXTERNAL_INTERRUPT || !ctls.pin_ctls.external_interrupt_exiting
+        || ctls.exit_ctls.ack_interrupt_on_exit {
+        return false;
+    }
+    (read_exit_interruption_info() >> 31u32) & 0x1 == 0
+}
+
+/// 引理：快速路径的退出在完整分发中落入 ExternalInterrupt 分支
+/// 
+/// 编号解码为 ExternalInterrupt 且属于已处理的退出，该分支的条件成立，且没有已应答的向量；
+/// 没有向量时 handle_external_interrupt 不改变状态并返回 Reenter，apply_emulation_result 对 Reenter 也不改变状态，
+/// 因此直接返回的快速路径与完整分发得到相同的状态
+pub proof fn lemma_fast_path_matches_dispatch(raw: u32, ctls: VmcsControls, info: u32)
+    requires
+        spec_is_fast_path_exit(raw, ctls, info),
+    ensures
+        ExitReason::spec_from_raw(raw) == Some(ExitReason::ExternalInterrupt),
+        is_handled_exit(raw),
+        ExitReason::ExternalInterrupt.spec_dispatch_enabled(ctls, info),
+        spec_exit_intr_vector(info) is None,
+{
+}
+
+/// 引理：dispatcher 放行的退出都是已支持的原因，且它在分发 match 中有自己的分支
+/// 
+/// 分发 match 的通配分支为 unreached()，其前置条件 false 由这里的结论加上各分支的排除得到，
+/// 即该 match 覆盖了全部放行的原因；未放行的原因在 match 之前已交给 handle_unknown
+pub proof fn lemma_exit_dispatch_total(raw: u32, ctls: VmcsControls, info: u32)
+    requires
+        ExitReason::spec_from_raw(raw) is Some,
+        ExitReason::spec_from_raw(raw).unwrap().spec_dispatch_enabled(ctls, info),
+    ensures
+        is_handled_exit(raw),
+        raw < NUM_EXIT_REASONS,
+        !(ExitReason::spec_from_raw(raw).unwrap() is TripleFault),
+        !(ExitReason::spec_from_raw(raw).unwrap() is IoSmi),
+        !(ExitReason::spec_from_raw(raw).unwrap() is OtherSmi),
+{
+}
+
+} // verus!
@@ -0,0 +1,367 @@
+// Verus-verified version of percpu.rs
+// 每 CPU 共享数据：各 vCPU 的 IPI 信箱（跨 vCPU 投递 IPI 的目标）
+
+use vstd::prelude::*;
+use crate::arch::x86_64::*;
+
+verus! {
+
+/// 发往一个 vCPU 的 IPI 信箱
+/// 
+/// vCPU 的 ArchCpu（包括虚拟 LAPIC）只由运行它的物理 CPU 访问，发送方不能直接修改；
+/// 发送方把 IPI 记入目标的信箱并唤醒目标，目标在下一次 entry 前取走并投递到自己的虚拟 LAPIC
+pub struct IpiMailbox {
+    pub cpuid: usize,  // 信箱所属 vCPU 的 CPU ID
+    pub irr: VectorBitmap,  // fixed / lowest-priority 模式的向量
+    pub nmi: bool,
+    pub init: bool,
+    pub sipi: Option<u8>,  // wait-for-SIPI 状态只接受第一个 SIPI
+}
+
+impl IpiMailbox {
+    pub fn new(cpuid: usize) -> (result: Self)
+        ensures
+            result.cpuid == cpuid,
+            result.spec_is_empty(),
+    {
+        IpiMailbox { cpuid, irr: VectorBitmap::new(), nmi: false, init: false, sipi: None }
+    }
+    
+    /// 规范函数：信箱中没有任何 IPI
+    pub open spec fn spec_is_empty(&self) -> bool {
+        &&& forall|v: int| 0 <= v < NUM_VECTORS ==> !#[trigger] self.irr@[v]
+        &&& !self.nmi
+        &&& !self.init
+        &&& self.sipi is None
+    }
+    
+    /// 规范函数：按 icr 记入一个 IPI 之后的信箱
+    /// 
+    /// INIT 作废之前收到的 SIPI；INIT level de-assert 不记入任何东西
+    pub open spec fn spec_posted(&self, old_box: &IpiMailbox, icr: u64) -> bool {
+        let mode = spec_icr_delivery_mode(icr);
+        let vector = spec_icr_vector(icr);
+        if spec_icr_is_vectored(icr) {
+            &&& *self == (IpiMailbox { irr: self.irr, ..*old_box })
+            &&& self.irr@ == old_box.irr@.update(vector as int, true)
+        } else if mode == ICR_DELIVERY_NMI {
+            *self == (IpiMailbox { nmi: true, ..*old_box })
+        } else if mode == ICR_DELIVERY_INIT {
+            if spec_icr_init_deassert(icr) {
+                *self == *old_box
+            } else {
+                *self == (IpiMailbox { init: true, sipi: None, ..*old_box })
+            }
+        } else {
+            *self == (IpiMailbox {
+                sipi: if old_box.sipi is None { Some(vector) } else { old_box.sipi },
+                ..*old_box
+            })
+        }
+    }
+    
+    /// 按 icr 记入一个 IPI
+    pub fn post(&mut self, icr: u64)
+        requires
+            spec_icr_send_valid(icr),
+        ensures
+            self.cpuid == old(self).cpuid,
+            self.spec_posted(old(self), icr),
+    {
+        let mode = icr_delivery_mode(icr);
+        let vector = icr_vector(icr);
+        if icr_is_vectored(icr) {
+            self.irr.set(vector as usize, true);
+        } else if mode == ICR_DELIVERY_NMI {
+            self.nmi = true;
+        } else if mode == ICR_DELIVERY_INIT {
+            if !icr_init_deassert(icr) {
+                self.init = true;
+                self.sipi = None;
+            }
+        } else if self.sipi.is_none() {
+            self.sipi = Some(vector);
+        }
+    }
+}
+
+/// 按 CPU ID 索引的 IPI 信箱数组，只为实际存在的 num_cpus 个 CPU 分配槽位
+pub struct PerCpuArray {
+    pub mailboxes: Vec<IpiMailbox>,
+    pub num_cpus: usize,
+}
+
+impl PerCpuArray {
+    /// 为 num_cpus 个 CPU 各分配一个空信箱，i 号槽位属于 CPU i
+    pub fn new(num_cpus: usize) -> (result: Self)
+        requires
+            num_cpus <= MAX_CPU_NUM,
+        ensures
+            result.is_valid(),
+            result.num_cpus == num_cpus,
+            forall|i: int| 0 <= i < num_cpus ==> #[trigger] result.mailboxes@[i].spec_is_empty(),
+            forall|i: int| 0 <= i < num_cpus ==> #[trigger] result.mailboxes@[i].cpuid as int == i,
+    {
+        let mut mailboxes: Vec<IpiMailbox> = Vec::new();
+        let mut i: usize = 0;
+        while i < num_cpus
+            invariant
+                i <= num_cpus,
+                mailboxes.len() == i,
+                forall|j: int| 0 <= j < i ==> #[trigger] mailboxes@[j].spec_is_empty(),
+                forall|j: int| 0 <= j < i ==> #[trigger] mailboxes@[j].cpuid as int == j,
+            decreases num_cpus - i,
+        {
+            mailboxes.push(IpiMailbox::new(i));
+            i = i + 1;
+        }
+        PerCpuArray { mailboxes, num_cpus }
+    }
+    
+    /// 规范函数：每个存在的 CPU 恰有一个槽位，i 号槽位属于 CPU i
+    pub open spec fn is_valid(&self) -> bool {
+        &&& self.num_cpus <= MAX_CPU_NUM
+        &&& self.mailboxes.len() == self.num_cpus
+        &&& forall|i: int| 0 <= i < self.num_cpus ==> #[trigger] self.mailboxes@[i].cpuid as int == i
+    }
+    
+    /// 规范函数：id 对应一个存在的 CPU
+    pub open spec fn in_range(&self, id: usize) -> bool {
+        id < self.num_cpus
+    }
+    
+    /// 规范函数：sender 按 icr 发送 IPI 之后的信箱，每个目标（包括发送者自己）记入一次
+    pub open spec fn spec_ipi_sent(&self, old_array: &PerCpuArray, sender: usize, icr: u64) -> bool {
+        &&& self.num_cpus == old_array.num_cpus
+        &&& self.mailboxes.len() == old_array.mailboxes.len()
+        &&& forall|j: int| #![trigger self.mailboxes@[j]] 0 <= j < old_array.num_cpus ==>
+            if spec_icr_targets(icr, sender, j as usize) {
+                self.mailboxes@[j].spec_posted(&old_array.mailboxes@[j], icr)
+            } else {
+                self.mailboxes@[j] == old_array.mailboxes@[j]
+            }
+    }
+    
+    /// 按 icr 向 cpu 号 vCPU 的信箱记入 IPI，其他槽位不变
+    pub fn post(&mut self, cpu: usize, icr: u64)
+        requires
+            cpu < old(self).mailboxes.len(),
+            spec_icr_send_valid(icr),
+        ensures
+            self.mailboxes.len() == old(self).mailboxes.len(),
+            self.num_cpus == old(self).num_cpus,
+            self.mailboxes@[cpu as int].cpuid == old(self).mailboxes@[cpu as int].cpuid,
+            self.mailboxes@[cpu as int].spec_posted(&old(self).mailboxes@[cpu as int], icr),
+            forall|j: int| 0 <= j < self.mailboxes.len() && j != cpu
+                ==> #[trigger] self.mailboxes@[j] == old(self).mailboxes@[j],
+    {
+        if icr_is_vectored(icr) {
+            self.set_irr(cpu, icr_vector(icr));
+            return;
+        }
+        let mut mailbox = IpiMailbox::new(cpu);
+        self.mailboxes.set_and_swap(cpu, &mut mailbox);
+        mailbox.post(icr);
+        self.mailboxes.set_and_swap(cpu, &mut mailbox);
+    }
+    
+    /// 按 CPU ID 记入 IPI，越界的 ID（例如来自 guest 的目标）被忽略并返回 false（状态不变）
+    pub fn try_post(&mut self, id: usize, icr: u64) -> (result: bool)
+        requires
+            old(self).is_valid(),
+            spec_icr_send_valid(icr),
+        ensures
+            self.is_valid(),
+            self.num_cpus == old(self).num_cpus,
+            result <==> old(self).in_range(id),
+            !result ==> self.mailboxes@ == old(self).mailboxes@,
+            result ==> self.mailboxes@[id as int].spec_posted(&old(self).mailboxes@[id as int], icr),
+            forall|j: int| 0 <= j < self.mailboxes.len() && j != id
+                ==> #[trigger] self.mailboxes@[j] == old(self).mailboxes@[j],
+    {
+        if id >= self.mailboxes.len() {
+            return false;
+        }
+        self.post(id, icr);
+        true
+    }
+    
+    /// 按 CPU ID 取信箱
+    pub fn get_cpu(&self, id: usize) -> (result: &IpiMailbox)
+        requires
+            self.is_valid(),
+            self.in_range(id),
+        ensures
+            *result == self.mailboxes@[id as int],
+            result.cpuid == id,
+    {
+        &self.mailboxes[id]
+    }
+    
+    /// 按 CPU ID 取信箱，越界的 ID（例如 guest 给出的 IPI 目标）返回 None
+    pub fn try_get_cpu(&self, id: usize) -> (result: Option<&IpiMailbox>)
+        requires
+            self.is_valid(),
+        ensures
+            result is Some <==> self.in_range(id),
+            result matches Some(mailbox) ==> *mailbox == self.mailboxes@[id as int] && mailbox.cpuid == id,
+    {
+        if id < self.mailboxes.len() {
+            Some(&self.mailboxes[id])
+        } else {
+            None
+        }
+    }
+    
+    /// 按 CPU ID 取该 vCPU 等待置入虚拟 LAPIC IRR 的向量
+    pub fn get_apic(&self, id: usize) -> (result: &VectorBitmap)
+        requires
+            self.is_valid(),
+            self.in_range(id),
+        ensures
+            *result == self.mailboxes@[id as int].irr,
+    {
+        &self.mailboxes[id].irr
+    }
+    
+    /// 按 CPU ID 取等待置入 IRR 的向量，越界的 ID（例如来自 guest 的数据）返回 None
+    pub fn try_get_apic(&self, id: usize) -> (result: Option<&VectorBitmap>)
+        requires
+            self.is_valid(),
+        ensures
+            result is Some <==> self.in_range(id),
+            result matches Some(irr) ==> *irr == self.mailboxes@[id as int].irr,
+    {
+        if id < self.mailboxes.len() {
+            Some(&self.mailboxes[id].irr)
+        } else {
+            None
+        }
+    }
+    
+    /// 按 CPU ID 把 vector 记入信箱，越界的 ID 被忽略并返回 false（状态不变）
+    pub fn try_set_irr(&mut self, id: usize, vector: u8) -> (result: bool)
+        requires
+            old(self).is_valid(),
+        ensures
+            self.is_valid(),
+            self.num_cpus == old(self).num_cpus,
+            result <==> old(self).in_range(id),
+            !result ==> self.mailboxes@ == old(self).mailboxes@,
+            result ==> self.mailboxes@[id as int].irr@ == old(self).mailboxes@[id as int].irr@.update(vector as int, true),
+            result ==> self.mailboxes@[id as int] == (IpiMailbox {
+                irr: self.mailboxes@[id as int].irr,
+                ..old(self).mailboxes@[id as int]
+            }),
+            forall|j: int| 0 <= j < self.mailboxes.len() && j != id
+                ==> #[trigger] self.mailboxes@[j] == old(self).mailboxes@[j],
+    {
+        if id >= self.mailboxes.len() {
+            return false;
+        }
+        self.set_irr(id, vector);
+        true
+    }
+    
+    /// 将 vector 记入 cpu 号 vCPU 信箱的 IRR，其他槽位不变
+    pub fn set_irr(&mut self, cpu: usize, vector: u8)
+        requires
+            cpu < old(self).mailboxes.len(),
+        ensures
+            self.mailboxes.len() == old(self).mailboxes.len(),
+            self.num_cpus == old(self).num_cpus,
+            self.mailboxes@[cpu as int].irr@ == old(self).mailboxes@[cpu as int].irr@.update(vector as int, true),
+            self.mailboxes@[cpu as int] == (IpiMailbox {
+                irr: self.mailboxes@[cpu as int].irr,
+                ..old(self).mailboxes@[cpu as int]
+            }),
+            forall|j: int| 0 <= j < self.mailboxes.len() && j != cpu
+                ==> #[trigger] self.mailboxes@[j] == old(self).mailboxes@[j],
+    {
+        let mut mailbox = IpiMailbox::new(cpu);
+        self.mailboxes.set_and_swap(cpu, &mut mailbox);
+        mailbox.irr.set(vector as usize, true);
+        self.mailboxes.set_and_swap(cpu, &mut mailbox);
+    }
+    
+    /// 取走 cpu 号 vCPU 信箱中的全部 IPI，槽位变为空
+    pub fn take(&mut self, cpu: usize) -> (result: IpiMailbox)
+        requires
+            old(self).is_valid(),
+            old(self).in_range(cpu),
+        ensures
+            self.is_valid(),
+            self.num_cpus == old(self).num_cpus,
+            result == old(self).mailboxes@[cpu as int],
+            self.mailboxes@[cpu as int].spec_is_empty(),
+            forall|j: int| 0 <= j < self.mailboxes.len() && j != cpu
+                ==> #[trigger] self.mailboxes@[j] == old(self).mailboxes@[j],
+    {
+        let mut mailbox = IpiMailbox::new(cpu);
+        self.mailboxes.set_and_swap(cpu, &mut mailbox);
+        mailbox
+    }
+}
+
+/// 向 cpu 号物理 CPU 发送 host IPI，使其尽快取走信箱（信任边界）
+/// 
+/// 目标正在运行 guest 时产生外部中断退出，在 wait_for_wake_event 中等待时被唤醒；
+/// 两种情况都会在下一次 entry 前经 drain_ipis 取走信箱
+#[verifier::external_body]
+pub fn notify_cpu(cpu: usize)
+    requires
+        cpu < MAX_CPU_NUM,
+{
+    // 硬件操作：向 cpu 的物理 LAPIC 写 ICR（host 保留的通知向量）
+}
+
+/// 引理：num_cpus 本身不是合法的 CPU ID，get_cpu(num_cpus) 与 get_apic(num_cpus) 不满足前置条件，
+/// try_get_cpu(num_cpus) 返回 None
+pub proof fn lemma_num_cpus_out_of_range(array: PerCpuArray)
+    requires
+        array.is_valid(),
+    ensures
+        !array.in_range(array.num_cpus),
+        forall|id: usize| #[trigger] array.in_range(id) ==> id < MAX_CPU_NUM,
+{
+}
+
+/// 引理：0..num_cpus 中不同的槽位属于不同的 CPU
+pub proof fn lemma_slot_cpuids_distinct(array: PerCpuArray, i: int, j: int)
+    requires
+        array.is_valid(),
+        0 <= i < array.num_cpus,
+        0 <= j < array.num_cpus,
+        i != j,
+    ensures
+        array.mailboxes@[i].cpuid != array.mailboxes@[j].cpuid,
+{
+}
+
+/// 引理：运行 0..num_cpus 中不同槽位的两个 vCPU，按各自 cpuid 设置栈顶后每 CPU 区域（scratch 与栈）互不重叠
+pub proof fn lemma_slot_stacks_disjoint(array: PerCpuArray, a: ArchCpu, b: ArchCpu, i: int, j: int)
+    requires
+        array.is_valid(),
+        0 <= i < array.num_cpus,
+        0 <= j < array.num_cpus,
+        i != j,
+        a.cpuid == array.mailboxes@[i].cpuid,
+        b.cpuid == array.mailboxes@[j].cpuid,
+        a.core_end == b.core_end,
+        a.host_stack_top == a.core_end + (a.cpuid + 1) * PER_CPU_SIZE,
+        b.host_stack_top == b.core_end + (b.cpuid + 1) * PER_CPU_SIZE,
+    ensures
+        a.host_stack_top <= b.per_cpu_base() || b.host_stack_top <= a.per_cpu_base(),
+{
+    lemma_scratch_disjoint_from_stack(a);
+    lemma_scratch_disjoint_from_stack(b);
+    if i < j {
+        assert((i + 1) * PER_CPU_SIZE <= j * PER_CPU_SIZE) by (nonlinear_arith)
+            requires i < j;
+    } else {
+        assert((j + 1) * PER_CPU_SIZE <= i * PER_CPU_SIZE) by (nonlinear_arith)
+            requires j < i;
+    }
+}
+
+} // verus!